@@ -5,8 +5,160 @@ page-id = Page { $num }
 git-description = Git commit {$hash} on {$date}
 
 today = Today
+year = Year
 month = Month
 week = Week
+week-number = Week { $number }
+work-week = Work week
+three-days = 3 days
+two-weeks = 2 weeks
 day = Day
+agenda = Agenda
+
+monday = Monday
+tuesday = Tuesday
+wednesday = Wednesday
+thursday = Thursday
+friday = Friday
+saturday = Saturday
+sunday = Sunday
+monday-short = Mon
+tuesday-short = Tue
+wednesday-short = Wed
+thursday-short = Thu
+friday-short = Fri
+saturday-short = Sat
+sunday-short = Sun
+
+january = January
+february = February
+march = March
+april = April
+may = May
+june = June
+july = July
+august = August
+september = September
+october = October
+november = November
+december = December
 
 crate-event = Crate Event
+
+new-event = New Event
+edit-event = Edit Event
+event-title = Title
+calendar = Calendar
+all-day = All day
+starts = Starts
+ends = Ends
+location = Location
+notes = Notes
+save = Save
+delete = Delete
+no-editable-calendars = There are no calendars you can add events to
+end-before-start = The event must end after it starts
+repeating-event = Repeating event
+this-event = This event
+this-and-following-events = This and following events
+all-events = All events
+more-events = +{ $count } more
+no-events = No events
+
+add-caldav-account = Add CalDAV Account
+caldav-account-description = Connect to a CalDAV server such as Nextcloud or Radicale. The password is stored in the app settings, so use an app password if your server offers them.
+server-url = Server URL
+username = Username
+password = Password
+add = Add
+invalid-url = Enter a full URL, such as https://cloud.example.com/remote.php/dav
+
+subscribe-to-calendar = Subscribe to Calendar
+subscribe-description = Show a published calendar, such as holidays or an on-call rotation, from its webcal or https address. Subscribed calendars are read-only and refreshed along with your other calendars.
+feed-url = Calendar address
+subscribe = Subscribe
+invalid-feed-url = Enter a webcal, https or http address of an .ics file
+
+local-calendars = On This Computer
+personal = Personal
+
+settings = Settings
+none = None
+calendar-settings = Calendar
+first-weekday = First day of the week
+region-default = Region default
+show-week-numbers = Show week numbers
+default-view = Default view
+twelve-hour-clock = 12-hour time
+am = AM
+pm = PM
+working-hours-start = Working hours start
+working-hours-end = Working hours end
+new-events = New events
+event-duration = Duration
+reminder = Reminder
+default-calendar = Default calendar
+at-start = At the start
+before = { $duration } before
+calendars = Calendars
+sync = Sync
+refresh-interval = Refresh calendars every
+minutes = { $count ->
+    [one] 1 minute
+   *[other] { $count } minutes
+}
+hours = { $count ->
+    [one] 1 hour
+   *[other] { $count } hours
+}
+days = { $count ->
+    [one] 1 day
+   *[other] { $count } days
+}
+time-zones = Time zones
+second-time-zone = Second time zone
+third-time-zone = Third time zone
+time-zones-description = Hours in these zones are shown next to the week and day views.
+
+changes-pending = { $count ->
+    [one] 1 change waiting to sync
+   *[other] { $count } changes waiting to sync
+}
+changes-failed = { $count ->
+    [one] Couldn't sync changes to { $events }
+   *[other] Couldn't sync changes to { $count } events: { $events }
+}
+
+import-file = Import…
+import-events = Import Events
+import = Import
+import-summary = { $count ->
+    [one] 1 event will be added
+   *[other] { $count } events will be added
+}
+import-skipped = { $count ->
+    [one] 1 item will be skipped
+   *[other] { $count } items will be skipped
+}
+import-failed = { $count ->
+    [one] 1 event couldn't be imported
+   *[other] { $count } events couldn't be imported
+}
+import-unreadable = This file couldn't be read as an iCalendar file
+change-color = Change color…
+calendar-color = Calendar color
+color-hex = Color, such as #3584e4
+apply = Apply
+reset-color = Use the original color
+invalid-color = Enter a color as # followed by six hexadecimal digits
+export-calendar = Export…
+export-visible-range = Export visible range…
+export-events = Export events
+untitled = Untitled
+skip-not-an-event = { $component } isn't an event
+skip-missing-uid = It has no unique identifier
+skip-missing-start = It has no start time
+skip-invalid-value = Its { $property } can't be read
+skip-ends-before-start = It ends before it starts
+skip-cancelled = It was cancelled
+skip-unsupported-recurrence = The chosen calendar can't store how it repeats
@@ -5,8 +5,165 @@ page-id = Strona { $num }
 git-description = Git commit {$hash} z {$date}
 
 today = Dzisiaj
+year = Rok
 month = Miesiąc
 week = Tydzień
+week-number = Tydzień { $number }
+work-week = Tydzień roboczy
+three-days = 3 dni
+two-weeks = 2 tygodnie
 day = Dzień
+agenda = Agenda
+
+monday = Poniedziałek
+tuesday = Wtorek
+wednesday = Środa
+thursday = Czwartek
+friday = Piątek
+saturday = Sobota
+sunday = Niedziela
+monday-short = Pon
+tuesday-short = Wt
+wednesday-short = Śr
+thursday-short = Czw
+friday-short = Pt
+saturday-short = Sob
+sunday-short = Niedz
+
+january = Styczeń
+february = Luty
+march = Marzec
+april = Kwiecień
+may = Maj
+june = Czerwiec
+july = Lipiec
+august = Sierpień
+september = Wrzesień
+october = Październik
+november = Listopad
+december = Grudzień
 
 crate-event = Stwórz Wydarzenie
+
+new-event = Nowe wydarzenie
+edit-event = Edytuj wydarzenie
+event-title = Tytuł
+calendar = Kalendarz
+all-day = Cały dzień
+starts = Początek
+ends = Koniec
+location = Miejsce
+notes = Notatki
+save = Zapisz
+delete = Usuń
+no-editable-calendars = Brak kalendarzy, do których można dodać wydarzenia
+end-before-start = Wydarzenie musi kończyć się po rozpoczęciu
+repeating-event = Wydarzenie cykliczne
+this-event = To wydarzenie
+this-and-following-events = To i następne wydarzenia
+all-events = Wszystkie wydarzenia
+more-events = +{ $count } więcej
+no-events = Brak wydarzeń
+
+add-caldav-account = Dodaj konto CalDAV
+caldav-account-description = Połącz się z serwerem CalDAV, takim jak Nextcloud lub Radicale. Hasło jest przechowywane w ustawieniach aplikacji, więc użyj hasła aplikacji, jeśli serwer je obsługuje.
+server-url = Adres serwera
+username = Nazwa użytkownika
+password = Hasło
+add = Dodaj
+invalid-url = Wpisz pełny adres, na przykład https://cloud.example.com/remote.php/dav
+
+subscribe-to-calendar = Subskrybuj kalendarz
+subscribe-description = Wyświetl opublikowany kalendarz, na przykład świąt lub dyżurów, na podstawie jego adresu webcal lub https. Subskrybowane kalendarze są tylko do odczytu i odświeżane razem z pozostałymi kalendarzami.
+feed-url = Adres kalendarza
+subscribe = Subskrybuj
+invalid-feed-url = Wpisz adres webcal, https lub http pliku .ics
+
+local-calendars = Na tym komputerze
+personal = Osobisty
+
+settings = Ustawienia
+none = Brak
+calendar-settings = Kalendarz
+first-weekday = Pierwszy dzień tygodnia
+region-default = Domyślny dla regionu
+show-week-numbers = Pokazuj numery tygodni
+default-view = Widok domyślny
+twelve-hour-clock = Zegar 12-godzinny
+am = AM
+pm = PM
+working-hours-start = Początek godzin pracy
+working-hours-end = Koniec godzin pracy
+new-events = Nowe wydarzenia
+event-duration = Czas trwania
+reminder = Przypomnienie
+default-calendar = Domyślny kalendarz
+at-start = W chwili rozpoczęcia
+before = { $duration } przed
+calendars = Kalendarze
+sync = Synchronizacja
+refresh-interval = Odświeżaj kalendarze co
+minutes = { $count ->
+    [one] 1 minuta
+    [few] { $count } minuty
+   *[other] { $count } minut
+}
+hours = { $count ->
+    [one] 1 godzina
+    [few] { $count } godziny
+   *[other] { $count } godzin
+}
+days = { $count ->
+    [one] 1 dzień
+   *[other] { $count } dni
+}
+time-zones = Strefy czasowe
+second-time-zone = Druga strefa czasowa
+third-time-zone = Trzecia strefa czasowa
+time-zones-description = Godziny w tych strefach są wyświetlane obok widoku tygodnia i dnia.
+
+changes-pending = { $count ->
+    [one] 1 zmiana czeka na synchronizację
+    [few] { $count } zmiany czekają na synchronizację
+   *[other] { $count } zmian czeka na synchronizację
+}
+changes-failed = { $count ->
+    [one] Nie udało się zsynchronizować zmian w { $events }
+   *[other] Nie udało się zsynchronizować zmian w { $count } wydarzeniach: { $events }
+}
+
+import-file = Importuj…
+import-events = Importuj wydarzenia
+import = Importuj
+import-summary = { $count ->
+    [one] Zostanie dodane 1 wydarzenie
+    [few] Zostaną dodane { $count } wydarzenia
+   *[other] Zostanie dodanych { $count } wydarzeń
+}
+import-skipped = { $count ->
+    [one] 1 element zostanie pominięty
+    [few] { $count } elementy zostaną pominięte
+   *[other] { $count } elementów zostanie pominiętych
+}
+import-failed = { $count ->
+    [one] Nie udało się zaimportować 1 wydarzenia
+   *[other] Nie udało się zaimportować { $count } wydarzeń
+}
+import-unreadable = Tego pliku nie da się odczytać jako pliku iCalendar
+change-color = Zmień kolor…
+calendar-color = Kolor kalendarza
+color-hex = Kolor, na przykład #3584e4
+apply = Zastosuj
+reset-color = Użyj pierwotnego koloru
+invalid-color = Wpisz kolor jako # i sześć cyfr szesnastkowych
+export-calendar = Eksportuj…
+export-visible-range = Eksportuj widoczny zakres…
+export-events = Eksportuj wydarzenia
+untitled = Bez tytułu
+skip-not-an-event = { $component } nie jest wydarzeniem
+skip-missing-uid = Brak unikalnego identyfikatora
+skip-missing-start = Brak czasu rozpoczęcia
+skip-invalid-value = Nie da się odczytać pola { $property }
+skip-ends-before-start = Kończy się przed rozpoczęciem
+skip-cancelled = Zostało odwołane
+skip-unsupported-recurrence = Wybrany kalendarz nie obsługuje takiego powtarzania
@@ -1,16 +1,27 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
-use crate::components::LocalCalendar;
-use crate::config::Config;
+use crate::cache;
+use crate::components::{
+    caldav, color, editor, import, subscription, weekdays, CalDavForm, CalendarEvent, ColorForm,
+    DayRange, EventEditor, ImportPreview, LocalCalendar, SubscriptionForm, AGENDA_ID,
+};
+use crate::config::{calendar_key, ClockFormat, Config};
 use crate::fl;
-use crate::models::Calendar;
-use crate::services::CalendarServiceFactory;
+use crate::i18n;
+use crate::ical::{self, ParsedEvents};
+use crate::keyring;
+use crate::models::{zone, CalDavAccount, Calendar, Event, Feed, Provider, Source};
+use crate::outbox::{self, Replayed, RETRY_INTERVAL};
+use crate::services::{CalDavCalendarService, CalendarService, CalendarServiceFactory};
+use crate::sync;
 use crate::Result;
 use accounts::models::{Account, Service};
 use accounts::AccountsClient;
 use cosmic::app::context_drawer;
 use cosmic::cosmic_config::{self, CosmicConfigEntry};
+use cosmic::dialog::file_chooser;
 use cosmic::iced::alignment::{Horizontal, Vertical};
+use cosmic::iced::widget::scrollable::{self, AbsoluteOffset};
 use cosmic::iced::{stream, Alignment, Length, Subscription};
 use cosmic::prelude::*;
 use cosmic::theme::spacing;
@@ -18,9 +29,12 @@ use cosmic::widget::segmented_button::SingleSelect;
 use cosmic::widget::{self, menu, nav_bar};
 use cosmic::{cosmic_theme, theme};
 use futures_util::{SinkExt, StreamExt};
-use std::collections::{BTreeMap, HashMap, VecDeque};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::ops::Index;
-use time::OffsetDateTime;
+use std::time::Duration;
+use time::{Date, OffsetDateTime, Weekday};
+use time_tz::Tz;
 
 mod flags;
 pub use flags::*;
@@ -30,6 +44,39 @@ pub use settings::*;
 const REPOSITORY: &str = env!("CARGO_PKG_REPOSITORY");
 const APP_ICON: &[u8] = include_bytes!("../resources/icons/hicolor/scalable/apps/icon.svg");
 
+/// How many time zones can be shown next to the display zone in the week and day views.
+const MAX_SECONDARY_ZONES: usize = 2;
+
+/// The tabs that can be chosen to show when the app starts, in the order of the tab bar.
+const VIEWS: [Tab; 8] = [
+    Tab::Year,
+    Tab::Month,
+    Tab::Week,
+    Tab::Days(DayRange::WorkWeek),
+    Tab::Days(DayRange::ThreeDays),
+    Tab::Days(DayRange::TwoWeeks),
+    Tab::Day,
+    Tab::Agenda,
+];
+
+/// The lengths offered for new events, in minutes.
+const EVENT_DURATIONS: [u32; 6] = [15, 30, 45, 60, 90, 120];
+
+/// The reminders offered for new events, in minutes before their start.
+const REMINDERS: [Option<i64>; 8] = [
+    None,
+    Some(0),
+    Some(5),
+    Some(10),
+    Some(15),
+    Some(30),
+    Some(60),
+    Some(24 * 60),
+];
+
+/// The intervals calendars can be polled for changes at, in minutes.
+const REFRESH_INTERVALS: [u32; 5] = [5, 15, 30, 60, 240];
+
 /// The application model stores app-specific state used to describe its interface and
 /// drive its logic.
 pub struct AppModel {
@@ -45,11 +92,47 @@ pub struct AppModel {
     key_binds: HashMap<menu::KeyBind, MenuAction>,
     // Configuration data that persists between application runs.
     config: Config,
+    /// Handle used to write changes to the configuration.
+    config_handler: Option<cosmic_config::Config>,
     // Calendar data that persists between application runs.
     calendar: LocalCalendar,
     client: Option<AccountsClient>,
     accounts: VecDeque<Account>,
-    calendars: BTreeMap<Account, Vec<Calendar>>,
+    calendars: BTreeMap<Source, Vec<Calendar>>,
+    /// Events of each calendar, keyed by [`calendar_key`].
+    events: BTreeMap<String, Vec<Event>>,
+    /// The range the events were last requested for.
+    events_range: Option<(OffsetDateTime, OffsetDateTime)>,
+    /// The event editor shown in the context drawer.
+    editor: Option<EventEditor>,
+    /// The form for adding a CalDAV server shown in the context drawer.
+    caldav_form: Option<CalDavForm>,
+    /// The form for subscribing to an `.ics` feed shown in the context drawer.
+    subscription_form: Option<SubscriptionForm>,
+    /// The form for choosing the color of a calendar shown in the context drawer.
+    color_form: Option<ColorForm>,
+    /// The preview of an `.ics` file being imported shown in the context drawer.
+    import: Option<ImportPreview>,
+    /// How many changes are queued until their provider can be reached.
+    pending_changes: usize,
+    /// Titles of the events whose queued changes were refused by their provider.
+    failed_changes: Vec<String>,
+    /// The choices for secondary time zones in the settings, "None" first.
+    zone_names: Vec<String>,
+    /// The choices for the first day of the week in the settings, the region default first.
+    weekday_names: Vec<String>,
+    /// Labels of the choices in the settings, in the order of [`VIEWS`], [`EVENT_DURATIONS`],
+    /// [`REMINDERS`] and [`REFRESH_INTERVALS`].
+    view_names: Vec<String>,
+    duration_names: Vec<String>,
+    reminder_names: Vec<String>,
+    refresh_names: Vec<String>,
+    /// Every hour of the day and the end of it, in the configured clock format.
+    hour_names: Vec<String>,
+    /// The scroll offset of the agenda, as last reported or scrolled to.
+    agenda_offset: f32,
+    /// The day kept at the top of the agenda while the days above it are loading.
+    agenda_anchor: Option<Date>,
 }
 
 /// Messages emitted by the application and its widgets.
@@ -59,6 +142,26 @@ pub enum Message {
     SubscriptionChannel,
     ToggleContextPage(ContextPage),
     UpdateConfig(Config),
+    /// Sets the secondary time zone in a slot to one of `zone_names`.
+    SetSecondaryZone(usize, usize),
+    /// Sets the first day of the week to one of `weekday_names`.
+    SetFirstWeekday(usize),
+    SetShowWeekNumbers(bool),
+    /// Sets the tab shown at start to one of [`VIEWS`].
+    SetDefaultView(usize),
+    SetTwelveHourClock(bool),
+    /// Sets the hours from the first up to the second as working hours.
+    SetWorkingHours(u8, u8),
+    /// Sets the length of new events to one of [`EVENT_DURATIONS`].
+    SetEventDuration(usize),
+    /// Sets the reminder of new events to one of [`REMINDERS`].
+    SetReminder(usize),
+    /// Sets the calendar new events are added to, by its index among the editable ones.
+    SetDefaultCalendar(usize),
+    /// Shows or hides the events of the calendar with a [`calendar_key`].
+    SetCalendarVisible(String, bool),
+    /// Sets how often calendars are refreshed to one of [`REFRESH_INTERVALS`].
+    SetRefreshInterval(usize),
     TabSelected(widget::segmented_button::Entity),
     LaunchUrl(String),
     NavigateNextDay,
@@ -69,7 +172,20 @@ pub enum Message {
     NavigatePreviousYear,
     NavigateToday,
     AddEvent(OffsetDateTime),
+    /// Opens the event with an id in the calendar with a [`calendar_key`].
+    OpenEvent(String, String),
+    Editor(editor::Message),
+    EventSaved(Source, Event),
+    EventDeleted(Source, Event),
+    /// Occurrences of a recurring series changed, so the events shown are reloaded.
+    SeriesChanged,
+    /// Saving or deleting the event in the editor failed, for the reason given.
+    EditorFailed(String),
     SelectDate(OffsetDateTime),
+    /// Opens the day view on the date, selecting it as [`Message::SelectDate`] does.
+    OpenDay(OffsetDateTime),
+    /// Opens the week view on the week of the date.
+    OpenWeek(OffsetDateTime),
     SelectMonth(usize),
     SelectYear(usize),
     SelectDay(usize),
@@ -79,7 +195,37 @@ pub enum Message {
     ReloadAccounts,
     SetAccounts(VecDeque<Account>),
     LoadCalendars,
-    AddCalendars((Account, Vec<Calendar>)),
+    CachedCalendars((Source, Vec<Calendar>, Vec<(String, Vec<Event>)>)),
+    AddCalendars((Source, Vec<Calendar>)),
+    AddCalDavAccount,
+    CalDav(caldav::Message),
+    CalDavAccountAdded((CalDavAccount, Vec<Calendar>)),
+    CalDavAccountFailed(String),
+    Subscribe,
+    Subscription(subscription::Message),
+    Subscribed((Feed, Vec<Calendar>)),
+    SubscribeFailed(String),
+    /// Fetches the events of every calendar again.
+    Refresh,
+    ImportFile,
+    ImportLoaded((String, Option<ParsedEvents>)),
+    Import(import::Message),
+    Imported((Source, Vec<Event>, usize)),
+    ReplayOutbox,
+    Replayed(Replayed),
+    DismissFailedChanges,
+    ExportCalendar(nav_bar::Id),
+    /// Selects an item of the nav bar.
+    NavSelect(nav_bar::Id),
+    /// Opens the form for choosing the color of the calendar in the nav bar.
+    EditCalendarColor(nav_bar::Id),
+    CalendarColor(color::Message),
+    ExportVisibleRange,
+    LoadEvents,
+    /// Events of the calendar with a [`calendar_key`] within a range.
+    AddEvents((String, (OffsetDateTime, OffsetDateTime), Vec<Event>)),
+    /// The agenda was scrolled, to an absolute and a relative vertical offset.
+    AgendaScrolled(f32, f32),
 }
 
 /// Create a COSMIC application from the app model
@@ -114,6 +260,11 @@ impl cosmic::Application for AppModel {
 
         // Create a tab bar with three page items.
         let tabs = widget::segmented_button::Model::builder()
+            .insert(|b| {
+                b.text(fl!("year"))
+                    .icon(widget::icon::from_name("view-grid-symbolic"))
+                    .data(Tab::Year)
+            })
             .insert(|b| {
                 b.text(fl!("month"))
                     .icon(widget::icon::from_name("office-calendar-symbolic"))
@@ -125,13 +276,35 @@ impl cosmic::Application for AppModel {
                     .icon(widget::icon::from_name("x-office-spreadsheet-symbolic"))
                     .data(Tab::Week)
             })
+            .insert(|b| {
+                b.text(fl!("work-week"))
+                    .icon(widget::icon::from_name("x-office-spreadsheet-symbolic"))
+                    .data(Tab::Days(DayRange::WorkWeek))
+            })
+            .insert(|b| {
+                b.text(fl!("three-days"))
+                    .icon(widget::icon::from_name("x-office-spreadsheet-symbolic"))
+                    .data(Tab::Days(DayRange::ThreeDays))
+            })
+            .insert(|b| {
+                b.text(fl!("two-weeks"))
+                    .icon(widget::icon::from_name("x-office-spreadsheet-symbolic"))
+                    .data(Tab::Days(DayRange::TwoWeeks))
+            })
             .insert(|b| {
                 b.text(fl!("day"))
                     .icon(widget::icon::from_name("calendar-go-today-symbolic"))
                     .data(Tab::Day)
             })
+            .insert(|b| {
+                b.text(fl!("agenda"))
+                    .icon(widget::icon::from_name("view-list-symbolic"))
+                    .data(Tab::Agenda)
+            })
             .build();
 
+        let config_handler = cosmic_config::Config::new(Self::APP_ID, Config::VERSION).ok();
+
         // Construct the app model with the runtime's core.
         let mut app = AppModel {
             core,
@@ -140,8 +313,9 @@ impl cosmic::Application for AppModel {
             tabs,
             key_binds: HashMap::new(),
             // Optional configuration file for an application.
-            config: cosmic_config::Config::new(Self::APP_ID, Config::VERSION)
-                .map(|context| match Config::get_entry(&context) {
+            config: config_handler
+                .as_ref()
+                .map(|context| match Config::get_entry(context) {
                     Ok(config) => config,
                     Err((_errors, config)) => {
                         // for why in errors {
@@ -152,13 +326,54 @@ impl cosmic::Application for AppModel {
                     }
                 })
                 .unwrap_or_default(),
+            config_handler,
             calendar: LocalCalendar::default(),
             client: None,
             accounts: VecDeque::new(),
             calendars: BTreeMap::new(),
+            events: BTreeMap::new(),
+            events_range: None,
+            editor: None,
+            caldav_form: None,
+            subscription_form: None,
+            color_form: None,
+            pending_changes: 0,
+            failed_changes: vec![],
+            import: None,
+            zone_names: std::iter::once(fl!("none"))
+                .chain(zone::names().into_iter().map(str::to_string))
+                .collect(),
+            weekday_names: std::iter::once(fl!("region-default"))
+                .chain(weekdays(Weekday::Monday).map(i18n::weekday))
+                .collect(),
+            view_names: VIEWS.iter().map(|tab| tab.name()).collect(),
+            duration_names: EVENT_DURATIONS
+                .iter()
+                .map(|minutes| duration_label(*minutes))
+                .collect(),
+            reminder_names: REMINDERS
+                .iter()
+                .map(|reminder| match reminder {
+                    None => fl!("none"),
+                    Some(0) => fl!("at-start"),
+                    Some(minutes) => fl!("before", duration = duration_label(*minutes as u32)),
+                })
+                .collect(),
+            refresh_names: REFRESH_INTERVALS
+                .iter()
+                .map(|minutes| duration_label(*minutes))
+                .collect(),
+            hour_names: vec![],
+            agenda_offset: 0.0,
+            agenda_anchor: None,
         };
 
         app.core.nav_bar_set_toggled(false);
+        app.apply_config();
+        app.activate_tab(app.config.default_view);
+        if app.config.default_view == Tab::Agenda {
+            app.agenda_anchor = Some(app.calendar.reset_agenda());
+        }
 
         // Create a startup command that sets the window title.
         let command = app.update_title();
@@ -175,17 +390,54 @@ impl cosmic::Application for AppModel {
             menu::root(fl!("view")).apply(Element::from),
             menu::items(
                 &self.key_binds,
-                vec![menu::Item::Button(fl!("about"), None, MenuAction::About)],
+                vec![
+                    menu::Item::Button(fl!("import-file"), None, MenuAction::Import),
+                    menu::Item::Button(
+                        fl!("export-visible-range"),
+                        None,
+                        MenuAction::ExportVisibleRange,
+                    ),
+                    menu::Item::Button(
+                        fl!("add-caldav-account"),
+                        None,
+                        MenuAction::AddCalDavAccount,
+                    ),
+                    menu::Item::Button(fl!("subscribe-to-calendar"), None, MenuAction::Subscribe),
+                    menu::Item::Button(fl!("settings"), None, MenuAction::Settings),
+                    menu::Item::Button(fl!("about"), None, MenuAction::About),
+                ],
             ),
         )]);
 
         vec![menu_bar.into()]
     }
 
+    /// Elements to pack at the end of the header bar.
+    fn header_end<'a>(&'a self) -> Vec<Element<'a, Self::Message>> {
+        let mut elements = vec![];
+        if !self.failed_changes.is_empty() {
+            elements.push(
+                widget::button::text(fl!(
+                    "changes-failed",
+                    count = self.failed_changes.len(),
+                    events = self.failed_changes.join(", ")
+                ))
+                .on_press(Message::DismissFailedChanges)
+                .into(),
+            );
+        }
+        if self.pending_changes > 0 {
+            elements.push(
+                widget::text::caption(fl!("changes-pending", count = self.pending_changes)).into(),
+            );
+        }
+        elements
+    }
+
     fn header_center<'a>(&'a self) -> Vec<Element<'a, Self::Message>> {
         vec![widget::text(format!(
             "{} {}",
-            self.calendar.selected_date.month(),
+            i18n::month(self.calendar.selected_date.month()),
             self.calendar.selected_date.year()
         ))
         .width(Length::Fill)
@@ -229,7 +481,7 @@ impl cosmic::Application for AppModel {
                 self.calendar
                     .months()
                     .iter()
-                    .map(|m| m.to_string())
+                    .map(|m| i18n::month(*m))
                     .collect::<Vec<String>>(),
                 self.calendar
                     .months()
@@ -270,7 +522,7 @@ impl cosmic::Application for AppModel {
             .push(
                 widget::button::icon(widget::icon::from_name("list-add-symbolic"))
                     .tooltip(fl!("crate-event"))
-                    .on_press(Message::AddEvent(OffsetDateTime::now_local().unwrap())),
+                    .on_press(Message::AddEvent(zone::now(self.calendar.zone))),
             )
             .align_y(Vertical::Center)
             .spacing(spacing().space_xxs);
@@ -298,6 +550,72 @@ impl cosmic::Application for AppModel {
         Some(&self.nav)
     }
 
+    /// The nav bar, drawn from the model with a checkbox next to each calendar which
+    /// shows or hides its events without selecting it.
+    fn nav_bar(&self) -> Option<Element<'_, cosmic::Action<Self::Message>>> {
+        if !self.core.nav_bar_active() {
+            return None;
+        }
+
+        let active = self.nav.active();
+        let column = self.nav.iter().fold(
+            widget::column().spacing(spacing().space_xxxs),
+            |column, id| {
+                let name = self.nav.text(id).unwrap_or_default().to_string();
+                let Some((source, calendar)) = self.nav.data::<(Source, Calendar)>(id) else {
+                    return column.push(
+                        widget::text::heading(name)
+                            .apply(widget::container)
+                            .padding([spacing().space_xs, spacing().space_s]),
+                    );
+                };
+                let key = calendar_key(source, &calendar.id);
+                let visible = !self.config.hidden_calendars.contains(&key);
+                let row = widget::row()
+                    .push(widget::checkbox("", visible).on_toggle(move |visible| {
+                        cosmic::action::app(Message::SetCalendarVisible(key.clone(), visible))
+                    }))
+                    .push(
+                        widget::button::custom(widget::text::body(name))
+                            .class(cosmic::theme::Button::Text)
+                            .selected(id == active)
+                            .width(Length::Fill)
+                            .on_press(cosmic::action::app(Message::NavSelect(id))),
+                    )
+                    .align_y(Alignment::Center)
+                    .spacing(spacing().space_xxs)
+                    .padding([0, 0, 0, spacing().space_s]);
+                column.push(widget::context_menu(row, self.nav_context_menu(id)))
+            },
+        );
+
+        Some(
+            widget::scrollable(column)
+                .apply(widget::container)
+                .padding(spacing().space_xxs)
+                .width(Length::Shrink)
+                .max_width(280)
+                .height(Length::Fill)
+                .class(cosmic::theme::Container::Card)
+                .into(),
+        )
+    }
+
+    /// The context menu of calendars in the nav bar.
+    fn nav_context_menu(
+        &self,
+        id: nav_bar::Id,
+    ) -> Option<Vec<menu::Tree<cosmic::Action<Self::Message>>>> {
+        self.nav.data::<(Source, Calendar)>(id)?;
+        Some(menu::items(
+            &HashMap::new(),
+            vec![
+                menu::Item::Button(fl!("change-color"), None, NavMenuAction::Color(id)),
+                menu::Item::Button(fl!("export-calendar"), None, NavMenuAction::Export(id)),
+            ],
+        ))
+    }
+
     /// Display a context drawer if the context page is requested.
     fn context_drawer<'a>(&'a self) -> Option<context_drawer::ContextDrawer<'a, Self::Message>> {
         if !self.core.window.show_context {
@@ -310,6 +628,55 @@ impl cosmic::Application for AppModel {
                 Message::ToggleContextPage(ContextPage::About),
             )
             .title(fl!("about")),
+            ContextPage::Settings => context_drawer::context_drawer(
+                self.settings(),
+                Message::ToggleContextPage(ContextPage::Settings),
+            )
+            .title(fl!("settings")),
+            ContextPage::EventEditor => {
+                let editor = self.editor.as_ref()?;
+                context_drawer::context_drawer(
+                    editor.view().map(Message::Editor),
+                    Message::ToggleContextPage(ContextPage::EventEditor),
+                )
+                .title(if editor.is_new() {
+                    fl!("new-event")
+                } else {
+                    fl!("edit-event")
+                })
+            }
+            ContextPage::CalDavAccount => {
+                let form = self.caldav_form.as_ref()?;
+                context_drawer::context_drawer(
+                    form.view().map(Message::CalDav),
+                    Message::ToggleContextPage(ContextPage::CalDavAccount),
+                )
+                .title(fl!("add-caldav-account"))
+            }
+            ContextPage::Subscription => {
+                let form = self.subscription_form.as_ref()?;
+                context_drawer::context_drawer(
+                    form.view().map(Message::Subscription),
+                    Message::ToggleContextPage(ContextPage::Subscription),
+                )
+                .title(fl!("subscribe-to-calendar"))
+            }
+            ContextPage::CalendarColor => {
+                let form = self.color_form.as_ref()?;
+                context_drawer::context_drawer(
+                    form.view().map(Message::CalendarColor),
+                    Message::ToggleContextPage(ContextPage::CalendarColor),
+                )
+                .title(fl!("calendar-color"))
+            }
+            ContextPage::Import => {
+                let preview = self.import.as_ref()?;
+                context_drawer::context_drawer(
+                    preview.view().map(Message::Import),
+                    Message::ToggleContextPage(ContextPage::Import),
+                )
+                .title(fl!("import-events"))
+            }
         })
     }
 
@@ -324,11 +691,19 @@ impl cosmic::Application for AppModel {
             .button_alignment(cosmic::iced::Alignment::Center)
             .on_activate(Message::TabSelected);
 
+        let events = self.calendar_events();
+        let secondary_zones = self.secondary_zones();
         let active_tab = match self.tabs.active_data::<Tab>() {
             Some(active_tab) => match active_tab {
-                Tab::Month => self.calendar.month_view().into(),
-                Tab::Week => self.calendar.week_view().into(),
-                Tab::Day => self.calendar.day_view().into(),
+                Tab::Year => self.calendar.year_view(&events).into(),
+                Tab::Month => self.calendar.month_view(&events).into(),
+                Tab::Week => self.calendar.week_view(&events, &secondary_zones).into(),
+                Tab::Days(range) => self
+                    .calendar
+                    .range_view(*range, &events, &secondary_zones)
+                    .into(),
+                Tab::Day => self.calendar.day_view(&events, &secondary_zones).into(),
+                Tab::Agenda => self.calendar.agenda_view(&events).into(),
             },
             None => widget::text::title1("Welcome")
                 .apply(widget::container)
@@ -354,13 +729,24 @@ impl cosmic::Application for AppModel {
     fn subscription(&self) -> Subscription<Self::Message> {
         struct MySubscription;
 
+        // Calendars are polled for changes, and queued changes retried, whether
+        // or not accounts are available.
+        let timers = Subscription::batch(vec![
+            cosmic::iced::time::every(Duration::from_secs(
+                u64::from(self.config.refresh_interval.max(1)) * 60,
+            ))
+            .map(|_| Message::Refresh),
+            cosmic::iced::time::every(RETRY_INTERVAL).map(|_| Message::ReplayOutbox),
+        ]);
+
         let Some(client) = self.client.clone() else {
-            return Subscription::none();
+            return timers;
         };
         let account_changed_client = client.clone();
         let account_removed_client = client.clone();
 
         Subscription::batch(vec![
+            timers,
             // Create a subscription which emits updates through a channel.
             Subscription::run_with_id(
                 std::any::TypeId::of::<MySubscription>(),
@@ -429,9 +815,14 @@ impl cosmic::Application for AppModel {
     /// on the application's async runtime.
     fn update(&mut self, message: Self::Message) -> Task<cosmic::Action<Self::Message>> {
         let mut tasks = vec![];
+        let selected_date = self.calendar.selected_date.date();
         match message {
             Message::TabSelected(tab) => {
                 self.tabs.activate(tab);
+                if self.tabs.data::<Tab>(tab) == Some(&Tab::Agenda) {
+                    self.agenda_anchor = Some(self.calendar.reset_agenda());
+                    tasks.push(self.scroll_agenda());
+                }
             }
             Message::OpenRepositoryUrl => {
                 _ = open::that_detached(REPOSITORY);
@@ -451,6 +842,126 @@ impl cosmic::Application for AppModel {
             }
             Message::UpdateConfig(config) => {
                 self.config = config;
+                self.apply_config();
+                self.rebuild_nav();
+            }
+            Message::SetFirstWeekday(index) => {
+                let first_weekday = index
+                    .checked_sub(1)
+                    .map(|day| Weekday::Monday.nth_next(day as u8));
+                if let Some(handler) = &self.config_handler {
+                    if let Err(err) = self.config.set_first_weekday(handler, first_weekday) {
+                        tracing::error!("Failed to save the first day of the week: {}", err);
+                    }
+                }
+                self.apply_config();
+            }
+            Message::SetDefaultView(index) => {
+                if let (Some(tab), Some(handler)) = (VIEWS.get(index), &self.config_handler) {
+                    if let Err(err) = self.config.set_default_view(handler, *tab) {
+                        tracing::error!("Failed to save the default view: {}", err);
+                    }
+                }
+            }
+            Message::SetTwelveHourClock(twelve_hour) => {
+                let clock = if twelve_hour {
+                    ClockFormat::TwelveHour
+                } else {
+                    ClockFormat::TwentyFourHour
+                };
+                if let Some(handler) = &self.config_handler {
+                    if let Err(err) = self.config.set_clock(handler, clock) {
+                        tracing::error!("Failed to save the clock format: {}", err);
+                    }
+                }
+                self.apply_config();
+            }
+            Message::SetWorkingHours(start, end) => {
+                if start >= end || end > 24 {
+                    return Task::none();
+                }
+                if let Some(handler) = &self.config_handler {
+                    if let Err(err) = self.config.set_working_hours(handler, (start, end)) {
+                        tracing::error!("Failed to save working hours: {}", err);
+                    }
+                }
+                self.apply_config();
+            }
+            Message::SetEventDuration(index) => {
+                if let (Some(minutes), Some(handler)) =
+                    (EVENT_DURATIONS.get(index), &self.config_handler)
+                {
+                    if let Err(err) = self.config.set_event_duration(handler, *minutes) {
+                        tracing::error!("Failed to save the event duration: {}", err);
+                    }
+                }
+            }
+            Message::SetReminder(index) => {
+                if let (Some(reminder), Some(handler)) =
+                    (REMINDERS.get(index), &self.config_handler)
+                {
+                    if let Err(err) = self.config.set_reminder(handler, *reminder) {
+                        tracing::error!("Failed to save the default reminder: {}", err);
+                    }
+                }
+            }
+            Message::SetDefaultCalendar(index) => {
+                let key = self
+                    .editable_calendars()
+                    .get(index)
+                    .map(|(source, calendar)| calendar_key(source, &calendar.id));
+                if let (Some(key), Some(handler)) = (key, &self.config_handler) {
+                    if let Err(err) = self.config.set_default_calendar(handler, Some(key)) {
+                        tracing::error!("Failed to save the default calendar: {}", err);
+                    }
+                }
+            }
+            Message::SetCalendarVisible(key, visible) => {
+                let mut hidden = self.config.hidden_calendars.clone();
+                if visible {
+                    hidden.remove(&key);
+                } else {
+                    hidden.insert(key);
+                }
+                if let Some(handler) = &self.config_handler {
+                    if let Err(err) = self.config.set_hidden_calendars(handler, hidden) {
+                        tracing::error!("Failed to save hidden calendars: {}", err);
+                    }
+                }
+            }
+            Message::SetRefreshInterval(index) => {
+                if let (Some(minutes), Some(handler)) =
+                    (REFRESH_INTERVALS.get(index), &self.config_handler)
+                {
+                    if let Err(err) = self.config.set_refresh_interval(handler, *minutes) {
+                        tracing::error!("Failed to save the refresh interval: {}", err);
+                    }
+                }
+            }
+            Message::SetShowWeekNumbers(show) => {
+                if let Some(handler) = &self.config_handler {
+                    if let Err(err) = self.config.set_show_week_numbers(handler, show) {
+                        tracing::error!("Failed to save showing week numbers: {}", err);
+                    }
+                }
+                self.apply_config();
+            }
+            Message::SetSecondaryZone(slot, index) => {
+                if slot >= MAX_SECONDARY_ZONES {
+                    return Task::none();
+                }
+                let mut zones = self.config.secondary_zones.clone();
+                zones.resize(MAX_SECONDARY_ZONES, String::new());
+                zones[slot] = match index {
+                    0 => String::new(),
+                    index => self.zone_names.get(index).cloned().unwrap_or_default(),
+                };
+                zones.retain(|name| !name.is_empty());
+                if let Some(handler) = &self.config_handler {
+                    if let Err(err) = self.config.set_secondary_zones(handler, zones) {
+                        tracing::error!("Failed to save time zones: {}", err);
+                    }
+                }
             }
             Message::LaunchUrl(url) => match open::that_detached(&url) {
                 Ok(()) => {}
@@ -468,6 +979,7 @@ impl cosmic::Application for AppModel {
             }
             Message::ReloadAccounts => {
                 self.nav.clear();
+                self.calendars.clear();
                 tasks.push(cosmic::task::message(Message::LoadAccounts));
             }
             Message::LoadAccounts => {
@@ -482,10 +994,13 @@ impl cosmic::Application for AppModel {
                             Ok(accounts) => cosmic::action::app(Message::SetAccounts(accounts)),
                             Err(err) => {
                                 tracing::error!("Failed to load accounts: {}", err);
-                                cosmic::action::none()
+                                cosmic::action::app(Message::SetAccounts(VecDeque::new()))
                             }
                         },
                     ));
+                } else {
+                    // Without cosmic accounts, calendars configured in the app still load.
+                    tasks.push(cosmic::task::message(Message::SetAccounts(VecDeque::new())));
                 }
             }
             Message::SetAccounts(accounts) => {
@@ -493,17 +1008,60 @@ impl cosmic::Application for AppModel {
                 tasks.push(cosmic::task::message(Message::LoadCalendars));
             }
             Message::LoadCalendars => {
-                let accounts = self.accounts.clone();
-                for account in accounts {
+                // Local calendars are always loaded, so events can be added without any account.
+                let sources = std::iter::once(Source::Local)
+                    .chain(self.accounts.iter().cloned().map(Source::Account))
+                    .chain(
+                        self.config
+                            .caldav_accounts
+                            .iter()
+                            .cloned()
+                            .map(Source::CalDav),
+                    )
+                    .chain(
+                        self.config
+                            .subscriptions
+                            .iter()
+                            .cloned()
+                            .map(Source::Subscription),
+                    )
+                    .collect::<Vec<_>>();
+                for source in sources {
+                    // The cached copy paints right away, the network refresh replaces it after.
+                    let cached_source = source.clone();
+                    let range = self.events_range;
+                    tasks.push(Task::perform(
+                        cache::with(move |cache| {
+                            let calendars = cache.calendars(&cached_source)?;
+                            let mut events = vec![];
+                            if let Some((start, end)) = range {
+                                for calendar in &calendars {
+                                    let cached =
+                                        cache.events(&cached_source, &calendar.id, start, end)?;
+                                    events.push((calendar.id.clone(), cached));
+                                }
+                            }
+                            Ok((cached_source, calendars, events))
+                        }),
+                        |cached: Result<(Source, Vec<Calendar>, Vec<(String, Vec<Event>)>)>| {
+                            match cached {
+                                Ok(cached) => cosmic::action::app(Message::CachedCalendars(cached)),
+                                Err(err) => {
+                                    tracing::error!("Failed to read cached calendars: {}", err);
+                                    cosmic::action::none()
+                                }
+                            }
+                        },
+                    ));
                     tasks.push(cosmic::Task::perform(
                         async move {
-                            let mut service = CalendarServiceFactory::get_service(&account).await?;
+                            let mut service = CalendarServiceFactory::get_service(&source).await?;
                             let calendars = service.fetch_calendars().await?;
-                            Ok((account.clone(), calendars))
+                            Ok((source, calendars))
                         },
-                        |calendars: Result<(Account, Vec<Calendar>)>| match calendars {
-                            Ok((account, calendars)) => {
-                                cosmic::action::app(Message::AddCalendars((account, calendars)))
+                        |calendars: Result<(Source, Vec<Calendar>)>| match calendars {
+                            Ok((source, calendars)) => {
+                                cosmic::action::app(Message::AddCalendars((source, calendars)))
                             }
                             Err(err) => {
                                 tracing::error!("Failed to load calendars: {}", err);
@@ -513,25 +1071,574 @@ impl cosmic::Application for AppModel {
                     ));
                 }
             }
-            Message::AddCalendars((account, calendars)) => {
+            Message::CachedCalendars((source, calendars, events)) => {
+                // When the network answered first, its copy is already shown and newer.
+                if calendars.is_empty() || self.calendars.contains_key(&source) {
+                    return Task::none();
+                }
+                self.core.nav_bar_set_toggled(true);
+                for (calendar_id, events) in events {
+                    self.events
+                        .entry(calendar_key(&source, &calendar_id))
+                        .or_insert(events);
+                }
+                self.calendars.insert(source, calendars);
+                self.rebuild_nav();
+            }
+            Message::AddCalendars((source, calendars)) => {
                 self.core.nav_bar_set_toggled(true);
-                self.calendars.insert(account.clone(), calendars.clone());
-                self.nav.insert().text(account.username);
-                for calendar in calendars {
-                    self.nav
-                        .insert()
-                        .indent(1)
-                        .text(calendar.name.clone())
-                        .icon(widget::icon::from_name("office-calendar-symbolic"))
-                        .data(calendar);
+                if let Some(range) = self.events_range {
+                    tasks.extend(Self::fetch_events(&source, &calendars, range));
+                }
+                let (cached_source, cached_calendars) = (source.clone(), calendars.clone());
+                tasks.push(Task::perform(
+                    cache::with(move |cache| {
+                        cache.set_calendars(&cached_source, &cached_calendars)
+                    }),
+                    |cached: Result<()>| {
+                        if let Err(err) = cached {
+                            tracing::warn!("Failed to cache calendars: {}", err);
+                        }
+                        cosmic::action::none()
+                    },
+                ));
+                self.calendars.insert(source, calendars);
+                self.rebuild_nav();
+                // Reaching the source is a good time to send what was queued for it.
+                tasks.push(cosmic::task::message(Message::ReplayOutbox));
+            }
+            Message::AddCalDavAccount => {
+                self.caldav_form = Some(CalDavForm::default());
+                self.context_page = ContextPage::CalDavAccount;
+                self.core.window.show_context = true;
+            }
+            Message::CalDav(message) => {
+                let Some(form) = self.caldav_form.as_mut() else {
+                    return Task::none();
+                };
+                if let Some((account, password)) = form.update(message) {
+                    tasks.push(Task::perform(
+                        async move {
+                            let mut service =
+                                CalDavCalendarService::new(&account, password.clone())?;
+                            let calendars = service.fetch_calendars().await?;
+                            // Only a password the server accepted is kept.
+                            keyring::set_caldav_password(&account, &password).await?;
+                            Ok((account, calendars))
+                        },
+                        |result: Result<(CalDavAccount, Vec<Calendar>)>| match result {
+                            Ok(added) => cosmic::action::app(Message::CalDavAccountAdded(added)),
+                            Err(err) => {
+                                tracing::error!("Failed to connect to CalDAV server: {}", err);
+                                cosmic::action::app(Message::CalDavAccountFailed(err.to_string()))
+                            }
+                        },
+                    ));
+                }
+            }
+            Message::CalDavAccountAdded((account, calendars)) => {
+                let mut accounts = self.config.caldav_accounts.clone();
+                accounts.retain(|a| a.url != account.url || a.username != account.username);
+                accounts.push(account.clone());
+                if let Some(handler) = &self.config_handler {
+                    if let Err(err) = self.config.set_caldav_accounts(handler, accounts) {
+                        tracing::error!("Failed to save CalDAV account: {}", err);
+                    }
+                }
+                self.caldav_form = None;
+                self.core.window.show_context = false;
+                tasks.push(cosmic::task::message(Message::AddCalendars((
+                    Source::CalDav(account),
+                    calendars,
+                ))));
+            }
+            Message::CalDavAccountFailed(error) => {
+                if let Some(form) = self.caldav_form.as_mut() {
+                    form.fail(error);
+                }
+            }
+            Message::Subscribe => {
+                self.subscription_form = Some(SubscriptionForm::default());
+                self.context_page = ContextPage::Subscription;
+                self.core.window.show_context = true;
+            }
+            Message::Subscription(message) => {
+                let Some(form) = self.subscription_form.as_mut() else {
+                    return Task::none();
+                };
+                if let Some(feed) = form.update(message) {
+                    tasks.push(Task::perform(
+                        async move {
+                            let source = Source::Subscription(feed.clone());
+                            let mut service = CalendarServiceFactory::get_service(&source).await?;
+                            let calendars = service.fetch_calendars().await?;
+                            Ok((feed, calendars))
+                        },
+                        |result: Result<(Feed, Vec<Calendar>)>| match result {
+                            Ok(subscribed) => cosmic::action::app(Message::Subscribed(subscribed)),
+                            Err(err) => {
+                                tracing::error!("Failed to subscribe to calendar: {}", err);
+                                cosmic::action::app(Message::SubscribeFailed(err.to_string()))
+                            }
+                        },
+                    ));
+                }
+            }
+            Message::Subscribed((feed, calendars)) => {
+                if self.config.subscriptions.contains(&feed) {
+                    self.subscription_form = None;
+                    self.core.window.show_context = false;
+                    return Task::none();
+                }
+                let mut subscriptions = self.config.subscriptions.clone();
+                subscriptions.push(feed.clone());
+                if let Some(handler) = &self.config_handler {
+                    if let Err(err) = self.config.set_subscriptions(handler, subscriptions) {
+                        tracing::error!("Failed to save subscription: {}", err);
+                    }
+                }
+                self.subscription_form = None;
+                self.core.window.show_context = false;
+                tasks.push(cosmic::task::message(Message::AddCalendars((
+                    Source::Subscription(feed),
+                    calendars,
+                ))));
+            }
+            Message::SubscribeFailed(error) => {
+                if let Some(form) = self.subscription_form.as_mut() {
+                    form.fail(error);
+                }
+            }
+            Message::Refresh => {
+                if let Some(range) = self.events_range {
+                    for (source, calendars) in &self.calendars {
+                        tasks.extend(Self::fetch_events(source, calendars, range));
+                    }
+                }
+            }
+            Message::ImportFile => tasks.push(Task::perform(
+                async {
+                    let response = file_chooser::open::Dialog::new()
+                        .title(fl!("import-events"))
+                        .filter(file_chooser::FileFilter::new("iCalendar").glob("*.ics"))
+                        .open_file()
+                        .await
+                        .ok()?;
+                    let path = response.url().to_file_path().ok()?;
+                    let file_name = path.file_name()?.to_string_lossy().into_owned();
+                    // The calendar and provider are set once the user picks where to import.
+                    let parsed = match tokio::fs::read_to_string(&path).await {
+                        Ok(data) => ical::read_events(&data, "", Provider::Local)
+                            .inspect_err(|err| {
+                                tracing::error!("Failed to parse {file_name}: {err}")
+                            })
+                            .ok(),
+                        Err(err) => {
+                            tracing::error!("Failed to read {file_name}: {err}");
+                            None
+                        }
+                    };
+                    Some((file_name, parsed))
+                },
+                |loaded| match loaded {
+                    Some(loaded) => cosmic::action::app(Message::ImportLoaded(loaded)),
+                    None => cosmic::action::none(),
+                },
+            )),
+            Message::ImportLoaded((file_name, parsed)) => {
+                self.import = Some(match parsed {
+                    Some(parsed) => {
+                        ImportPreview::new(file_name, parsed, &self.editable_calendars())
+                    }
+                    None => ImportPreview::unreadable(file_name),
+                });
+                self.context_page = ContextPage::Import;
+                self.core.window.show_context = true;
+            }
+            Message::Import(message) => {
+                let Some(preview) = self.import.as_mut() else {
+                    return Task::none();
+                };
+                if let Some((source, calendar, events)) = preview.update(message) {
+                    let count = events.len();
+                    let imported_source = source.clone();
+                    tasks.push(Task::perform(
+                        async move {
+                            // Imported events are cached and queued like any other new event.
+                            let mut created = vec![];
+                            for event in events {
+                                let summary = event.summary.clone();
+                                match outbox::create(source.clone(), calendar.clone(), event).await
+                                {
+                                    Ok(event) => created.push(event),
+                                    Err(err) => {
+                                        tracing::error!(
+                                            "Failed to import event {}: {}",
+                                            summary,
+                                            err
+                                        )
+                                    }
+                                }
+                            }
+                            let failed = count - created.len();
+                            Ok((created, failed))
+                        },
+                        move |imported: Result<(Vec<Event>, usize)>| match imported {
+                            Ok((created, failed)) => cosmic::action::app(Message::Imported((
+                                imported_source.clone(),
+                                created,
+                                failed,
+                            ))),
+                            Err(err) => {
+                                tracing::error!("Failed to import events: {}", err);
+                                cosmic::action::app(Message::Imported((
+                                    imported_source.clone(),
+                                    vec![],
+                                    count,
+                                )))
+                            }
+                        },
+                    ));
+                }
+            }
+            Message::Imported((source, created, failed)) => {
+                for event in created {
+                    self.events
+                        .entry(calendar_key(&source, &event.calendar_id))
+                        .or_default()
+                        .push(event);
+                }
+                // Sends the events that were queued right away if possible.
+                tasks.push(cosmic::task::message(Message::ReplayOutbox));
+                if failed > 0 {
+                    if let Some(preview) = self.import.as_mut() {
+                        preview.fail(failed);
+                    }
+                } else {
+                    self.import = None;
+                    self.core.window.show_context = false;
+                }
+            }
+            Message::NavSelect(id) => return self.on_nav_select(id),
+            Message::EditCalendarColor(id) => {
+                let Some((source, calendar)) = self.nav_calendar(id) else {
+                    return Task::none();
+                };
+                let key = calendar_key(&source, &calendar.id);
+                let color = self
+                    .config
+                    .calendar_colors
+                    .get(&key)
+                    .or(calendar.color.as_ref())
+                    .map(String::as_str);
+                self.color_form = Some(ColorForm::new(key, calendar.name.clone(), color));
+                self.context_page = ContextPage::CalendarColor;
+                self.core.window.show_context = true;
+            }
+            Message::CalendarColor(message) => {
+                let Some(form) = self.color_form.as_mut() else {
+                    return Task::none();
+                };
+                let key = form.key().to_string();
+                let mut colors = self.config.calendar_colors.clone();
+                match form.update(message) {
+                    Some(color::Action::Set(color)) => colors.insert(key, color),
+                    Some(color::Action::Reset) => colors.remove(&key),
+                    None => return Task::none(),
+                };
+                if let Some(handler) = &self.config_handler {
+                    if let Err(err) = self.config.set_calendar_colors(handler, colors) {
+                        tracing::error!("Failed to save the calendar color: {}", err);
+                    }
+                }
+            }
+            Message::ExportCalendar(id) => {
+                let Some((source, calendar)) = self.nav_calendar(id) else {
+                    return Task::none();
+                };
+                tasks.push(Task::perform(
+                    async move {
+                        let Some(path) = export_path(format!("{}.ics", calendar.name)).await else {
+                            return Ok(());
+                        };
+                        let mut service = CalendarServiceFactory::get_service(&source).await?;
+                        let events = service.fetch_all_events(&calendar.id).await?;
+                        let data = ical::write_calendar(Some(&calendar.name), &events);
+                        tokio::fs::write(path, data).await?;
+                        Ok(())
+                    },
+                    |exported: Result<()>| {
+                        if let Err(err) = exported {
+                            tracing::error!("Failed to export calendar: {}", err);
+                        }
+                        cosmic::action::none()
+                    },
+                ));
+            }
+            Message::ExportVisibleRange => {
+                let (start, end) = self.visible_range();
+                // Loaded events are expanded occurrences, so the series they belong to
+                // are exported in their place, as their providers store them.
+                let series: Vec<(Source, Calendar, HashSet<String>)> = self
+                    .calendars
+                    .iter()
+                    .flat_map(|(source, calendars)| {
+                        calendars.iter().map(move |calendar| (source, calendar))
+                    })
+                    .filter_map(|(source, calendar)| {
+                        let key = calendar_key(source, &calendar.id);
+                        if self.config.hidden_calendars.contains(&key) {
+                            return None;
+                        }
+                        let ids: HashSet<String> = self
+                            .events
+                            .get(&key)?
+                            .iter()
+                            .filter(|event| event.overlaps(start, end))
+                            .map(|event| event.series_id().unwrap_or(&event.id).to_string())
+                            .collect();
+                        (!ids.is_empty()).then(|| (source.clone(), calendar.clone(), ids))
+                    })
+                    .collect();
+                let last_day = (end - time::Duration::DAY).date();
+                let file_name = format!("{}_{last_day}.ics", start.date());
+                tasks.push(Task::perform(
+                    async move {
+                        let Some(path) = export_path(file_name).await else {
+                            return Ok(());
+                        };
+                        let mut events = vec![];
+                        for (source, calendar, ids) in series {
+                            let mut service = CalendarServiceFactory::get_service(&source).await?;
+                            let all = service.fetch_all_events(&calendar.id).await?;
+                            events.extend(all.into_iter().filter(|event| {
+                                ids.contains(&event.id)
+                                    || event.series_id().is_some_and(|id| ids.contains(id))
+                            }));
+                        }
+                        tokio::fs::write(path, ical::write_calendar(None, &events)).await?;
+                        Ok(())
+                    },
+                    |exported: Result<()>| {
+                        if let Err(err) = exported {
+                            tracing::error!("Failed to export events: {}", err);
+                        }
+                        cosmic::action::none()
+                    },
+                ));
+            }
+            Message::LoadEvents => {
+                self.events_range = None;
+            }
+            Message::AddEvents((key, (start, end), events)) => {
+                let calendar_events = self.events.entry(key).or_default();
+                calendar_events.retain(|event| !event.overlaps(start, end));
+                calendar_events.extend(events);
+                // Days above the anchor may have grown, push it back into place.
+                if self.agenda_anchor.is_some() {
+                    tasks.push(self.scroll_agenda());
+                }
+            }
+            Message::AgendaScrolled(offset, relative) => {
+                // Any other offset than the one scrolled to means the user scrolled.
+                if offset > 0.0 && (offset - self.agenda_offset).abs() > 1.0 {
+                    self.agenda_anchor = None;
+                }
+                self.agenda_offset = offset;
+                if self.agenda_anchor.is_none() {
+                    if let Some(previous) = self.calendar.agenda_scrolled(offset, relative) {
+                        self.agenda_anchor = Some(previous);
+                        tasks.push(self.scroll_agenda());
+                    }
                 }
             }
             Message::AddEvent(date) => {
-                tracing::info!("Adding event on {date}");
+                self.editor = Some(EventEditor::create(
+                    date,
+                    self.calendar.zone,
+                    &self.editable_calendars(),
+                    &self.config,
+                ));
+                self.context_page = ContextPage::EventEditor;
+                self.core.window.show_context = true;
+            }
+            Message::OpenEvent(key, event_id) => {
+                let found = self.calendars.iter().find_map(|(source, calendars)| {
+                    let calendar = calendars
+                        .iter()
+                        .find(|c| calendar_key(source, &c.id) == key)?;
+                    let event = self.events.get(&key)?.iter().find(|e| e.id == event_id)?;
+                    Some((source.clone(), calendar.clone(), event.clone()))
+                });
+                if let Some((source, calendar, event)) = found {
+                    let zone = self.calendar.zone;
+                    self.editor = Some(EventEditor::edit(
+                        event,
+                        source,
+                        calendar,
+                        zone,
+                        &self.config,
+                    ));
+                    self.context_page = ContextPage::EventEditor;
+                    self.core.window.show_context = true;
+                }
+            }
+            Message::Editor(message) => {
+                let Some(editor) = self.editor.as_mut() else {
+                    return Task::none();
+                };
+                match editor.update(message) {
+                    Some(editor::Action::Save(source, calendar, event)) => {
+                        let saved_source = source.clone();
+                        tasks.push(Task::perform(
+                            outbox::save(source, calendar, event),
+                            move |event: Result<Event>| match event {
+                                Ok(event) => cosmic::action::app(Message::EventSaved(
+                                    saved_source.clone(),
+                                    event,
+                                )),
+                                Err(err) => {
+                                    tracing::error!("Failed to save event: {}", err);
+                                    cosmic::action::app(Message::EditorFailed(err.to_string()))
+                                }
+                            },
+                        ));
+                    }
+                    Some(editor::Action::Delete(source, calendar, event)) => {
+                        let deleted_source = source.clone();
+                        tasks.push(Task::perform(
+                            outbox::delete(source, calendar, event),
+                            move |event: Result<Event>| match event {
+                                Ok(event) => cosmic::action::app(Message::EventDeleted(
+                                    deleted_source.clone(),
+                                    event,
+                                )),
+                                Err(err) => {
+                                    tracing::error!("Failed to delete event: {}", err);
+                                    cosmic::action::app(Message::EditorFailed(err.to_string()))
+                                }
+                            },
+                        ));
+                    }
+                    // Changes to a series take several writes that depend on the series
+                    // as stored, so unlike single events they aren't queued offline.
+                    Some(editor::Action::SaveOccurrence(
+                        source,
+                        calendar,
+                        occurrence,
+                        changed,
+                        scope,
+                    )) => {
+                        tasks.push(Task::perform(
+                            async move {
+                                let mut service =
+                                    CalendarServiceFactory::get_service(&source).await?;
+                                service
+                                    .update_occurrence(&calendar, &occurrence, &changed, scope)
+                                    .await
+                            },
+                            |saved: Result<()>| match saved {
+                                Ok(()) => cosmic::action::app(Message::SeriesChanged),
+                                Err(err) => {
+                                    tracing::error!("Failed to save event: {}", err);
+                                    cosmic::action::app(Message::EditorFailed(err.to_string()))
+                                }
+                            },
+                        ));
+                    }
+                    Some(editor::Action::DeleteOccurrence(source, calendar, occurrence, scope)) => {
+                        tasks.push(Task::perform(
+                            async move {
+                                let mut service =
+                                    CalendarServiceFactory::get_service(&source).await?;
+                                service
+                                    .delete_occurrence(&calendar, &occurrence, scope)
+                                    .await
+                            },
+                            |deleted: Result<()>| match deleted {
+                                Ok(()) => cosmic::action::app(Message::SeriesChanged),
+                                Err(err) => {
+                                    tracing::error!("Failed to delete event: {}", err);
+                                    cosmic::action::app(Message::EditorFailed(err.to_string()))
+                                }
+                            },
+                        ));
+                    }
+                    None => {}
+                }
+            }
+            Message::EventSaved(source, event) => {
+                let calendar_events = self
+                    .events
+                    .entry(calendar_key(&source, &event.calendar_id))
+                    .or_default();
+                calendar_events.retain(|e| e.id != event.id);
+                calendar_events.push(event);
+                self.editor = None;
+                self.core.window.show_context = false;
+                // Picks up the change if it was queued, and sends it right away if possible.
+                tasks.push(cosmic::task::message(Message::ReplayOutbox));
+            }
+            Message::EventDeleted(source, event) => {
+                let key = calendar_key(&source, &event.calendar_id);
+                if let Some(calendar_events) = self.events.get_mut(&key) {
+                    calendar_events.retain(|e| e.id != event.id);
+                }
+                self.editor = None;
+                self.core.window.show_context = false;
+                tasks.push(cosmic::task::message(Message::ReplayOutbox));
+            }
+            Message::SeriesChanged => {
+                self.editor = None;
+                self.core.window.show_context = false;
+                tasks.push(cosmic::task::message(Message::LoadEvents));
+            }
+            Message::EditorFailed(error) => {
+                if let Some(editor) = self.editor.as_mut() {
+                    editor.fail(error);
+                }
             }
+            Message::ReplayOutbox => {
+                let sources = self.calendars.keys().cloned().collect();
+                tasks.push(Task::perform(
+                    outbox::replay(sources),
+                    |replayed: Result<Replayed>| match replayed {
+                        Ok(replayed) => cosmic::action::app(Message::Replayed(replayed)),
+                        Err(err) => {
+                            tracing::error!("Failed to replay queued changes: {}", err);
+                            cosmic::action::none()
+                        }
+                    },
+                ));
+            }
+            Message::Replayed(replayed) => {
+                for (source, pending_id, event) in replayed.saved {
+                    let calendar_events = self
+                        .events
+                        .entry(calendar_key(&source, &event.calendar_id))
+                        .or_default();
+                    calendar_events.retain(|e| e.id != pending_id && e.id != event.id);
+                    calendar_events.push(event);
+                }
+                if !replayed.failed.is_empty() {
+                    // Fetching again shows the events as their providers kept them.
+                    self.events_range = None;
+                    self.failed_changes = replayed.failed;
+                }
+                self.pending_changes = replayed.remaining;
+            }
+            Message::DismissFailedChanges => self.failed_changes.clear(),
             Message::SelectDate(date) => {
                 self.calendar.set_date(date);
             }
+            Message::OpenDay(date) => {
+                self.activate_tab(Tab::Day);
+                return self.update(Message::SelectDate(date));
+            }
+            Message::OpenWeek(date) => {
+                self.calendar.set_date(date);
+                self.activate_tab(Tab::Week);
+            }
             Message::SelectDay(idx) => {
                 let days = self.calendar.days();
                 let day = days.index(idx);
@@ -561,12 +1668,20 @@ impl cosmic::Application for AppModel {
             }
             Message::NavigateToday => self.calendar.set_today(),
             Message::NavigateNextDay => {
-                if let Err(err) = self.calendar.next_day() {
+                let result = match self.tabs.active_data::<Tab>() {
+                    Some(Tab::Days(range)) => self.calendar.shift_days(range.step()),
+                    _ => self.calendar.next_day(),
+                };
+                if let Err(err) = result {
                     tracing::error!("failed to navigate to next day: {err}");
                 }
             }
             Message::NavigatePreviousDay => {
-                if let Err(err) = self.calendar.previous_day() {
+                let result = match self.tabs.active_data::<Tab>() {
+                    Some(Tab::Days(range)) => self.calendar.shift_days(-range.step()),
+                    _ => self.calendar.previous_day(),
+                };
+                if let Err(err) = result {
                     tracing::error!("failed to navigate to previous day: {err}");
                 }
             }
@@ -591,6 +1706,23 @@ impl cosmic::Application for AppModel {
                 }
             }
         }
+
+        // The agenda starts over from a date picked with the navigation in the footer.
+        if self.calendar.selected_date.date() != selected_date
+            && self.tabs.active_data::<Tab>() == Some(&Tab::Agenda)
+        {
+            self.agenda_anchor = Some(self.calendar.reset_agenda());
+            tasks.push(self.scroll_agenda());
+        }
+
+        let range = self.visible_range();
+        if self.events_range != Some(range) {
+            self.events_range = Some(range);
+            for (source, calendars) in &self.calendars {
+                tasks.extend(Self::fetch_events(source, calendars, range));
+            }
+        }
+
         Task::batch(tasks)
     }
 
@@ -638,6 +1770,331 @@ impl AppModel {
             .into()
     }
 
+    /// The settings page, with the preferences stored in the config.
+    pub fn settings<'a>(&'a self) -> Element<'a, Message> {
+        let labels = [fl!("second-time-zone"), fl!("third-time-zone")];
+        let zones = labels
+            .into_iter()
+            .enumerate()
+            .fold(widget::column(), |column, (slot, label)| {
+                let selected = self
+                    .config
+                    .secondary_zones
+                    .get(slot)
+                    .and_then(|name| self.zone_names.iter().position(|n| n == name))
+                    .unwrap_or(0);
+                column.push(widget::settings::item(
+                    label,
+                    widget::dropdown(&self.zone_names, Some(selected), move |index| {
+                        Message::SetSecondaryZone(slot, index)
+                    }),
+                ))
+            })
+            .spacing(spacing().space_xxs);
+
+        let first_weekday = self
+            .config
+            .first_weekday
+            .map_or(0, |weekday| weekday.number_days_from_monday() as usize + 1);
+        let (work_start, work_end) = self.config.working_hours;
+        let hours = self.hour_names.len().saturating_sub(1);
+
+        let display = widget::column()
+            .push(widget::settings::item(
+                fl!("default-view"),
+                widget::dropdown(
+                    &self.view_names,
+                    VIEWS
+                        .iter()
+                        .position(|tab| *tab == self.config.default_view),
+                    Message::SetDefaultView,
+                ),
+            ))
+            .push(widget::settings::item(
+                fl!("first-weekday"),
+                widget::dropdown(
+                    &self.weekday_names,
+                    Some(first_weekday),
+                    Message::SetFirstWeekday,
+                ),
+            ))
+            .push(widget::settings::item(
+                fl!("show-week-numbers"),
+                widget::toggler(self.config.show_week_numbers)
+                    .on_toggle(Message::SetShowWeekNumbers),
+            ))
+            .push(widget::settings::item(
+                fl!("twelve-hour-clock"),
+                widget::toggler(self.config.clock == ClockFormat::TwelveHour)
+                    .on_toggle(Message::SetTwelveHourClock),
+            ))
+            .push(widget::settings::item(
+                fl!("working-hours-start"),
+                widget::dropdown(
+                    &self.hour_names[..hours],
+                    Some(work_start as usize),
+                    move |hour| Message::SetWorkingHours(hour as u8, work_end),
+                ),
+            ))
+            .push(widget::settings::item(
+                fl!("working-hours-end"),
+                widget::dropdown(
+                    self.hour_names.get(1..).unwrap_or_default(),
+                    (work_end as usize).checked_sub(1),
+                    move |hour| Message::SetWorkingHours(work_start, hour as u8 + 1),
+                ),
+            ))
+            .spacing(spacing().space_xxs);
+
+        let default_calendar = self.config.default_calendar.as_deref();
+        let editable = self.editable_calendars();
+        let selected_calendar = editable.iter().position(|(source, calendar)| {
+            default_calendar == Some(calendar_key(source, &calendar.id).as_str())
+        });
+        let calendar_choices = editable.iter().enumerate().fold(
+            widget::column().spacing(spacing().space_xxs),
+            |column, (index, (_, calendar))| {
+                column.push(widget::radio(
+                    widget::text::body(calendar.name.clone()),
+                    index,
+                    selected_calendar,
+                    Message::SetDefaultCalendar,
+                ))
+            },
+        );
+
+        let new_events = widget::column()
+            .push(widget::settings::item(
+                fl!("event-duration"),
+                widget::dropdown(
+                    &self.duration_names,
+                    EVENT_DURATIONS
+                        .iter()
+                        .position(|minutes| *minutes == self.config.event_duration),
+                    Message::SetEventDuration,
+                ),
+            ))
+            .push(widget::settings::item(
+                fl!("reminder"),
+                widget::dropdown(
+                    &self.reminder_names,
+                    REMINDERS
+                        .iter()
+                        .position(|reminder| *reminder == self.config.reminder),
+                    Message::SetReminder,
+                ),
+            ))
+            .push(widget::text::body(fl!("default-calendar")))
+            .push(calendar_choices)
+            .spacing(spacing().space_xxs);
+
+        let calendars = self
+            .calendars
+            .iter()
+            .flat_map(|(source, calendars)| {
+                calendars
+                    .iter()
+                    .map(move |calendar| (calendar_key(source, &calendar.id), calendar))
+            })
+            .fold(
+                widget::column().spacing(spacing().space_xxs),
+                |column, (key, calendar)| {
+                    let visible = !self.config.hidden_calendars.contains(&key);
+                    column.push(widget::settings::item(
+                        calendar.name.clone(),
+                        widget::toggler(visible).on_toggle(move |visible| {
+                            Message::SetCalendarVisible(key.clone(), visible)
+                        }),
+                    ))
+                },
+            );
+
+        widget::column()
+            .push(widget::text::heading(fl!("calendar-settings")))
+            .push(display)
+            .push(widget::text::heading(fl!("new-events")))
+            .push(new_events)
+            .push(widget::text::heading(fl!("calendars")))
+            .push(calendars)
+            .push(widget::text::heading(fl!("time-zones")))
+            .push(zones)
+            .push(widget::text::caption(fl!("time-zones-description")))
+            .push(widget::text::heading(fl!("sync")))
+            .push(widget::settings::item(
+                fl!("refresh-interval"),
+                widget::dropdown(
+                    &self.refresh_names,
+                    REFRESH_INTERVALS
+                        .iter()
+                        .position(|minutes| *minutes == self.config.refresh_interval),
+                    Message::SetRefreshInterval,
+                ),
+            ))
+            .spacing(spacing().space_s)
+            .into()
+    }
+
+    /// Lays out the grids as configured, starting weeks on the region default day
+    /// unless another one was chosen.
+    fn apply_config(&mut self) {
+        self.calendar.first_weekday = self
+            .config
+            .first_weekday
+            .unwrap_or_else(i18n::first_weekday);
+        self.calendar.show_week_numbers = self.config.show_week_numbers;
+        self.calendar.clock = self.config.clock;
+        self.calendar.working_hours = self.config.working_hours;
+        self.hour_names = (0..=24)
+            .map(|hour| self.config.clock.format(hour, 0))
+            .collect();
+    }
+
+    /// The extra time zones shown next to the hours of the week and day views.
+    fn secondary_zones(&self) -> Vec<&'static Tz> {
+        self.config
+            .secondary_zones
+            .iter()
+            .filter_map(|name| zone::named(name))
+            .collect()
+    }
+
+    /// Every loaded event of the calendars that aren't hidden, paired with the color
+    /// chosen for its calendar, or else the one its provider reports.
+    fn calendar_events(&self) -> Vec<CalendarEvent<'_>> {
+        self.calendars
+            .iter()
+            .flat_map(|(source, calendars)| {
+                calendars
+                    .iter()
+                    .map(move |calendar| (calendar_key(source, &calendar.id), calendar))
+            })
+            .filter(|(key, _)| !self.config.hidden_calendars.contains(key))
+            .flat_map(|(key, calendar)| {
+                let color = self
+                    .config
+                    .calendar_colors
+                    .get(&key)
+                    .or(calendar.color.as_ref())
+                    .map(String::as_str);
+                // The key is borrowed from the map, so events can point to it.
+                self.events
+                    .get_key_value(&key)
+                    .into_iter()
+                    .flat_map(move |(key, events)| {
+                        events
+                            .iter()
+                            .map(move |event| CalendarEvent::new(event, key, color))
+                    })
+            })
+            .collect()
+    }
+
+    /// Every calendar events can be written to, along with its source.
+    fn editable_calendars(&self) -> Vec<(Source, Calendar)> {
+        self.calendars
+            .iter()
+            .flat_map(|(source, calendars)| {
+                calendars
+                    .iter()
+                    .filter(|calendar| calendar.is_editable())
+                    .map(move |calendar| (source.clone(), calendar.clone()))
+            })
+            .collect()
+    }
+
+    /// Fills the nav bar with every source and its calendars, keeping the selected calendar.
+    fn rebuild_nav(&mut self) {
+        let active = self
+            .nav
+            .active_data::<(Source, Calendar)>()
+            .map(|(source, calendar)| calendar_key(source, &calendar.id));
+        self.nav.clear();
+        for (source, calendars) in &self.calendars {
+            self.nav.insert().text(source.name());
+            for calendar in calendars {
+                let id = self
+                    .nav
+                    .insert()
+                    .indent(1)
+                    .text(calendar.name.clone())
+                    .icon(widget::icon::from_name("office-calendar-symbolic"))
+                    .data((source.clone(), calendar.clone()))
+                    .id();
+                if active.as_deref() == Some(calendar_key(source, &calendar.id).as_str()) {
+                    self.nav.activate(id);
+                }
+            }
+        }
+    }
+
+    /// The calendar of a nav bar item, along with its source.
+    fn nav_calendar(&self, id: nav_bar::Id) -> Option<(Source, Calendar)> {
+        self.nav.data::<(Source, Calendar)>(id).cloned()
+    }
+
+    /// Creates one task per calendar fetching its events within `range` through the cache.
+    fn fetch_events(
+        source: &Source,
+        calendars: &[Calendar],
+        range: (OffsetDateTime, OffsetDateTime),
+    ) -> Vec<Task<cosmic::Action<Message>>> {
+        calendars
+            .iter()
+            .map(|calendar| {
+                let source = source.clone();
+                let calendar_id = calendar.id.clone();
+                let key = calendar_key(&source, &calendar_id);
+                Task::perform(
+                    async move {
+                        let events =
+                            sync::fetch_events(source, calendar_id, range.0, range.1).await?;
+                        Ok((key, range, events))
+                    },
+                    |events: Result<(String, (OffsetDateTime, OffsetDateTime), Vec<Event>)>| {
+                        match events {
+                            Ok(events) => cosmic::action::app(Message::AddEvents(events)),
+                            Err(err) => {
+                                tracing::error!("Failed to load events: {}", err);
+                                cosmic::action::none()
+                            }
+                        }
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// The time range shown in the active tab, which events are loaded for.
+    fn visible_range(&self) -> (OffsetDateTime, OffsetDateTime) {
+        match self.tabs.active_data::<Tab>() {
+            Some(Tab::Year) => self.calendar.year_range(),
+            Some(Tab::Agenda) => self.calendar.agenda_range(),
+            Some(Tab::Days(range)) => self.calendar.range_range(*range),
+            _ => self.calendar.visible_range(),
+        }
+    }
+
+    /// Scrolls the agenda so the anchored day is at the top.
+    fn scroll_agenda(&mut self) -> Task<cosmic::Action<Message>> {
+        let Some(anchor) = self.agenda_anchor else {
+            return Task::none();
+        };
+        let offset = self.calendar.agenda_offset(anchor, &self.calendar_events());
+        self.agenda_offset = offset;
+        scrollable::scroll_to(AGENDA_ID.clone(), AbsoluteOffset { x: 0.0, y: offset })
+    }
+
+    /// Switches the tab bar to the tab showing `tab`.
+    fn activate_tab(&mut self, tab: Tab) {
+        let entity = self
+            .tabs
+            .iter()
+            .find(|entity| self.tabs.data::<Tab>(*entity) == Some(&tab));
+        if let Some(entity) = entity {
+            self.tabs.activate(entity);
+        }
+    }
+
     /// Updates the header and window titles.
     pub fn update_title(&mut self) -> Task<cosmic::Action<Message>> {
         let mut window_title = fl!("app-title");
@@ -655,11 +2112,55 @@ impl AppModel {
     }
 }
 
+/// A number of minutes in the largest unit that divides it, such as "2 hours".
+fn duration_label(minutes: u32) -> String {
+    if minutes > 0 && minutes % (24 * 60) == 0 {
+        fl!("days", count = minutes / (24 * 60))
+    } else if minutes > 0 && minutes % 60 == 0 {
+        fl!("hours", count = minutes / 60)
+    } else {
+        fl!("minutes", count = minutes)
+    }
+}
+
+/// Asks where to save an exported `.ics` file, suggesting `file_name`.
+/// Returns `None` when the dialog is cancelled.
+async fn export_path(file_name: String) -> Option<std::path::PathBuf> {
+    let response = file_chooser::save::Dialog::new()
+        .title(fl!("export-events"))
+        .file_name(file_name)
+        .save_file()
+        .await
+        .ok()?;
+    response.url()?.to_file_path().ok()
+}
+
 /// The tab to display in the application.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Tab {
+    Year,
     Month,
     Week,
+    /// A number of days side by side, like the week.
+    Days(DayRange),
     Day,
+    Agenda,
+}
+
+impl Tab {
+    /// The label of the tab in the tab bar.
+    fn name(self) -> String {
+        match self {
+            Tab::Year => fl!("year"),
+            Tab::Month => fl!("month"),
+            Tab::Week | Tab::Days(DayRange::Week) => fl!("week"),
+            Tab::Days(DayRange::WorkWeek) => fl!("work-week"),
+            Tab::Days(DayRange::ThreeDays) => fl!("three-days"),
+            Tab::Days(DayRange::TwoWeeks) => fl!("two-weeks"),
+            Tab::Day => fl!("day"),
+            Tab::Agenda => fl!("agenda"),
+        }
+    }
 }
 
 /// The context page to display in the context drawer.
@@ -667,11 +2168,22 @@ pub enum Tab {
 pub enum ContextPage {
     #[default]
     About,
+    Settings,
+    EventEditor,
+    CalDavAccount,
+    Subscription,
+    CalendarColor,
+    Import,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum MenuAction {
     About,
+    Settings,
+    Import,
+    ExportVisibleRange,
+    AddCalDavAccount,
+    Subscribe,
 }
 
 impl menu::action::MenuAction for MenuAction {
@@ -680,6 +2192,29 @@ impl menu::action::MenuAction for MenuAction {
     fn message(&self) -> Self::Message {
         match self {
             MenuAction::About => Message::ToggleContextPage(ContextPage::About),
+            MenuAction::Settings => Message::ToggleContextPage(ContextPage::Settings),
+            MenuAction::Import => Message::ImportFile,
+            MenuAction::ExportVisibleRange => Message::ExportVisibleRange,
+            MenuAction::AddCalDavAccount => Message::AddCalDavAccount,
+            MenuAction::Subscribe => Message::Subscribe,
+        }
+    }
+}
+
+/// Actions of the context menu of calendars in the nav bar.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NavMenuAction {
+    Color(nav_bar::Id),
+    Export(nav_bar::Id),
+}
+
+impl menu::action::MenuAction for NavMenuAction {
+    type Message = cosmic::Action<Message>;
+
+    fn message(&self) -> Self::Message {
+        match self {
+            NavMenuAction::Color(id) => cosmic::Action::App(Message::EditCalendarColor(*id)),
+            NavMenuAction::Export(id) => cosmic::Action::App(Message::ExportCalendar(*id)),
         }
     }
 }
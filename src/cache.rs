@@ -0,0 +1,749 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! An SQLite cache of the calendars and events fetched from every source, so the
+//! app can show them right away on launch and keep working without a network.
+//! Changes waiting in the outbox aren't a cache and can't be fetched again, so they
+//! live in a database of their own under the data directory, attached to the cache.
+
+use std::path::PathBuf;
+
+use cosmic::Application;
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
+use time::{Duration, OffsetDateTime, UtcOffset};
+
+use crate::{
+    app::AppModel,
+    models::{Calendar, Event, Source},
+    outbox::{Operation, PendingChange},
+    services::SyncChanges,
+    Error, Result,
+};
+
+/// Name of the database file, inside the app's cache directory.
+const CACHE_FILE: &str = "cache.sqlite3";
+
+/// Name of the outbox database file, inside the app's data directory.
+const OUTBOX_FILE: &str = "outbox.sqlite3";
+
+/// Bumped whenever the tables change; older caches are dropped and filled again.
+const SCHEMA_VERSION: i64 = 4;
+
+const SCHEMA: &str = "
+    CREATE TABLE calendars (
+        source TEXT NOT NULL,
+        id TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        data TEXT NOT NULL,
+        PRIMARY KEY (source, id)
+    );
+    CREATE TABLE events (
+        source TEXT NOT NULL,
+        calendar_id TEXT NOT NULL,
+        id TEXT NOT NULL,
+        start INTEGER NOT NULL,
+        end INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX events_by_range ON events (source, calendar_id, start, end);
+    CREATE TABLE sync_state (
+        source TEXT NOT NULL,
+        calendar_id TEXT NOT NULL,
+        token TEXT NOT NULL,
+        start INTEGER NOT NULL,
+        end INTEGER NOT NULL,
+        PRIMARY KEY (source, calendar_id)
+    );
+";
+
+/// The outbox, created in the attached `store` database when missing. It's never
+/// dropped along with the cache.
+const OUTBOX_SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS store.outbox (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        source TEXT NOT NULL,
+        operation TEXT NOT NULL,
+        calendar TEXT NOT NULL,
+        event TEXT NOT NULL
+    );
+";
+
+pub struct Cache {
+    connection: Connection,
+}
+
+/// Where incremental sync of a calendar left off.
+#[derive(Debug, Clone)]
+pub struct SyncState {
+    /// The Google sync token or Graph delta link of the last sync.
+    pub token: String,
+    /// The range of events the sync covers, fixed by the full sync that started it.
+    pub start: OffsetDateTime,
+    pub end: OffsetDateTime,
+}
+
+/// Runs `f` against the cache on a blocking thread, since SQLite calls block.
+pub async fn with<T, F>(f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&mut Cache) -> Result<T> + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || f(&mut Cache::open()?))
+        .await
+        .map_err(|err| Error::Unknown(format!("Cache task failed: {err}")))?
+}
+
+impl Cache {
+    pub fn open() -> Result<Self> {
+        let (path, outbox_path) = Self::paths()?;
+        for path in [&path, &outbox_path] {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        Self::init(Connection::open(path)?, &outbox_path.to_string_lossy())
+    }
+
+    /// A cache and outbox kept in memory, which are gone once it's dropped.
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?, ":memory:")
+    }
+
+    /// Attaches the outbox at `outbox_path` to the cache in `connection`, and creates
+    /// or rebuilds the tables as needed.
+    fn init(mut connection: Connection, outbox_path: &str) -> Result<Self> {
+        // Every task opens its own connection, so wait for the others instead of failing.
+        connection.busy_timeout(std::time::Duration::from_secs(5))?;
+        connection.execute("ATTACH DATABASE ?1 AS store", [outbox_path])?;
+        connection.execute_batch(OUTBOX_SCHEMA)?;
+
+        let version: i64 = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version != SCHEMA_VERSION {
+            // Tasks open the cache concurrently on first run, so only the one that gets
+            // the write lock first rebuilds it, and the others find it up to date.
+            let transaction =
+                connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
+            let version: i64 =
+                transaction.query_row("PRAGMA main.user_version", [], |row| row.get(0))?;
+            if version != SCHEMA_VERSION {
+                // Caches from before the outbox moved out still hold queued changes.
+                let has_outbox = transaction
+                    .query_row(
+                        "SELECT 1 FROM main.sqlite_master WHERE type = 'table' AND name = 'outbox'",
+                        [],
+                        |_| Ok(()),
+                    )
+                    .optional()?
+                    .is_some();
+                if has_outbox {
+                    transaction.execute_batch(
+                        "INSERT INTO store.outbox (source, operation, calendar, event)
+                        SELECT source, operation, calendar, event FROM main.outbox ORDER BY id;
+                        DROP TABLE main.outbox;",
+                    )?;
+                }
+                transaction.execute_batch(&format!(
+                    "DROP TABLE IF EXISTS main.calendars;
+                    DROP TABLE IF EXISTS main.events;
+                    DROP TABLE IF EXISTS main.sync_state;
+                    {SCHEMA}
+                    PRAGMA main.user_version = {SCHEMA_VERSION};"
+                ))?;
+            }
+            transaction.commit()?;
+        }
+        Ok(Cache { connection })
+    }
+
+    /// The cache database and the outbox database.
+    fn paths() -> Result<(PathBuf, PathBuf)> {
+        let cache_dir = dirs::cache_dir()
+            .ok_or_else(|| Error::Unknown("No cache directory available".to_string()))?;
+        let data_dir = dirs::data_dir()
+            .ok_or_else(|| Error::Unknown("No data directory available".to_string()))?;
+        Ok((
+            cache_dir.join(AppModel::APP_ID).join(CACHE_FILE),
+            data_dir.join(AppModel::APP_ID).join(OUTBOX_FILE),
+        ))
+    }
+
+    /// The calendars last fetched from `source`, in the order the source listed them.
+    pub fn calendars(&self, source: &Source) -> Result<Vec<Calendar>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT data FROM calendars WHERE source = ?1 ORDER BY position")?;
+        let rows = statement.query_map([source.key()], |row| row.get::<_, String>(0))?;
+
+        let mut calendars = vec![];
+        for data in rows {
+            calendars.push(serde_json::from_str(&data?)?);
+        }
+        Ok(calendars)
+    }
+
+    /// Replaces the calendars of `source`, dropping the events of calendars that are gone.
+    pub fn set_calendars(&mut self, source: &Source, calendars: &[Calendar]) -> Result<()> {
+        let key = source.key();
+        let transaction = self.connection.transaction()?;
+        transaction.execute("DELETE FROM calendars WHERE source = ?1", [&key])?;
+        for (position, calendar) in calendars.iter().enumerate() {
+            transaction.execute(
+                "INSERT INTO calendars (source, id, position, data) VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    key,
+                    calendar.id,
+                    position as i64,
+                    serde_json::to_string(calendar)?
+                ],
+            )?;
+        }
+        transaction.execute(
+            "DELETE FROM events WHERE source = ?1
+            AND calendar_id NOT IN (SELECT id FROM calendars WHERE source = ?1)",
+            [&key],
+        )?;
+        transaction.execute(
+            "DELETE FROM sync_state WHERE source = ?1
+            AND calendar_id NOT IN (SELECT id FROM calendars WHERE source = ?1)",
+            [&key],
+        )?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// The cached events of a calendar that overlap `[start, end)`.
+    pub fn events(
+        &self,
+        source: &Source,
+        calendar_id: &str,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+    ) -> Result<Vec<Event>> {
+        Ok(self
+            .candidates(source, calendar_id, start, end)?
+            .into_iter()
+            .map(|(_, event)| event)
+            .collect())
+    }
+
+    /// Replaces the cached events of a calendar within `[start, end)` with `events`,
+    /// the ones just fetched for that range.
+    pub fn set_events(
+        &mut self,
+        source: &Source,
+        calendar_id: &str,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+        events: &[Event],
+    ) -> Result<()> {
+        let stale = self.candidates(source, calendar_id, start, end)?;
+        let transaction = self.connection.transaction()?;
+        for (row_id, _) in stale {
+            transaction.execute("DELETE FROM events WHERE rowid = ?1", [row_id])?;
+        }
+        for event in events {
+            insert_event(&transaction, source, event)?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Stores a created or changed event in place of its previous version.
+    pub fn save_event(&mut self, source: &Source, event: &Event) -> Result<()> {
+        let transaction = self.connection.transaction()?;
+        delete_event(&transaction, source, event)?;
+        insert_event(&transaction, source, event)?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    pub fn remove_event(&mut self, source: &Source, event: &Event) -> Result<()> {
+        delete_event(&self.connection, source, event)
+    }
+
+    pub fn sync_state(&self, source: &Source, calendar_id: &str) -> Result<Option<SyncState>> {
+        let state = self
+            .connection
+            .query_row(
+                "SELECT token, start, end FROM sync_state WHERE source = ?1 AND calendar_id = ?2",
+                params![source.key(), calendar_id],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, i64>(2)?,
+                    ))
+                },
+            )
+            .optional()?;
+        let Some((token, start, end)) = state else {
+            return Ok(None);
+        };
+        Ok(Some(SyncState {
+            token,
+            start: OffsetDateTime::from_unix_timestamp(start)?,
+            end: OffsetDateTime::from_unix_timestamp(end)?,
+        }))
+    }
+
+    /// Applies the changes of an incremental sync and remembers where it left off.
+    /// Removing a recurring series also removes the occurrences expanded from it.
+    pub fn apply_changes(
+        &mut self,
+        source: &Source,
+        calendar_id: &str,
+        state: &SyncState,
+        changes: &SyncChanges,
+    ) -> Result<()> {
+        let key = source.key();
+        let transaction = self.connection.transaction()?;
+        if changes.full {
+            transaction.execute(
+                "DELETE FROM events WHERE source = ?1 AND calendar_id = ?2",
+                params![key, calendar_id],
+            )?;
+        }
+        let ids = changes
+            .removed
+            .iter()
+            .chain(changes.changed.iter().map(|event| &event.id));
+        for id in ids {
+            transaction.execute(
+                "DELETE FROM events WHERE source = ?1 AND calendar_id = ?2 AND (id = ?3
+                OR json_extract(data, '$.extra.recurringEventId') = ?3
+                OR json_extract(data, '$.extra.seriesMasterId') = ?3)",
+                params![key, calendar_id, id],
+            )?;
+        }
+        for event in &changes.changed {
+            insert_event(&transaction, source, event)?;
+        }
+        transaction.execute(
+            "INSERT OR REPLACE INTO sync_state (source, calendar_id, token, start, end)
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                key,
+                calendar_id,
+                state.token,
+                state.start.unix_timestamp(),
+                state.end.unix_timestamp()
+            ],
+        )?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Queues a change made while offline. Each event has at most one queued change:
+    /// edits to an event waiting to be created or updated are folded into that change,
+    /// and deleting an event that was never created drops it altogether.
+    pub fn enqueue(
+        &mut self,
+        source: &Source,
+        operation: Operation,
+        calendar: &Calendar,
+        event: &Event,
+    ) -> Result<()> {
+        let key = source.key();
+        let transaction = self.connection.transaction()?;
+        let queued = transaction
+            .query_row(
+                "SELECT id, operation FROM store.outbox
+                WHERE source = ?1 AND json_extract(event, '$.id') = ?2",
+                params![key, event.id],
+                |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+            )
+            .optional()?;
+        let event_data = serde_json::to_string(event)?;
+        match queued.map(|(id, queued)| (id, Operation::parse(&queued))) {
+            Some((id, Some(Operation::Create))) if operation == Operation::Delete => {
+                transaction.execute("DELETE FROM store.outbox WHERE id = ?1", [id])?;
+            }
+            Some((id, Some(Operation::Create | Operation::Update))) => {
+                let operation = match operation {
+                    Operation::Update => None,
+                    operation => Some(operation.as_str()),
+                };
+                transaction.execute(
+                    "UPDATE store.outbox SET event = ?2, operation = COALESCE(?3, operation)
+                    WHERE id = ?1",
+                    params![id, event_data, operation],
+                )?;
+            }
+            _ => {
+                transaction.execute(
+                    "INSERT INTO store.outbox (source, operation, calendar, event)
+                    VALUES (?1, ?2, ?3, ?4)",
+                    params![
+                        key,
+                        operation.as_str(),
+                        serde_json::to_string(calendar)?,
+                        event_data
+                    ],
+                )?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Every queued change, oldest first.
+    pub fn pending_changes(&self) -> Result<Vec<PendingChange>> {
+        let mut statement = self.connection.prepare(
+            "SELECT id, source, operation, calendar, event FROM store.outbox ORDER BY id",
+        )?;
+        let rows = statement.query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+            ))
+        })?;
+
+        let mut changes = vec![];
+        for row in rows {
+            let (id, source, operation, calendar, event) = row?;
+            let Some(operation) = Operation::parse(&operation) else {
+                continue;
+            };
+            changes.push(PendingChange {
+                id,
+                source,
+                operation,
+                calendar: serde_json::from_str(&calendar)?,
+                event: serde_json::from_str(&event)?,
+            });
+        }
+        Ok(changes)
+    }
+
+    pub fn remove_pending(&mut self, id: i64) -> Result<()> {
+        self.connection
+            .execute("DELETE FROM store.outbox WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    /// Events overlapping `[start, end)` along with their row ids. The stored bounds
+    /// place all-day events at midnight UTC, so the query is widened by a day on each
+    /// side and the result narrowed with [`Event::overlaps`].
+    fn candidates(
+        &self,
+        source: &Source,
+        calendar_id: &str,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+    ) -> Result<Vec<(i64, Event)>> {
+        let mut statement = self.connection.prepare(
+            "SELECT rowid, data FROM events
+            WHERE source = ?1 AND calendar_id = ?2 AND start < ?3 AND end > ?4",
+        )?;
+        let rows = statement.query_map(
+            params![
+                source.key(),
+                calendar_id,
+                (end + Duration::DAY).unix_timestamp(),
+                (start - Duration::DAY).unix_timestamp(),
+            ],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+        )?;
+
+        let mut events = vec![];
+        for row in rows {
+            let (row_id, data) = row?;
+            let event: Event = serde_json::from_str(&data)?;
+            if event.overlaps(start, end) {
+                events.push((row_id, event));
+            }
+        }
+        Ok(events)
+    }
+}
+
+fn insert_event(connection: &Connection, source: &Source, event: &Event) -> Result<()> {
+    connection.execute(
+        "INSERT INTO events (source, calendar_id, id, start, end, data)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            source.key(),
+            event.calendar_id,
+            event.id,
+            event.start.at_offset(UtcOffset::UTC).unix_timestamp(),
+            event.end.at_offset(UtcOffset::UTC).unix_timestamp(),
+            serde_json::to_string(event)?
+        ],
+    )?;
+    Ok(())
+}
+
+fn delete_event(connection: &Connection, source: &Source, event: &Event) -> Result<()> {
+    connection.execute(
+        "DELETE FROM events WHERE source = ?1 AND calendar_id = ?2 AND id = ?3",
+        params![source.key(), event.calendar_id, event.id],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+    use time::macros::{date, datetime};
+
+    use super::*;
+    use crate::models::fixtures::{all_day, calendar, event};
+
+    fn meeting(summary: &str) -> Event {
+        let mut meeting = event(
+            "work",
+            "pending-1",
+            datetime!(2024-03-04 10:00 UTC),
+            datetime!(2024-03-04 11:00 UTC),
+        );
+        meeting.summary = summary.to_string();
+        meeting
+    }
+
+    #[test]
+    fn updating_a_queued_creation_folds_into_it() {
+        let mut cache = Cache::open_in_memory().unwrap();
+        let calendar = calendar("work");
+        cache
+            .enqueue(
+                &Source::Local,
+                Operation::Create,
+                &calendar,
+                &meeting("Standup"),
+            )
+            .unwrap();
+        cache
+            .enqueue(
+                &Source::Local,
+                Operation::Update,
+                &calendar,
+                &meeting("Retro"),
+            )
+            .unwrap();
+
+        let pending = cache.pending_changes().unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].operation, Operation::Create);
+        assert_eq!(pending[0].event.summary, "Retro");
+    }
+
+    #[test]
+    fn deleting_a_queued_creation_drops_it() {
+        let mut cache = Cache::open_in_memory().unwrap();
+        let calendar = calendar("work");
+        cache
+            .enqueue(
+                &Source::Local,
+                Operation::Create,
+                &calendar,
+                &meeting("Standup"),
+            )
+            .unwrap();
+        cache
+            .enqueue(
+                &Source::Local,
+                Operation::Delete,
+                &calendar,
+                &meeting("Standup"),
+            )
+            .unwrap();
+
+        assert!(cache.pending_changes().unwrap().is_empty());
+    }
+
+    #[test]
+    fn deleting_a_queued_update_replaces_it() {
+        let mut cache = Cache::open_in_memory().unwrap();
+        let calendar = calendar("work");
+        let mut saved = meeting("Standup");
+        saved.id = "abc".to_string();
+        cache
+            .enqueue(&Source::Local, Operation::Update, &calendar, &saved)
+            .unwrap();
+        cache
+            .enqueue(&Source::Local, Operation::Delete, &calendar, &saved)
+            .unwrap();
+
+        let pending = cache.pending_changes().unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].operation, Operation::Delete);
+    }
+
+    fn standup(id: &str) -> Event {
+        event(
+            "work",
+            id,
+            datetime!(2024-03-04 10:00 UTC),
+            datetime!(2024-03-04 11:00 UTC),
+        )
+    }
+
+    /// The ids of the events cached for the work calendar in March 2024.
+    fn cached_ids(cache: &Cache) -> Vec<String> {
+        let mut ids: Vec<String> = cache
+            .events(
+                &Source::Local,
+                "work",
+                datetime!(2024-03-01 00:00 UTC),
+                datetime!(2024-04-01 00:00 UTC),
+            )
+            .unwrap()
+            .into_iter()
+            .map(|event| event.id)
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    fn window(token: &str, start: OffsetDateTime, end: OffsetDateTime) -> SyncState {
+        SyncState {
+            token: token.to_string(),
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn all_day_events_are_found_from_any_offset() {
+        let mut cache = Cache::open_in_memory().unwrap();
+        let holiday = all_day("work", "holiday", date!(2024 - 03 - 05));
+        cache.save_event(&Source::Local, &holiday).unwrap();
+
+        // The evening of the holiday west of UTC is already the next day in UTC.
+        let evening = cache
+            .events(
+                &Source::Local,
+                "work",
+                datetime!(2024-03-05 20:00 -12),
+                datetime!(2024-03-05 22:00 -12),
+            )
+            .unwrap();
+        assert_eq!(evening.len(), 1);
+
+        // The next day east of UTC starts while it's still the holiday in UTC.
+        let next_day = cache
+            .events(
+                &Source::Local,
+                "work",
+                datetime!(2024-03-06 00:00 +10),
+                datetime!(2024-03-07 00:00 +10),
+            )
+            .unwrap();
+        assert!(next_day.is_empty());
+    }
+
+    #[test]
+    fn changes_replace_and_remove_cached_events() {
+        let mut cache = Cache::open_in_memory().unwrap();
+        let start = datetime!(2024-03-01 00:00 UTC);
+        let end = datetime!(2024-04-01 00:00 UTC);
+        cache
+            .set_events(
+                &Source::Local,
+                "work",
+                start,
+                end,
+                &[standup("a"), standup("b")],
+            )
+            .unwrap();
+
+        let mut renamed = standup("a");
+        renamed.summary = "Retro".to_string();
+        let changes = SyncChanges {
+            full: false,
+            changed: vec![renamed, standup("c")],
+            removed: vec!["b".to_string()],
+            token: "2".to_string(),
+        };
+        let state = window("2", start, end);
+        cache
+            .apply_changes(&Source::Local, "work", &state, &changes)
+            .unwrap();
+
+        assert_eq!(cached_ids(&cache), ["a", "c"]);
+        let events = cache.events(&Source::Local, "work", start, end).unwrap();
+        let renamed = events.iter().find(|event| event.id == "a").unwrap();
+        assert_eq!(renamed.summary, "Retro");
+        let stored = cache.sync_state(&Source::Local, "work").unwrap().unwrap();
+        assert_eq!(stored.token, "2");
+    }
+
+    #[test]
+    fn removing_a_series_removes_its_occurrences() {
+        let mut cache = Cache::open_in_memory().unwrap();
+        let mut google = standup("google_20240304T100000Z");
+        google.extra.insert(
+            "recurringEventId".to_string(),
+            Value::String("google".to_string()),
+        );
+        let mut graph = standup("AAMkOccurrence");
+        graph.extra.insert(
+            "seriesMasterId".to_string(),
+            Value::String("AAMkMaster".to_string()),
+        );
+        for event in [google, graph, standup("single")] {
+            cache.save_event(&Source::Local, &event).unwrap();
+        }
+
+        let changes = SyncChanges {
+            full: false,
+            changed: vec![],
+            removed: vec!["google".to_string(), "AAMkMaster".to_string()],
+            token: "2".to_string(),
+        };
+        let state = window(
+            "2",
+            datetime!(2024-01-01 00:00 UTC),
+            datetime!(2025-01-01 00:00 UTC),
+        );
+        cache
+            .apply_changes(&Source::Local, "work", &state, &changes)
+            .unwrap();
+
+        assert_eq!(cached_ids(&cache), ["single"]);
+    }
+
+    #[test]
+    fn full_sync_replaces_the_calendar_and_moves_the_window() {
+        let mut cache = Cache::open_in_memory().unwrap();
+        let old = window(
+            "1",
+            datetime!(2023-01-01 00:00 UTC),
+            datetime!(2025-01-01 00:00 UTC),
+        );
+        let changes = SyncChanges {
+            full: true,
+            changed: vec![standup("a"), standup("b")],
+            removed: vec![],
+            token: "1".to_string(),
+        };
+        cache
+            .apply_changes(&Source::Local, "work", &old, &changes)
+            .unwrap();
+
+        // The token expired, and the full sync that followed started a new window.
+        let new = window(
+            "2",
+            datetime!(2024-01-01 00:00 UTC),
+            datetime!(2026-01-01 00:00 UTC),
+        );
+        let changes = SyncChanges {
+            full: true,
+            changed: vec![standup("b"), standup("c")],
+            removed: vec![],
+            token: "2".to_string(),
+        };
+        cache
+            .apply_changes(&Source::Local, "work", &new, &changes)
+            .unwrap();
+
+        assert_eq!(cached_ids(&cache), ["b", "c"]);
+        let stored = cache.sync_state(&Source::Local, "work").unwrap().unwrap();
+        assert_eq!(stored.token, "2");
+        assert_eq!(stored.start, new.start);
+        assert_eq!(stored.end, new.end);
+    }
+}
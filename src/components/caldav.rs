@@ -0,0 +1,93 @@
+use cosmic::{iced::Length, theme::spacing, widget, Element};
+
+use crate::{fl, models::CalDavAccount};
+
+/// Form for adding a CalDAV server, shown in the context drawer.
+#[derive(Debug, Clone, Default)]
+pub struct CalDavForm {
+    url: String,
+    username: String,
+    password: String,
+    /// Set while the server is being contacted, to prevent adding it twice.
+    connecting: bool,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Url(String),
+    Username(String),
+    Password(String),
+    Add,
+}
+
+impl CalDavForm {
+    /// Returns the account to connect to and its password once the form is submitted.
+    pub fn update(&mut self, message: Message) -> Option<(CalDavAccount, String)> {
+        match message {
+            Message::Url(url) => self.url = url,
+            Message::Username(username) => self.username = username,
+            Message::Password(password) => self.password = password,
+            Message::Add => {
+                let url = self.url.trim();
+                if reqwest::Url::parse(url).is_err() {
+                    self.error = Some(fl!("invalid-url"));
+                    return None;
+                }
+                self.error = None;
+                self.connecting = true;
+                let account = CalDavAccount {
+                    url: url.to_string(),
+                    username: self.username.trim().to_string(),
+                };
+                return Some((account, self.password.clone()));
+            }
+        }
+        None
+    }
+
+    /// Shows why connecting to the server failed and lets the user try again.
+    pub fn fail(&mut self, error: String) {
+        self.connecting = false;
+        self.error = Some(error);
+    }
+
+    pub fn view<'a>(&'a self) -> Element<'a, Message> {
+        let mut column = widget::column()
+            .push(widget::text::body(fl!("caldav-account-description")))
+            .push(
+                widget::text_input(fl!("server-url"), &self.url)
+                    .on_input(Message::Url)
+                    .width(Length::Fill),
+            )
+            .push(
+                widget::text_input(fl!("username"), &self.username)
+                    .on_input(Message::Username)
+                    .width(Length::Fill),
+            )
+            .push(
+                widget::secure_input(fl!("password"), &self.password, None, true)
+                    .on_input(Message::Password)
+                    .width(Length::Fill),
+            );
+
+        if let Some(error) = &self.error {
+            column = column
+                .push(widget::text::caption(error.as_str()).class(cosmic::style::Text::Accent));
+        }
+
+        column
+            .push(
+                widget::row().push(widget::horizontal_space()).push(
+                    widget::button::suggested(fl!("add")).on_press_maybe(
+                        (!self.connecting
+                            && !self.url.trim().is_empty()
+                            && !self.username.trim().is_empty())
+                        .then_some(Message::Add),
+                    ),
+                ),
+            )
+            .spacing(spacing().space_s)
+            .into()
+    }
+}
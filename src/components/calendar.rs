@@ -1,59 +1,206 @@
-use crate::Result;
+use crate::{
+    config::ClockFormat,
+    i18n,
+    models::{zone, Event},
+    Result,
+};
 use cosmic::{
-    iced::{alignment::Horizontal, Length},
+    iced::{alignment::Horizontal, Color, Length},
     theme::spacing,
     widget, Element,
 };
-use time::{Month, OffsetDateTime, Weekday};
+use serde::{Deserialize, Serialize};
+use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Weekday};
+use time_tz::Tz;
 
+mod agenda;
 mod day;
+mod events;
 mod month;
 mod week;
+mod year;
+
+pub use agenda::SCROLLABLE_ID as AGENDA_ID;
+
+/// Color used for calendars that don't specify one.
+const DEFAULT_EVENT_COLOR: Color = Color::from_rgb(0.21, 0.52, 0.89);
+
+/// An event paired with the calendar it belongs to and its color, ready to be drawn.
+#[derive(Debug, Clone, Copy)]
+pub struct CalendarEvent<'a> {
+    pub event: &'a Event,
+    /// The [`calendar_key`](crate::config::calendar_key) of the calendar.
+    pub calendar: &'a str,
+    pub color: Color,
+}
+
+impl<'a> CalendarEvent<'a> {
+    /// Pairs `event` of the calendar with the key `calendar` with `color`, a `#rrggbb`
+    /// string as reported by providers.
+    pub fn new(event: &'a Event, calendar: &'a str, color: Option<&str>) -> Self {
+        Self {
+            event,
+            calendar,
+            color: color.and_then(parse_color).unwrap_or(DEFAULT_EVENT_COLOR),
+        }
+    }
+}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) fn parse_color(hex: &str) -> Option<Color> {
+    let hex = hex.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some(Color::from_rgb8(channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// A run of consecutive days shown side by side in a time grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DayRange {
+    Week,
+    /// Monday to Friday.
+    WorkWeek,
+    ThreeDays,
+    TwoWeeks,
+}
+
+impl DayRange {
+    /// How many days are shown.
+    pub fn days(self) -> i64 {
+        match self {
+            DayRange::Week => 7,
+            DayRange::WorkWeek => 5,
+            DayRange::ThreeDays => 3,
+            DayRange::TwoWeeks => 14,
+        }
+    }
+
+    /// How many days navigating forwards or backwards moves by. The work week skips
+    /// the weekend, so it moves by a whole week.
+    pub fn step(self) -> i64 {
+        match self {
+            DayRange::WorkWeek => 7,
+            range => range.days(),
+        }
+    }
+
+    /// The first day shown when `date` is selected and weeks start on `first_weekday`.
+    fn start(self, date: Date, first_weekday: Weekday) -> Date {
+        let week_start = week_start(date, first_weekday);
+        match self {
+            DayRange::Week | DayRange::TwoWeeks => week_start,
+            DayRange::WorkWeek => {
+                let to_monday = (7 - first_weekday.number_days_from_monday()) % 7;
+                week_start + time::Duration::days(to_monday as i64)
+            }
+            DayRange::ThreeDays => date,
+        }
+    }
+}
+
+/// The `first_weekday` on or before `date`.
+pub fn week_start(date: Date, first_weekday: Weekday) -> Date {
+    let days = (date.weekday().number_days_from_monday() + 7
+        - first_weekday.number_days_from_monday())
+        % 7;
+    date - time::Duration::days(days as i64)
+}
+
+/// The number of the week starting on `start`. Weeks starting on Monday are
+/// numbered per ISO 8601, where the first week is the one with the year's first
+/// Thursday. Otherwise the first week is the one with January 1st.
+pub fn week_number(start: Date, first_weekday: Weekday) -> u8 {
+    if first_weekday == Weekday::Monday {
+        return start.iso_week();
+    }
+    let week_end = start + time::Duration::days(6);
+    let Ok(new_year) = Date::from_ordinal_date(week_end.year(), 1) else {
+        return 1;
+    };
+    let first_week = week_start(new_year, first_weekday);
+    ((start - first_week).whole_weeks() + 1) as u8
+}
+
+/// The days of the week in order, starting on `first_weekday`.
+pub fn weekdays(first_weekday: Weekday) -> impl Iterator<Item = Weekday> {
+    (0..7).map(move |day| first_weekday.nth_next(day))
+}
+
+#[derive(Debug, Clone, Copy)]
 pub struct LocalCalendar {
+    /// The zone the calendar is displayed in. Both dates below are in it,
+    /// and events are converted to it before being laid out.
+    pub zone: &'static Tz,
     pub current_date: OffsetDateTime,
     pub selected_date: OffsetDateTime,
+    /// The day weeks start on in the grids.
+    pub first_weekday: Weekday,
+    /// Whether the month and week views show the number of each week.
+    pub show_week_numbers: bool,
+    pub clock: ClockFormat,
+    /// The hours left unshaded in the time grids, from the first up to the second.
+    pub working_hours: (u8, u8),
+    /// The days listed in the agenda, which grow as it is scrolled.
+    agenda_start: Date,
+    agenda_end: Date,
 }
 
 impl Default for LocalCalendar {
     fn default() -> Self {
-        let current_date = match OffsetDateTime::now_local() {
-            Ok(date) => date,
-            Err(err) => {
-                tracing::error!("Failed to get local time: {}", err);
-                panic!("Failed to get local time")
-            }
-        };
-        Self {
-            current_date,
-            selected_date: current_date,
-        }
+        Self::new(zone::system())
     }
 }
 
 impl LocalCalendar {
-    #[allow(unused)]
-    pub fn new(current_date: OffsetDateTime) -> Self {
+    pub fn new(zone: &'static Tz) -> Self {
+        let current_date = zone::now(zone);
         Self {
+            zone,
             current_date,
             selected_date: current_date,
+            first_weekday: i18n::first_weekday(),
+            show_week_numbers: false,
+            clock: ClockFormat::default(),
+            working_hours: (9, 17),
+            agenda_start: current_date.date(),
+            agenda_end: current_date.date() + time::Duration::days(agenda::DAYS_PER_PAGE),
         }
     }
 
-    pub fn month_view<'a>(&'a self) -> impl Into<Element<'a, crate::app::Message>> {
-        let weekday_headers = widget::row::with_children(vec![
-            month::weekday_header("Sun"),
-            month::weekday_header("Mon"),
-            month::weekday_header("Tue"),
-            month::weekday_header("Wed"),
-            month::weekday_header("Thu"),
-            month::weekday_header("Fri"),
-            month::weekday_header("Sat"),
-        ])
-        .spacing(spacing().space_xxs);
+    /// The same wall-clock time as `date_time`, with the offset `zone` has on that day.
+    /// Adding days or months keeps the offset of the starting date, which is wrong
+    /// once a daylight saving change is crossed.
+    fn localize(&self, date_time: OffsetDateTime) -> OffsetDateTime {
+        zone::resolve(
+            PrimitiveDateTime::new(date_time.date(), date_time.time()),
+            self.zone,
+        )
+    }
+
+    pub fn month_view<'a>(
+        &'a self,
+        events: &[CalendarEvent<'_>],
+    ) -> impl Into<Element<'a, crate::app::Message>> {
+        let mut weekday_headers = widget::row().spacing(spacing().space_xxs);
+        if self.show_week_numbers {
+            weekday_headers =
+                weekday_headers.push(widget::Space::new(month::WEEK_NUMBER_WIDTH, Length::Shrink));
+        }
+        for weekday in weekdays(self.first_weekday) {
+            weekday_headers =
+                weekday_headers.push(month::weekday_header(i18n::weekday_short(weekday)));
+        }
 
-        let calendar_grid = month::month_grid(&self.selected_date, &self.current_date);
+        let calendar_grid = month::month_grid(
+            &self.selected_date,
+            &self.current_date,
+            self.first_weekday,
+            self.show_week_numbers,
+            self.clock,
+            self.zone,
+            events,
+        );
 
         widget::column()
             .push(weekday_headers)
@@ -64,43 +211,75 @@ impl LocalCalendar {
             .padding([0, 0, spacing().space_xxs, 0])
     }
 
-    pub fn week_view<'a>(&'a self) -> impl Into<Element<'a, crate::app::Message>> {
-        let selected_date = self.selected_date;
-        let days_since_sunday = match selected_date.weekday() {
-            Weekday::Sunday => 0,
-            Weekday::Monday => 1,
-            Weekday::Tuesday => 2,
-            Weekday::Wednesday => 3,
-            Weekday::Thursday => 4,
-            Weekday::Friday => 5,
-            Weekday::Saturday => 6,
-        };
+    /// The week of the selected date, with the hours in each of `secondary_zones`
+    /// shown next to those of the display zone.
+    pub fn week_view<'a>(
+        &'a self,
+        events: &[CalendarEvent<'_>],
+        secondary_zones: &[&Tz],
+    ) -> impl Into<Element<'a, crate::app::Message>> {
+        self.range_view(DayRange::Week, events, secondary_zones)
+    }
 
-        let week_start = selected_date
-            .checked_sub(time::Duration::days(days_since_sunday as i64))
-            .unwrap();
+    /// The days of `range` around the selected date side by side, laid out like the week view.
+    pub fn range_view<'a>(
+        &'a self,
+        range: DayRange,
+        events: &[CalendarEvent<'_>],
+        secondary_zones: &[&Tz],
+    ) -> impl Into<Element<'a, crate::app::Message>> {
+        let start = range.start(self.selected_date.date(), self.first_weekday);
+        let start_time = zone::midnight(start, self.zone);
+        let days = range.days();
 
         let mut header_row = widget::row().padding([0, spacing().space_xs, 0, 0]);
+        for other in secondary_zones {
+            header_row = header_row.push(week::zone_header(
+                zone::abbreviation(other, start_time),
+                Length::Fixed(week::ZONE_COLUMN_WIDTH),
+            ));
+        }
+        let abbreviation =
+            (!secondary_zones.is_empty()).then(|| zone::abbreviation(self.zone, start_time));
+        header_row = header_row.push(if self.show_week_numbers {
+            week::week_number_header(
+                week_number(week_start(start, self.first_weekday), self.first_weekday),
+                abbreviation,
+                start_time,
+            )
+        } else if let Some(abbreviation) = abbreviation {
+            week::zone_header(abbreviation, Length::Fill)
+        } else {
+            week::empty_day_header()
+        });
 
-        let day_names = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
-        for i in -1..7 {
-            if i == -1 {
-                header_row = header_row.push(week::empty_day_header());
-                continue;
-            }
-
-            let day_date = week_start.checked_add(time::Duration::days(i)).unwrap();
-            let is_today = day_date.date() == self.current_date.date();
+        for i in 0..days {
+            let day_date = start + time::Duration::days(i);
+            let is_today = day_date == self.current_date.date();
 
-            let day_header =
-                week::day_header(day_names[i as usize], day_date.day(), is_today, day_date);
+            let day_header = week::day_header(
+                i18n::weekday_short(day_date.weekday()),
+                day_date.day(),
+                is_today,
+                zone::midnight(day_date, self.zone),
+            );
             header_row = header_row.push(day_header);
         }
 
-        let time_grid = week::week_grid(&week_start);
+        let all_day_row = week::all_day_row(start, days, self.zone, secondary_zones.len(), events);
+        let time_grid = week::week_grid(
+            start,
+            days,
+            self.zone,
+            secondary_zones,
+            events,
+            self.clock,
+            self.working_hours,
+        );
 
         widget::column()
             .push(header_row)
+            .push(all_day_row)
             .push(
                 widget::scrollable(time_grid)
                     .height(Length::Fill)
@@ -109,14 +288,150 @@ impl LocalCalendar {
             .padding([0, 0, spacing().space_xxs, 0])
     }
 
-    pub fn day_view<'a>(&'a self) -> impl Into<Element<'a, crate::app::Message>> {
-        let time_grid = day::single_day_time_grid(&self.selected_date);
+    /// The selected day, with the hours in each of `secondary_zones` shown next to
+    /// those of the display zone.
+    pub fn day_view<'a>(
+        &'a self,
+        events: &[CalendarEvent<'_>],
+        secondary_zones: &[&Tz],
+    ) -> impl Into<Element<'a, crate::app::Message>> {
+        let all_day_row = day::all_day_row(&self.selected_date, self.zone, secondary_zones, events);
+        let time_grid = day::single_day_time_grid(
+            &self.selected_date,
+            self.zone,
+            secondary_zones,
+            events,
+            self.clock,
+            self.working_hours,
+        );
 
         widget::column()
+            .push(all_day_row)
             .push(widget::scrollable(time_grid).height(Length::Fill))
             .padding([0, 0, spacing().space_xxs, 0])
     }
 
+    pub fn year_view<'a>(
+        &'a self,
+        events: &[CalendarEvent<'_>],
+    ) -> impl Into<Element<'a, crate::app::Message>> {
+        year::year_grid(
+            &self.selected_date,
+            &self.current_date,
+            self.first_weekday,
+            self.zone,
+            events,
+        )
+    }
+
+    /// The upcoming events grouped by day, in a list that reports its scroll position
+    /// through [`Message::AgendaScrolled`](crate::app::Message::AgendaScrolled).
+    pub fn agenda_view<'a>(
+        &'a self,
+        events: &[CalendarEvent<'_>],
+    ) -> impl Into<Element<'a, crate::app::Message>> {
+        let list = agenda::agenda_list(
+            self.agenda_start,
+            self.agenda_end,
+            self.current_date.date(),
+            self.zone,
+            self.clock,
+            events,
+        );
+
+        widget::scrollable(list)
+            .id(agenda::SCROLLABLE_ID.clone())
+            .on_scroll(|viewport| {
+                crate::app::Message::AgendaScrolled(
+                    viewport.absolute_offset().y,
+                    viewport.relative_offset().y,
+                )
+            })
+            .height(Length::Fill)
+            .width(Length::Fill)
+    }
+
+    /// The time range shown in the view of `range`.
+    pub fn range_range(&self, range: DayRange) -> (OffsetDateTime, OffsetDateTime) {
+        let start = range.start(self.selected_date.date(), self.first_weekday);
+        let end = start + time::Duration::days(range.days());
+        (
+            zone::midnight(start, self.zone),
+            zone::midnight(end, self.zone),
+        )
+    }
+
+    /// The time range of the year of the selected date.
+    pub fn year_range(&self) -> (OffsetDateTime, OffsetDateTime) {
+        let year = self.selected_date.year();
+        let start = Date::from_ordinal_date(year, 1).unwrap_or(self.selected_date.date());
+        let end = Date::from_ordinal_date(year + 1, 1).unwrap_or(start + time::Duration::weeks(53));
+        (
+            zone::midnight(start, self.zone),
+            zone::midnight(end, self.zone),
+        )
+    }
+
+    /// The time range listed in the agenda.
+    pub fn agenda_range(&self) -> (OffsetDateTime, OffsetDateTime) {
+        (
+            zone::midnight(self.agenda_start, self.zone),
+            zone::midnight(self.agenda_end, self.zone),
+        )
+    }
+
+    /// Lists a page of days on either side of the selected date in the agenda,
+    /// and returns the selected date, which should be scrolled to.
+    pub fn reset_agenda(&mut self) -> Date {
+        let date = self.selected_date.date();
+        self.agenda_start = date - time::Duration::days(agenda::DAYS_PER_PAGE);
+        self.agenda_end = date + time::Duration::days(agenda::DAYS_PER_PAGE);
+        date
+    }
+
+    /// Adds a page of days below the agenda when it was scrolled near the end, or above
+    /// it when it was scrolled to the top. In that case returns the day that was first
+    /// before, which should stay where it was on screen.
+    pub fn agenda_scrolled(&mut self, offset: f32, relative: f32) -> Option<Date> {
+        let page = time::Duration::days(agenda::DAYS_PER_PAGE);
+        if relative >= agenda::LOAD_THRESHOLD {
+            self.agenda_end += page;
+            None
+        } else if offset <= 0.0 {
+            let previous = self.agenda_start;
+            self.agenda_start -= page;
+            Some(previous)
+        } else {
+            None
+        }
+    }
+
+    /// How far down the agenda `date` starts, given the events currently listed.
+    pub fn agenda_offset(&self, date: Date, events: &[CalendarEvent<'_>]) -> f32 {
+        agenda::height(self.agenda_start, date, self.zone, events)
+    }
+
+    /// The time range covered by the month grid of the selected date, which also
+    /// contains the selected week and day.
+    pub fn visible_range(&self) -> (OffsetDateTime, OffsetDateTime) {
+        let start = month::grid_start(&self.selected_date, self.first_weekday).date();
+        let end = start + time::Duration::weeks(6);
+        (
+            zone::midnight(start, self.zone),
+            zone::midnight(end, self.zone),
+        )
+    }
+
+    /// Moves the selected date by `days`, forwards or backwards.
+    pub fn shift_days(&mut self, days: i64) -> Result<()> {
+        let new_date = self
+            .selected_date
+            .checked_add(time::Duration::days(days))
+            .ok_or_else(|| crate::Error::DateCalculation("Failed to shift date".into()))?;
+        self.selected_date = self.localize(new_date);
+        Ok(())
+    }
+
     pub fn set_today(&mut self) {
         self.selected_date = self.current_date;
     }
@@ -126,7 +441,7 @@ impl LocalCalendar {
             .selected_date
             .checked_add(time::Duration::days(1))
             .ok_or_else(|| crate::Error::DateCalculation("Failed to calculate next day".into()))?;
-        self.selected_date = new_date;
+        self.selected_date = self.localize(new_date);
         Ok(())
     }
 
@@ -137,35 +452,35 @@ impl LocalCalendar {
             .ok_or_else(|| {
                 crate::Error::DateCalculation("Failed to calculate previous day".into())
             })?;
-        self.selected_date = new_date;
+        self.selected_date = self.localize(new_date);
         Ok(())
     }
 
     pub fn next_month(&mut self) -> Result<()> {
         let next_month = self.selected_date.month().next();
         let new_date = self.selected_date.replace_month(next_month)?;
-        self.selected_date = new_date;
+        self.selected_date = self.localize(new_date);
         Ok(())
     }
 
     pub fn previous_month(&mut self) -> Result<()> {
         let previous_month = self.selected_date.month().previous();
         let new_date = self.selected_date.replace_month(previous_month)?;
-        self.selected_date = new_date;
+        self.selected_date = self.localize(new_date);
         Ok(())
     }
 
     pub fn next_year(&mut self) -> Result<()> {
         let next_year = self.selected_date.year();
         let new_date = self.selected_date.replace_year(next_year + 1)?;
-        self.selected_date = new_date;
+        self.selected_date = self.localize(new_date);
         Ok(())
     }
 
     pub fn previous_year(&mut self) -> Result<()> {
         let next_year = self.selected_date.year();
         let new_date = self.selected_date.replace_year(next_year - 1)?;
-        self.selected_date = new_date;
+        self.selected_date = self.localize(new_date);
         Ok(())
     }
 
@@ -174,7 +489,7 @@ impl LocalCalendar {
     }
 
     pub fn set_date(&mut self, date: OffsetDateTime) {
-        self.selected_date = date;
+        self.selected_date = self.localize(date);
     }
 
     pub fn months(&self) -> [Month; 12] {
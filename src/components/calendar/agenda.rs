@@ -0,0 +1,178 @@
+use std::sync::LazyLock;
+
+use cosmic::{
+    iced::{widget::container, Alignment, Background, Border, Color, Length},
+    theme::spacing,
+    widget, Apply, Element,
+};
+use time::{Date, Duration};
+use time_tz::Tz;
+
+use super::{events, CalendarEvent};
+use crate::{app::Message, config::ClockFormat, fl, i18n};
+
+/// Identifies the scrollable of the agenda, to keep its position when days are added above.
+pub static SCROLLABLE_ID: LazyLock<widget::Id> = LazyLock::new(|| widget::Id::new("agenda"));
+
+/// How many days the agenda grows by when scrolled to either end.
+pub const DAYS_PER_PAGE: i64 = 30;
+
+/// How far down the list, as a fraction of its length, more days are added below.
+pub const LOAD_THRESHOLD: f32 = 0.9;
+
+/// Height of the date heading of each day.
+const HEADER_HEIGHT: f32 = 36.0;
+
+/// Height of each event, and of the "no events" line of empty days.
+const ROW_HEIGHT: f32 = 40.0;
+
+/// Lists the days from `start` to `end` (exclusive) with their events, or a
+/// "no events" line for the days without any.
+pub fn agenda_list<'a>(
+    start: Date,
+    end: Date,
+    today: Date,
+    zone: &Tz,
+    clock: ClockFormat,
+    events: &[CalendarEvent<'_>],
+) -> Element<'a, Message> {
+    let mut column = widget::column()
+        .width(Length::Fill)
+        .padding([0, spacing().space_s]);
+
+    let mut date = start;
+    while date < end {
+        column = column.push(day_header(date, today));
+        let day_events = day_events(date, zone, events);
+        if day_events.is_empty() {
+            column = column.push(
+                widget::text::body(fl!("no-events"))
+                    .class(cosmic::style::Text::Color(Color::from_rgb(0.5, 0.5, 0.5)))
+                    .apply(widget::container)
+                    .center_y(ROW_HEIGHT),
+            );
+        }
+        for event in day_events {
+            column = column.push(event_row(event, date, zone, clock));
+        }
+        date += Duration::DAY;
+    }
+
+    column.into()
+}
+
+/// The height the days from `start` to `end` take in the list, so the scroll
+/// position can be kept when days are added above them.
+pub fn height(start: Date, end: Date, zone: &Tz, events: &[CalendarEvent<'_>]) -> f32 {
+    let mut height = 0.0;
+    let mut date = start;
+    while date < end {
+        let rows = day_events(date, zone, events).len().max(1);
+        height += HEADER_HEIGHT + rows as f32 * ROW_HEIGHT;
+        date += Duration::DAY;
+    }
+    height
+}
+
+/// The events on `date`, all-day ones first and the rest by start.
+fn day_events<'a, 'b>(
+    date: Date,
+    zone: &Tz,
+    events: &'b [CalendarEvent<'a>],
+) -> Vec<&'b CalendarEvent<'a>> {
+    let mut day_events: Vec<_> = events
+        .iter()
+        .filter(|e| {
+            let (first, last) = events::day_span(e.event, zone);
+            (first..=last).contains(&date)
+        })
+        .collect();
+    day_events.sort_by_key(|e| (!e.event.is_all_day(), e.event.start.in_zone(zone)));
+    day_events
+}
+
+fn day_header<'a>(date: Date, today: Date) -> Element<'a, Message> {
+    let label = format!(
+        "{} {} {}",
+        i18n::weekday(date.weekday()),
+        date.day(),
+        i18n::month(date.month())
+    );
+    let text = if date == today {
+        widget::text::heading(label).class(cosmic::style::Text::Accent)
+    } else {
+        widget::text::heading(label)
+    };
+
+    widget::container(text)
+        .width(Length::Fill)
+        .align_bottom(HEADER_HEIGHT)
+        .padding([0, 0, spacing().space_xxxs, 0])
+        .into()
+}
+
+/// An event of `date`, with a bar in the color of its calendar, the time it
+/// takes that day, its title and location.
+fn event_row<'a>(
+    event: &CalendarEvent<'_>,
+    date: Date,
+    zone: &Tz,
+    clock: ClockFormat,
+) -> Element<'a, Message> {
+    let color = event.color;
+    let bar = widget::container(widget::Space::new(4, Length::Fill)).class(
+        cosmic::theme::Container::custom(move |_theme| container::Style {
+            background: Some(Background::Color(color)),
+            border: Border {
+                radius: 2.0.into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }),
+    );
+
+    let mut details = widget::column().push(widget::text::body(event.event.summary.clone()));
+    if let Some(location) = &event.event.location {
+        details = details.push(widget::text::caption(location.clone()));
+    }
+
+    let content = widget::row()
+        .push(bar)
+        .push(widget::text::body(time_range(event, date, zone, clock)).width(Length::Fixed(120.0)))
+        .push(details)
+        .spacing(spacing().space_xs)
+        .align_y(Alignment::Center)
+        .height(Length::Fill);
+
+    widget::button::custom(content)
+        .width(Length::Fill)
+        .height(ROW_HEIGHT)
+        .padding([spacing().space_xxxs, 0])
+        .class(cosmic::style::Button::Text)
+        .on_press(Message::OpenEvent(
+            event.calendar.to_string(),
+            event.event.id.clone(),
+        ))
+        .into()
+}
+
+/// When an event takes place on `date`, with "…" for the ends that fall on other days.
+fn time_range(event: &CalendarEvent<'_>, date: Date, zone: &Tz, clock: ClockFormat) -> String {
+    if event.event.is_all_day() {
+        return fl!("all-day");
+    }
+    let (day_start, day_end) = events::day_bounds(date, zone);
+    let start = event.event.start.in_zone(zone);
+    let end = event.event.end.in_zone(zone);
+    let start = if start >= day_start {
+        events::time_label(start, clock)
+    } else {
+        "…".to_string()
+    };
+    let end = if end <= day_end {
+        events::time_label(end, clock)
+    } else {
+        "…".to_string()
+    };
+    format!("{start} – {end}")
+}
@@ -1,73 +1,146 @@
 use cosmic::{
-    iced::{alignment::Horizontal, Length},
+    iced::{
+        alignment::Horizontal,
+        widget::{Space, Stack},
+        Length,
+    },
     theme::spacing,
-    widget, Element,
+    widget, Apply, Element,
 };
 use time::OffsetDateTime;
+use time_tz::Tz;
 
+use super::{events, week, CalendarEvent};
+use crate::{config::ClockFormat, models::zone};
+
+/// Height of each 30 minute slot in the day grid.
+const SLOT_HEIGHT: f32 = 80.0;
+
+/// Width of the column with the hours of the display zone.
+const LABEL_WIDTH: f32 = 100.0;
+
+/// The time grid of the selected day, with the hours of each of `secondary_zones`
+/// in columns left of the hours of `zone`. The hours outside `working_hours` are shaded.
 pub fn single_day_time_grid<'a>(
     selected_date: &OffsetDateTime,
+    zone: &Tz,
+    secondary_zones: &[&Tz],
+    events: &[CalendarEvent<'_>],
+    clock: ClockFormat,
+    working_hours: (u8, u8),
 ) -> impl Into<Element<'a, crate::app::Message>> {
-    let mut main_column = widget::column().spacing(0);
+    let date = selected_date.date();
+    let mut cells = widget::column().width(Length::Fill);
 
     for hour in 0..24 {
-        main_column = main_column.push(widget::divider::horizontal::default());
-
-        let hour_row = single_day_hour_row(hour, false, selected_date);
-        main_column = main_column.push(hour_row);
+        let is_working = week::is_working_hour(hour, working_hours);
+        cells = cells
+            .push(widget::divider::horizontal::default())
+            .push(single_day_time_cell(
+                hour,
+                false,
+                selected_date,
+                zone,
+                is_working,
+            ))
+            .push(widget::divider::horizontal::light())
+            .push(single_day_time_cell(
+                hour,
+                true,
+                selected_date,
+                zone,
+                is_working,
+            ));
+    }
 
-        main_column = main_column.push(widget::divider::horizontal::light());
+    let day_column = Stack::with_children(vec![
+        cells.into(),
+        events::timed_layer(date, zone, events, SLOT_HEIGHT, clock),
+    ])
+    .width(Length::Fill);
 
-        let half_hour_row = single_day_hour_row(hour, true, selected_date);
-        main_column = main_column.push(half_hour_row);
+    let mut row = widget::row();
+    for other in secondary_zones {
+        row = row.push(hour_labels(
+            week::ZONE_COLUMN_WIDTH,
+            |hour, is_half_hour| week::zone_time(date, hour, is_half_hour, zone, other, clock),
+        ));
     }
-
-    main_column
+    row.push(hour_labels(LABEL_WIDTH, |hour, is_half_hour| {
+        clock.format(hour, if is_half_hour { 30 } else { 0 })
+    }))
+    .push(widget::divider::vertical::default())
+    .push(day_column)
 }
 
-fn single_day_hour_row<'a>(
-    hour: u8,
-    is_half_hour: bool,
+/// The all-day events of the selected day, shown above the time grid.
+/// When secondary zones are shown, the space above the hour labels holds the
+/// abbreviations of the zones.
+pub fn all_day_row<'a>(
     selected_date: &OffsetDateTime,
+    zone: &Tz,
+    secondary_zones: &[&Tz],
+    events: &[CalendarEvent<'_>],
 ) -> impl Into<Element<'a, crate::app::Message>> {
     let mut row = widget::row();
-
-    let time_label = if is_half_hour {
-        format!("{:02}:30", hour)
+    if secondary_zones.is_empty() {
+        row = row.push(Space::new(LABEL_WIDTH, Length::Shrink));
     } else {
-        format!("{:02}:00", hour)
-    };
-
-    let time_container =
-        widget::container(widget::text::body(time_label).align_x(Horizontal::Right))
-            .width(100)
-            .height(40)
-            .center_y(Length::Fill)
-            .padding([0, spacing().space_s, 0, 0]);
-
-    row = row.push(time_container);
-    row = row.push(widget::divider::vertical::default());
+        for other in secondary_zones {
+            row = row.push(week::zone_header(
+                zone::abbreviation(other, *selected_date),
+                Length::Fixed(week::ZONE_COLUMN_WIDTH),
+            ));
+        }
+        row = row.push(week::zone_header(
+            zone::abbreviation(zone, *selected_date),
+            Length::Fixed(LABEL_WIDTH),
+        ));
+    }
+    row.push(events::all_day_column(selected_date.date(), zone, events))
+}
 
-    let time_cell = single_day_time_cell(hour, is_half_hour, selected_date);
-    row = row.push(time_cell);
+/// A column with a label for every half hour of the day.
+fn hour_labels<'a>(
+    width: f32,
+    label: impl Fn(u8, bool) -> String,
+) -> Element<'a, crate::app::Message> {
+    let mut column = widget::column().width(width);
+    for hour in 0..24 {
+        column = column
+            .push(widget::divider::horizontal::default())
+            .push(single_day_hour_label(label(hour, false), width))
+            .push(widget::divider::horizontal::light())
+            .push(single_day_hour_label(label(hour, true), width));
+    }
+    column.into()
+}
 
-    row.height(80)
+fn single_day_hour_label<'a>(label: String, width: f32) -> Element<'a, crate::app::Message> {
+    widget::container(widget::text::body(label).align_x(Horizontal::Right))
+        .width(width)
+        .center_y(SLOT_HEIGHT)
+        .padding([0, spacing().space_s, 0, 0])
+        .into()
 }
 
 fn single_day_time_cell<'a>(
     hour: u8,
     is_half_hour: bool,
     date: &OffsetDateTime,
+    zone: &Tz,
+    is_working: bool,
 ) -> impl Into<Element<'a, crate::app::Message>> {
-    let minute = if is_half_hour { 30 } else { 0 };
-    let cell_time = date
-        .replace_hour(hour)
-        .and_then(|d| d.replace_minute(minute))
-        .unwrap_or(*date);
+    let cell_time = week::slot_time(date.date(), hour, is_half_hour, zone);
 
-    widget::button::text("")
+    widget::container(widget::text(""))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .class(week::off_hours_style(is_working))
+        .apply(widget::button::custom)
+        .padding(0)
         .width(Length::Fill)
-        .height(80)
+        .height(SLOT_HEIGHT)
         .class(cosmic::style::Button::Text)
         .on_press(crate::app::Message::AddEvent(cell_time))
 }
@@ -0,0 +1,258 @@
+use cosmic::{
+    iced::{
+        widget::{container, Space},
+        Background, Border, Color, Length,
+    },
+    theme::spacing,
+    widget, Element,
+};
+use time::{Date, Duration, OffsetDateTime};
+use time_tz::{OffsetDateTimeExt, Tz};
+
+use super::CalendarEvent;
+use crate::{
+    app::Message,
+    config::ClockFormat,
+    models::{zone, Event, EventTime},
+};
+
+/// Height of the horizontal dividers drawn between time slots.
+pub const DIVIDER_HEIGHT: f32 = 1.0;
+
+/// Height of the single-line chips used for all-day events and in month cells.
+pub const CHIP_HEIGHT: f32 = 20.0;
+
+/// Events shorter than this are drawn as if they lasted this long, so they stay clickable.
+const MIN_EVENT_MINUTES: i64 = 15;
+
+/// The part of an event that falls within one day, in minutes since midnight.
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    start: i64,
+    end: i64,
+    index: usize,
+}
+
+/// A run of segments that overlap each other, split into lanes drawn side by side.
+struct Cluster {
+    start: i64,
+    end: i64,
+    lanes: Vec<Vec<Segment>>,
+}
+
+/// Draws the timed events of `date` as blocks positioned by their start and end,
+/// meant to be stacked on top of a column of 30 minute slots of `slot_height`.
+/// Blocks are placed by their wall-clock time in `zone`, so on days when clocks
+/// change they still line up with the hour labels.
+pub fn timed_layer<'a>(
+    date: Date,
+    zone: &Tz,
+    events: &[CalendarEvent<'_>],
+    slot_height: f32,
+    clock: ClockFormat,
+) -> Element<'a, Message> {
+    let pixels_per_minute = (slot_height + DIVIDER_HEIGHT) / 30.0;
+    let height = |minutes: i64| Length::Fixed(minutes as f32 * pixels_per_minute);
+
+    let (day_start, day_end) = day_bounds(date, zone);
+    let segments = events
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| !e.event.is_all_day() && e.event.overlaps(day_start, day_end))
+        .map(|(index, e)| {
+            let start = minute_of_day(e.event.start.in_zone(zone).max(day_start), date);
+            let end = minute_of_day(e.event.end.in_zone(zone).min(day_end), date);
+            Segment {
+                start,
+                end: end.max(start + MIN_EVENT_MINUTES).min(24 * 60),
+                index,
+            }
+        })
+        .collect();
+
+    let mut layer = widget::column().width(Length::Fill);
+    let mut cursor = 0;
+    for cluster in clusters(segments) {
+        layer = layer.push(Space::new(Length::Fill, height(cluster.start - cursor)));
+
+        let mut lanes = widget::row()
+            .height(height(cluster.end - cluster.start))
+            .spacing(spacing().space_xxxs);
+        for lane in cluster.lanes {
+            let mut column = widget::column().width(Length::FillPortion(1));
+            let mut lane_cursor = cluster.start;
+            for segment in lane {
+                let event = &events[segment.index];
+                column = column
+                    .push(Space::new(
+                        Length::Fill,
+                        height(segment.start - lane_cursor),
+                    ))
+                    .push(
+                        event_block(event, zone, clock)
+                            .width(Length::Fill)
+                            .height(height(segment.end - segment.start)),
+                    );
+                lane_cursor = segment.end;
+            }
+            lanes = lanes.push(column);
+        }
+
+        layer = layer.push(lanes);
+        cursor = cluster.end;
+    }
+
+    layer.into()
+}
+
+/// Lists the all-day events of `date` as chips, for the strip above the time grid.
+pub fn all_day_column<'a>(
+    date: Date,
+    zone: &Tz,
+    events: &[CalendarEvent<'_>],
+) -> Element<'a, Message> {
+    let (day_start, day_end) = day_bounds(date, zone);
+
+    events
+        .iter()
+        .filter(|e| e.event.is_all_day() && e.event.overlaps(day_start, day_end))
+        .fold(
+            widget::column().spacing(spacing().space_xxxs),
+            |column, event| column.push(event_chip(event, None)),
+        )
+        .width(Length::Fill)
+        .padding(spacing().space_xxxs)
+        .into()
+}
+
+/// A block showing the start time and title of a timed event.
+fn event_block<'a>(
+    event: &CalendarEvent<'_>,
+    zone: &Tz,
+    clock: ClockFormat,
+) -> widget::Button<'a, Message> {
+    let start = event.event.start.in_zone(zone);
+    let content = widget::column()
+        .push(widget::text::caption(event.event.summary.clone()).font(cosmic::font::bold()))
+        .push(widget::text::caption(time_label(start, clock)));
+
+    colored_button(content, event)
+}
+
+/// A single-line chip with an optional time prefix, used for all-day and month cells.
+pub fn event_chip<'a>(
+    event: &CalendarEvent<'_>,
+    time: Option<String>,
+) -> widget::Button<'a, Message> {
+    let label = match time {
+        Some(time) => format!("{} {}", time, event.event.summary),
+        None => event.event.summary.clone(),
+    };
+    colored_button(widget::text::caption(label), event)
+        .width(Length::Fill)
+        .height(CHIP_HEIGHT)
+}
+
+/// The first and last day an event covers, seen from `zone`.
+pub fn day_span(event: &Event, zone: &Tz) -> (Date, Date) {
+    let first = event.start.date_in(zone);
+    let last = match event.end {
+        EventTime::Date(end) => end.previous_day().unwrap_or(end),
+        EventTime::DateTime(end) => (end - Duration::NANOSECOND).to_timezone(zone).date(),
+    };
+    (first, last.max(first))
+}
+
+/// The instants `date` starts and ends in `zone`, which are 23 or 25 hours apart
+/// when clocks change that day.
+pub fn day_bounds(date: Date, zone: &Tz) -> (OffsetDateTime, OffsetDateTime) {
+    let start = zone::midnight(date, zone);
+    let end = date
+        .next_day()
+        .map(|next| zone::midnight(next, zone))
+        .unwrap_or(start + Duration::DAY);
+    (start, end)
+}
+
+/// Minutes since midnight on the clock of `time`, which is clamped to `date`.
+fn minute_of_day(time: OffsetDateTime, date: Date) -> i64 {
+    if time.date() > date {
+        24 * 60
+    } else {
+        time.hour() as i64 * 60 + time.minute() as i64
+    }
+}
+
+fn colored_button<'a>(
+    content: impl Into<Element<'a, Message>>,
+    event: &CalendarEvent<'_>,
+) -> widget::Button<'a, Message> {
+    let color = event.color;
+    let block = widget::container(content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding([0, spacing().space_xxs])
+        .clip(true)
+        .class(cosmic::theme::Container::custom(move |_theme| {
+            container::Style {
+                background: Some(Background::Color(color)),
+                text_color: Some(text_color(color)),
+                border: Border {
+                    radius: 4.0.into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        }));
+
+    widget::button::custom(block)
+        .padding(0)
+        .class(cosmic::style::Button::Text)
+        .on_press(Message::OpenEvent(
+            event.calendar.to_string(),
+            event.event.id.clone(),
+        ))
+}
+
+pub fn time_label(time: OffsetDateTime, clock: ClockFormat) -> String {
+    clock.format(time.hour(), time.minute())
+}
+
+/// Picks black or white text depending on how light the background is.
+fn text_color(background: Color) -> Color {
+    let luminance = 0.299 * background.r + 0.587 * background.g + 0.114 * background.b;
+    if luminance > 0.6 {
+        Color::BLACK
+    } else {
+        Color::WHITE
+    }
+}
+
+/// Groups overlapping segments and assigns each one to the first lane that is free
+/// at its start, so overlapping events end up side by side.
+fn clusters(mut segments: Vec<Segment>) -> Vec<Cluster> {
+    segments.sort_by_key(|s| (s.start, -s.end));
+
+    let mut clusters: Vec<Cluster> = vec![];
+    for segment in segments {
+        match clusters.last_mut() {
+            Some(cluster) if segment.start < cluster.end => {
+                cluster.end = cluster.end.max(segment.end);
+                let free_lane = cluster
+                    .lanes
+                    .iter_mut()
+                    .find(|lane| lane.last().is_some_and(|last| last.end <= segment.start));
+                match free_lane {
+                    Some(lane) => lane.push(segment),
+                    None => cluster.lanes.push(vec![segment]),
+                }
+            }
+            _ => clusters.push(Cluster {
+                start: segment.start,
+                end: segment.end,
+                lanes: vec![vec![segment]],
+            }),
+        }
+    }
+    clusters
+}
@@ -1,11 +1,34 @@
 use cosmic::{
-    iced::{Color, Length},
+    iced::{
+        widget::{Space, Stack},
+        Color, Length,
+    },
     theme::spacing,
     widget, Apply, Element,
 };
-use time::{OffsetDateTime, Weekday};
+use time::{Date, Duration, OffsetDateTime, Weekday};
+use time_tz::Tz;
 
-pub fn weekday_header<'a>(day: &'static str) -> Element<'a, crate::app::Message> {
+use super::{events, CalendarEvent};
+use crate::{config::ClockFormat, fl, models::zone};
+
+/// Height reserved at the top of each cell for the day number.
+const DAY_NUMBER_HEIGHT: f32 = 24.0;
+
+/// How many bars and chips fit in a cell before the rest is folded into "+N more".
+const MAX_ROWS: usize = 3;
+
+/// Width of the column with the week numbers left of the grid.
+pub const WEEK_NUMBER_WIDTH: f32 = 32.0;
+
+/// A multi-day event clipped to one week row, as column indices within that row.
+struct Bar {
+    first: usize,
+    last: usize,
+    index: usize,
+}
+
+pub fn weekday_header<'a>(day: String) -> Element<'a, crate::app::Message> {
     widget::text::body(day)
         .apply(widget::container)
         .width(Length::Fill)
@@ -14,41 +37,48 @@ pub fn weekday_header<'a>(day: &'static str) -> Element<'a, crate::app::Message>
         .into()
 }
 
+/// The first day shown in the month grid, which is the `first_weekday` on or before
+/// the first of the selected month.
+pub fn grid_start(selected_date: &OffsetDateTime, first_weekday: Weekday) -> OffsetDateTime {
+    let first_of_month = selected_date.replace_day(1).unwrap();
+    let start = super::week_start(first_of_month.date(), first_weekday);
+    first_of_month.replace_date(start)
+}
+
 pub fn month_grid<'a>(
     selected_date: &OffsetDateTime,
     current_date: &OffsetDateTime,
+    first_weekday: Weekday,
+    show_week_numbers: bool,
+    clock: ClockFormat,
+    zone: &Tz,
+    events: &[CalendarEvent<'_>],
 ) -> Element<'a, crate::app::Message> {
-    let first_of_month = selected_date.replace_day(1).unwrap();
-
-    let first_weekday = first_of_month.weekday();
-    let days_from_prev_month = match first_weekday {
-        Weekday::Sunday => 0,
-        Weekday::Monday => 1,
-        Weekday::Tuesday => 2,
-        Weekday::Wednesday => 3,
-        Weekday::Thursday => 4,
-        Weekday::Friday => 5,
-        Weekday::Saturday => 6,
-    };
-
-    let start_date = first_of_month
-        .checked_sub(time::Duration::days(days_from_prev_month as i64))
-        .unwrap();
+    let start_date = grid_start(selected_date, first_weekday);
 
     let mut calendar_column = widget::column().spacing(spacing().space_xxs);
 
     for week in 0..6 {
+        let week_start = start_date.checked_add(time::Duration::weeks(week)).unwrap();
+        let lanes = bar_lanes(week_start.date(), zone, events);
+        let visible_lanes = lanes.len().min(MAX_ROWS);
+
         let mut week_row = widget::row().spacing(spacing().space_xxs);
 
         for day in 0..7 {
-            let current_day_offset = week * 7 + day;
-            let display_date = start_date
-                .checked_add(time::Duration::days(current_day_offset as i64))
+            let display_date = week_start
+                .checked_add(time::Duration::days(day as i64))
                 .unwrap();
 
             let is_current_month = display_date.month() == selected_date.month();
             let is_today = display_date.date() == current_date.date();
             let is_selected = display_date.date() == selected_date.date();
+            let hidden_bars = lanes
+                .iter()
+                .skip(MAX_ROWS)
+                .flatten()
+                .filter(|bar| (bar.first..=bar.last).contains(&day))
+                .count();
 
             let day_button = day_button(
                 display_date.day(),
@@ -56,22 +86,72 @@ pub fn month_grid<'a>(
                 is_today,
                 is_selected,
                 display_date,
+                day_events(
+                    display_date.date(),
+                    zone,
+                    clock,
+                    events,
+                    visible_lanes,
+                    hidden_bars,
+                ),
             );
             week_row = week_row.push(day_button);
         }
 
-        calendar_column = calendar_column.push(week_row);
+        let mut bars = widget::column()
+            .push(Space::new(Length::Fill, DAY_NUMBER_HEIGHT))
+            .padding(spacing().space_xxxs)
+            .spacing(spacing().space_xxxs);
+        for lane in lanes.iter().take(MAX_ROWS) {
+            bars = bars.push(bar_row(lane, events));
+        }
+
+        let mut row = widget::row().spacing(spacing().space_xxs);
+        if show_week_numbers {
+            row = row.push(week_number_button(
+                super::week_number(week_start.date(), first_weekday),
+                week_start,
+            ));
+        }
+        calendar_column = calendar_column.push(
+            row.push(
+                Stack::with_children(vec![week_row.into(), bars.into()])
+                    .width(Length::Fill)
+                    .height(Length::Fill),
+            )
+            .height(Length::Fill),
+        );
     }
 
     calendar_column.into()
 }
 
+/// The number of the week starting at `week_start`, which opens it in the week view.
+fn week_number_button<'a>(
+    number: u8,
+    week_start: OffsetDateTime,
+) -> Element<'a, crate::app::Message> {
+    widget::text::caption(number.to_string())
+        .class(cosmic::style::Text::Color(Color::from_rgb(0.5, 0.5, 0.5)))
+        .apply(widget::container)
+        .center_x(Length::Fill)
+        .height(DAY_NUMBER_HEIGHT)
+        .apply(widget::button::custom)
+        .width(WEEK_NUMBER_WIDTH)
+        .height(Length::Fill)
+        .padding(spacing().space_xxxs)
+        .class(cosmic::style::Button::Text)
+        .on_press(crate::app::Message::OpenWeek(week_start))
+        .into()
+}
+
 fn day_button<'a>(
     day: u8,
     is_current_month: bool,
     is_today: bool,
     is_selected: bool,
     date: OffsetDateTime,
+    events: Element<'a, crate::app::Message>,
 ) -> impl Into<Element<'a, crate::app::Message>> {
     let mut day_text = widget::text::body(day.to_string());
 
@@ -79,9 +159,15 @@ fn day_button<'a>(
         day_text = day_text.class(cosmic::style::Text::Color(Color::from_rgb(0.5, 0.5, 0.5)));
     }
 
-    let mut day_button = widget::button::custom(day_text)
+    let content = widget::column()
+        .push(widget::container(day_text).height(DAY_NUMBER_HEIGHT))
+        .push(events)
+        .spacing(spacing().space_xxxs);
+
+    let mut day_button = widget::button::custom(content)
         .width(Length::Fill)
         .height(Length::Fill)
+        .padding(spacing().space_xxxs)
         .class(cosmic::style::Button::MenuFolder)
         .on_press(crate::app::Message::SelectDate(
             date.replace_day(day).unwrap(),
@@ -95,3 +181,106 @@ fn day_button<'a>(
 
     day_button
 }
+
+/// The chips of the single-day events of `date`, below the space taken by the
+/// spanning bars, followed by a "+N more" link when they don't all fit.
+fn day_events<'a>(
+    date: Date,
+    zone: &Tz,
+    clock: ClockFormat,
+    events: &[CalendarEvent<'_>],
+    visible_lanes: usize,
+    hidden_bars: usize,
+) -> Element<'a, crate::app::Message> {
+    let mut day_events: Vec<&CalendarEvent<'_>> = events
+        .iter()
+        .filter(|e| {
+            let (first, last) = events::day_span(e.event, zone);
+            first == last && first == date
+        })
+        .collect();
+    day_events.sort_by_key(|e| (!e.event.is_all_day(), e.event.start.in_zone(zone)));
+
+    let bar_space = visible_lanes as f32 * (events::CHIP_HEIGHT + spacing().space_xxxs as f32);
+    let available = MAX_ROWS - visible_lanes;
+    let hidden = hidden_bars + day_events.len().saturating_sub(available);
+
+    let mut column = widget::column()
+        .push(Space::new(Length::Fill, bar_space))
+        .spacing(spacing().space_xxxs);
+    for event in day_events.into_iter().take(available) {
+        let time = (!event.event.is_all_day())
+            .then(|| events::time_label(event.event.start.in_zone(zone), clock));
+        column = column.push(events::event_chip(event, time));
+    }
+    if hidden > 0 {
+        column = column.push(
+            widget::button::link(fl!("more-events", count = hidden))
+                .padding(0)
+                .on_press(crate::app::Message::OpenDay(zone::midnight(date, zone))),
+        );
+    }
+
+    column.into()
+}
+
+/// Assigns the multi-day events touching the week starting at `week_start` to lanes,
+/// so that bars in the same lane never overlap.
+fn bar_lanes(week_start: Date, zone: &Tz, events: &[CalendarEvent<'_>]) -> Vec<Vec<Bar>> {
+    let week_end = week_start + Duration::days(6);
+
+    let mut bars: Vec<Bar> = events
+        .iter()
+        .enumerate()
+        .filter_map(|(index, e)| {
+            let (first, last) = events::day_span(e.event, zone);
+            if first == last || last < week_start || first > week_end {
+                return None;
+            }
+            Some(Bar {
+                first: (first.max(week_start) - week_start).whole_days() as usize,
+                last: (last.min(week_end) - week_start).whole_days() as usize,
+                index,
+            })
+        })
+        .collect();
+    bars.sort_by_key(|bar| (bar.first, std::cmp::Reverse(bar.last)));
+
+    let mut lanes: Vec<Vec<Bar>> = vec![];
+    for bar in bars {
+        match lanes
+            .iter_mut()
+            .find(|lane| lane.last().is_some_and(|last| last.last < bar.first))
+        {
+            Some(lane) => lane.push(bar),
+            None => lanes.push(vec![bar]),
+        }
+    }
+    lanes
+}
+
+/// One lane of spanning bars, laid out across the seven columns of a week row.
+fn bar_row<'a>(lane: &[Bar], events: &[CalendarEvent<'_>]) -> Element<'a, crate::app::Message> {
+    let mut row = widget::row().height(events::CHIP_HEIGHT);
+    let mut cursor = 0;
+    for bar in lane {
+        if bar.first > cursor {
+            row = row.push(Space::new(
+                Length::FillPortion((bar.first - cursor) as u16),
+                Length::Shrink,
+            ));
+        }
+        row = row.push(
+            widget::container(events::event_chip(&events[bar.index], None))
+                .width(Length::FillPortion((bar.last - bar.first + 1) as u16)),
+        );
+        cursor = bar.last + 1;
+    }
+    if cursor < 7 {
+        row = row.push(Space::new(
+            Length::FillPortion((7 - cursor) as u16),
+            Length::Shrink,
+        ));
+    }
+    row.into()
+}
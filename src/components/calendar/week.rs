@@ -1,88 +1,185 @@
 use cosmic::{
-    iced::{alignment::Horizontal, Length},
+    iced::{
+        alignment::Horizontal,
+        widget::{container, Space, Stack},
+        Background, Color, Length,
+    },
     theme::spacing,
     widget, Apply, Element,
 };
-use time::OffsetDateTime;
+use time::{Date, Duration, OffsetDateTime, PrimitiveDateTime, Time};
+use time_tz::{OffsetDateTimeExt, Tz};
 
-pub fn week_grid<'a>(week_start: &OffsetDateTime) -> impl Into<Element<'a, crate::app::Message>> {
-    let mut main_column = widget::column().spacing(0);
+use super::{events, CalendarEvent};
+use crate::{config::ClockFormat, fl, models::zone};
 
-    for hour in 0..24 {
-        main_column = main_column.push(widget::divider::horizontal::default());
+/// Height of each 30 minute slot in the week grid.
+const SLOT_HEIGHT: f32 = 60.0;
+
+/// Width of each column with the hours in a secondary time zone.
+pub const ZONE_COLUMN_WIDTH: f32 = 64.0;
 
-        let row = hour_row(hour, false, week_start);
-        main_column = main_column.push(row);
+/// The time grid of `days` days from `start`, with the hours of each of `secondary_zones`
+/// in columns left of the hours of `zone`. Those are the times on the first day.
+/// The hours outside `working_hours` are shaded.
+pub fn week_grid<'a>(
+    start: Date,
+    days: i64,
+    zone: &Tz,
+    secondary_zones: &[&Tz],
+    events: &[CalendarEvent<'_>],
+    clock: ClockFormat,
+    working_hours: (u8, u8),
+) -> impl Into<Element<'a, crate::app::Message>> {
+    let mut row = widget::row();
+    for other in secondary_zones {
+        row = row.push(hour_labels(Length::Fixed(ZONE_COLUMN_WIDTH), |hour| {
+            zone_time(start, hour, false, zone, other, clock)
+        }));
+    }
+    row = row
+        .push(hour_labels(Length::Fill, |hour| clock.format(hour, 0)))
+        .push(widget::divider::vertical::default());
 
-        main_column = main_column.push(widget::divider::horizontal::light());
+    for day in 0..days {
+        let day_date = start + Duration::days(day);
 
-        let half_hour_row = hour_row(hour, true, week_start);
-        main_column = main_column.push(half_hour_row);
+        let column = Stack::with_children(vec![
+            day_cells(day_date, zone, working_hours),
+            events::timed_layer(day_date, zone, events, SLOT_HEIGHT, clock),
+        ])
+        .width(Length::Fill);
+        row = row.push(column);
+        row = row.push(widget::divider::vertical::default());
     }
 
-    main_column
+    row
 }
 
-fn hour_row<'a>(
-    hour: u8,
-    is_half_hour: bool,
-    week_start: &OffsetDateTime,
+/// The all-day events of `days` days from `start`, in a strip lined up with the day headers.
+pub fn all_day_row<'a>(
+    start: Date,
+    days: i64,
+    zone: &Tz,
+    zone_columns: usize,
+    events: &[CalendarEvent<'_>],
 ) -> impl Into<Element<'a, crate::app::Message>> {
-    let mut row = widget::row().height(60);
-
-    let time_label = if is_half_hour {
-        String::new()
-    } else {
-        format!("{:02}:00", hour)
-    };
-
-    let time_container =
-        widget::container(widget::text::body(time_label).align_x(Horizontal::Right))
-            .width(30)
-            .height(30)
-            .align_top(Length::Fill)
-            .center_x(Length::Fill)
-            .padding([spacing().space_xxxs, 0, 0, 0]);
-
-    row = row.push(time_container);
-    row = row.push(widget::divider::vertical::default());
-
-    for day in 0..7 {
-        let day_date = week_start.checked_add(time::Duration::days(day)).unwrap();
-
-        let cell = time_cell(hour, is_half_hour, day_date);
-        row = row.push(cell);
-        row = row.push(widget::divider::vertical::default());
+    let mut row = widget::row().padding([0, spacing().space_xs, 0, 0]);
+    for _ in 0..zone_columns {
+        row = row.push(Space::new(ZONE_COLUMN_WIDTH, Length::Shrink));
+    }
+    row = row.push(Space::new(Length::Fill, Length::Shrink));
+
+    for day in 0..days {
+        let day_date = start + Duration::days(day);
+        row = row.push(events::all_day_column(day_date, zone, events));
     }
 
     row
 }
 
+fn hour_labels<'a>(
+    width: Length,
+    label: impl Fn(u8) -> String,
+) -> Element<'a, crate::app::Message> {
+    let mut column = widget::column().width(width);
+
+    for hour in 0..24 {
+        let time_container =
+            widget::container(widget::text::body(label(hour)).align_x(Horizontal::Right))
+                .align_top(SLOT_HEIGHT)
+                .center_x(width)
+                .padding([spacing().space_xxxs, 0, 0, 0]);
+
+        column = column
+            .push(widget::divider::horizontal::default())
+            .push(time_container)
+            .push(widget::divider::horizontal::light())
+            .push(Space::new(Length::Fill, SLOT_HEIGHT));
+    }
+
+    column.into()
+}
+
+fn day_cells<'a>(
+    date: Date,
+    zone: &Tz,
+    working_hours: (u8, u8),
+) -> Element<'a, crate::app::Message> {
+    let mut column = widget::column().width(Length::Fill);
+
+    for hour in 0..24 {
+        let is_working = is_working_hour(hour, working_hours);
+        column = column
+            .push(widget::divider::horizontal::default())
+            .push(time_cell(hour, false, date, zone, is_working))
+            .push(widget::divider::horizontal::light())
+            .push(time_cell(hour, true, date, zone, is_working));
+    }
+
+    column.into()
+}
+
 fn time_cell<'a>(
     hour: u8,
     is_half_hour: bool,
-    date: OffsetDateTime,
+    date: Date,
+    zone: &Tz,
+    is_working: bool,
 ) -> impl Into<Element<'a, crate::app::Message>> {
-    let minute = if is_half_hour { 30 } else { 0 };
-    let cell_time = date
-        .replace_hour(hour)
-        .and_then(|d| d.replace_minute(minute))
-        .and_then(|d| d.replace_second(0))
-        .unwrap_or(date);
+    let cell_time = slot_time(date, hour, is_half_hour, zone);
 
     let container = widget::container(widget::text(""))
         .width(Length::Fill)
-        .height(30);
+        .height(Length::Fill)
+        .class(off_hours_style(is_working));
 
     widget::button::custom(container)
+        .padding(0)
         .width(Length::Fill)
-        .height(60)
+        .height(SLOT_HEIGHT)
         .class(cosmic::style::Button::Text)
         .on_press(crate::app::Message::AddEvent(cell_time))
 }
 
+/// The instant the slot at `hour` (or half past it) of `date` starts in `zone`.
+pub fn slot_time(date: Date, hour: u8, is_half_hour: bool, zone: &Tz) -> OffsetDateTime {
+    let minute = if is_half_hour { 30 } else { 0 };
+    let time = Time::from_hms(hour, minute, 0).unwrap_or(Time::MIDNIGHT);
+    zone::resolve(PrimitiveDateTime::new(date, time), zone)
+}
+
+/// The wall-clock time in `other` at the slot at `hour` (or half past it) of `date` in `zone`.
+pub fn zone_time(
+    date: Date,
+    hour: u8,
+    is_half_hour: bool,
+    zone: &Tz,
+    other: &Tz,
+    clock: ClockFormat,
+) -> String {
+    let time = slot_time(date, hour, is_half_hour, zone).to_timezone(other);
+    clock.format(time.hour(), time.minute())
+}
+
+/// Whether `hour` falls within `working_hours`, which end before their second hour.
+pub fn is_working_hour(hour: u8, (start, end): (u8, u8)) -> bool {
+    (start..end).contains(&hour)
+}
+
+/// Shades the slots outside working hours.
+pub fn off_hours_style(is_working: bool) -> cosmic::theme::Container<'static> {
+    cosmic::theme::Container::custom(move |theme| {
+        let shade: Color = theme.cosmic().on_bg_color().into();
+        container::Style {
+            background: (!is_working).then_some(Background::Color(Color { a: 0.04, ..shade })),
+            ..Default::default()
+        }
+    })
+}
+
 pub fn day_header<'a>(
-    day_name: &'static str,
+    day_name: String,
     day_number: u8,
     is_today: bool,
     date: OffsetDateTime,
@@ -107,6 +204,40 @@ pub fn day_header<'a>(
         .height(60)
 }
 
+/// The abbreviation of a time zone, above its column of hours.
+pub fn zone_header<'a>(abbreviation: String, width: Length) -> Element<'a, crate::app::Message> {
+    widget::text::caption(abbreviation)
+        .apply(widget::container)
+        .center_x(width)
+        .align_bottom(Length::Fill)
+        .padding(spacing().space_xxxs)
+        .into()
+}
+
+/// The number of the week in the corner above the hours, with the abbreviation of
+/// the display zone below it when secondary zones are shown.
+pub fn week_number_header<'a>(
+    number: u8,
+    abbreviation: Option<String>,
+    date: OffsetDateTime,
+) -> Element<'a, crate::app::Message> {
+    let mut header = widget::column()
+        .push(widget::text::caption(fl!("week-number", number = number)))
+        .align_x(Horizontal::Center);
+    if let Some(abbreviation) = abbreviation {
+        header = header
+            .push(Space::new(Length::Shrink, Length::Fill))
+            .push(widget::text::caption(abbreviation));
+    }
+
+    widget::button::custom(header.padding(spacing().space_xxxs))
+        .width(Length::Fill)
+        .height(60)
+        .class(cosmic::style::Button::Text)
+        .on_press(crate::app::Message::OpenWeek(date))
+        .into()
+}
+
 pub fn empty_day_header<'a>() -> Element<'a, crate::app::Message> {
     let header = widget::column()
         .push(
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+use cosmic::{
+    iced::{alignment::Horizontal, widget::container, Background, Border, Color, Length},
+    theme::spacing,
+    widget, Apply, Element,
+};
+use time::{Date, Duration, Month, OffsetDateTime, Weekday};
+use time_tz::Tz;
+
+use super::{events, month, CalendarEvent};
+use crate::{app::Message, i18n, models::zone};
+
+/// From how many events on a day it is shaded as fully busy.
+const BUSIEST: usize = 4;
+
+/// Size of each day in the small month grids.
+const DAY_SIZE: f32 = 28.0;
+
+/// The twelve months of the year of `selected_date` as small grids, with each day
+/// shaded by how many events it has.
+pub fn year_grid<'a>(
+    selected_date: &OffsetDateTime,
+    current_date: &OffsetDateTime,
+    first_weekday: Weekday,
+    zone: &Tz,
+    events: &[CalendarEvent<'_>],
+) -> Element<'a, Message> {
+    let busy = busy_days(selected_date.year(), zone, events);
+
+    let months: Vec<Element<'a, Message>> = (1..=12u8)
+        .filter_map(|month| Month::try_from(month).ok())
+        .filter_map(|month| {
+            let first = selected_date
+                .replace_day(1)
+                .ok()?
+                .replace_month(month)
+                .ok()?;
+            Some(mini_month(
+                &first,
+                current_date.date(),
+                first_weekday,
+                zone,
+                &busy,
+            ))
+        })
+        .collect();
+
+    widget::flex_row(months)
+        .row_spacing(spacing().space_m)
+        .column_spacing(spacing().space_m)
+        .apply(widget::container)
+        .center_x(Length::Fill)
+        .padding(spacing().space_s)
+        .apply(widget::scrollable)
+        .height(Length::Fill)
+        .into()
+}
+
+/// How many events fall on each day of `year`.
+fn busy_days(year: i32, zone: &Tz, events: &[CalendarEvent<'_>]) -> HashMap<Date, usize> {
+    let mut busy = HashMap::new();
+    for event in events {
+        let (first, last) = events::day_span(event.event, zone);
+        let mut date = first;
+        while date <= last {
+            if date.year() == year {
+                *busy.entry(date).or_default() += 1;
+            }
+            date += Duration::DAY;
+        }
+    }
+    busy
+}
+
+/// One month, laid out like the month grid starting from [`month::grid_start`].
+fn mini_month<'a>(
+    first: &OffsetDateTime,
+    today: Date,
+    first_weekday: Weekday,
+    zone: &Tz,
+    busy: &HashMap<Date, usize>,
+) -> Element<'a, Message> {
+    let start = month::grid_start(first, first_weekday).date();
+
+    let mut weekdays = widget::row();
+    for weekday in super::weekdays(first_weekday) {
+        let letter: String = i18n::weekday_short(weekday).chars().take(1).collect();
+        weekdays = weekdays.push(
+            widget::text::caption(letter)
+                .align_x(Horizontal::Center)
+                .width(DAY_SIZE),
+        );
+    }
+
+    let mut grid = widget::column()
+        .push(widget::text::heading(i18n::month(first.month())))
+        .push(weekdays);
+    for week in 0..6 {
+        let mut row = widget::row();
+        for day in 0..7 {
+            let date = start + Duration::days(week * 7 + day);
+            row = row.push(if date.month() == first.month() {
+                day_button(
+                    date,
+                    date == today,
+                    busy.get(&date).copied().unwrap_or(0),
+                    zone,
+                )
+            } else {
+                widget::Space::new(DAY_SIZE, DAY_SIZE).into()
+            });
+        }
+        grid = grid.push(row);
+    }
+
+    grid.spacing(spacing().space_xxxs).into()
+}
+
+/// A day shaded with the accent color, more strongly the more events it has,
+/// which opens the day view when clicked.
+fn day_button<'a>(date: Date, is_today: bool, events: usize, zone: &Tz) -> Element<'a, Message> {
+    let busyness = events.min(BUSIEST) as f32 / BUSIEST as f32;
+    let label = widget::text::caption(date.day().to_string())
+        .align_x(Horizontal::Center)
+        .apply(widget::container)
+        .center(Length::Fill)
+        .class(cosmic::theme::Container::custom(move |theme| {
+            let accent: Color = theme.cosmic().accent_color().into();
+            container::Style {
+                background: (busyness > 0.0).then_some(Background::Color(Color {
+                    a: 0.15 + 0.6 * busyness,
+                    ..accent
+                })),
+                border: Border {
+                    radius: (DAY_SIZE / 2.0).into(),
+                    width: if is_today { 1.0 } else { 0.0 },
+                    color: accent,
+                },
+                ..Default::default()
+            }
+        }));
+
+    widget::button::custom(label)
+        .width(DAY_SIZE)
+        .height(DAY_SIZE)
+        .padding(0)
+        .class(cosmic::style::Button::Text)
+        .on_press(Message::OpenDay(zone::midnight(date, zone)))
+        .into()
+}
@@ -0,0 +1,157 @@
+use cosmic::{
+    iced::{widget::container, Background, Border, Color, Length},
+    theme::spacing,
+    widget, Apply, Element,
+};
+
+use super::calendar::parse_color;
+use crate::fl;
+
+/// The colors offered for calendars, besides entering one.
+const SWATCHES: [&str; 12] = [
+    "#3584e4", "#2190a4", "#3a944a", "#c88800", "#ed5b00", "#e62d42", "#d56199", "#9141ac",
+    "#6f8396", "#865e3c", "#1c71d8", "#26a269",
+];
+
+/// Size of each swatch.
+const SWATCH_SIZE: f32 = 32.0;
+
+/// Form for choosing the color a calendar's events are shown in, overriding the one
+/// its provider reports. Shown in the context drawer.
+#[derive(Debug, Clone)]
+pub struct ColorForm {
+    /// The [`calendar_key`](crate::config::calendar_key) of the calendar.
+    key: String,
+    name: String,
+    /// The color as `#rrggbb`, as picked or typed.
+    hex: String,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Swatch(usize),
+    Hex(String),
+    Apply,
+    Reset,
+}
+
+/// What to do with the color of the calendar after the form handled a message.
+pub enum Action {
+    /// Show the calendar in this `#rrggbb` color.
+    Set(String),
+    /// Show the calendar in the color its provider reports again.
+    Reset,
+}
+
+impl ColorForm {
+    /// A form for the calendar named `name` with the key `key`, currently shown in `color`.
+    pub fn new(key: String, name: String, color: Option<&str>) -> Self {
+        Self {
+            key,
+            name,
+            hex: color.unwrap_or_default().to_string(),
+            error: None,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Action> {
+        match message {
+            Message::Swatch(index) => {
+                let hex = SWATCHES.get(index)?;
+                self.hex = hex.to_string();
+                self.error = None;
+                return Some(Action::Set(self.hex.clone()));
+            }
+            Message::Hex(hex) => self.hex = hex,
+            Message::Apply => {
+                let hex = self.hex.trim().to_lowercase();
+                if parse_color(&hex).is_none() {
+                    self.error = Some(fl!("invalid-color"));
+                    return None;
+                }
+                self.error = None;
+                self.hex = hex.clone();
+                return Some(Action::Set(hex));
+            }
+            Message::Reset => {
+                self.error = None;
+                return Some(Action::Reset);
+            }
+        }
+        None
+    }
+
+    pub fn view<'a>(&'a self) -> Element<'a, Message> {
+        let selected = parse_color(self.hex.trim());
+        let swatches: Vec<Element<'a, Message>> = SWATCHES
+            .iter()
+            .enumerate()
+            .filter_map(|(index, hex)| {
+                let color = parse_color(hex)?;
+                Some(swatch(color, selected == Some(color), index))
+            })
+            .collect();
+
+        let mut column =
+            widget::column()
+                .push(widget::text::heading(self.name.as_str()))
+                .push(
+                    widget::flex_row(swatches)
+                        .row_spacing(spacing().space_xxs)
+                        .column_spacing(spacing().space_xxs),
+                )
+                .push(
+                    widget::row()
+                        .push(
+                            widget::text_input(fl!("color-hex"), &self.hex)
+                                .on_input(Message::Hex)
+                                .width(Length::Fill),
+                        )
+                        .push(widget::button::standard(fl!("apply")).on_press_maybe(
+                            (!self.hex.trim().is_empty()).then_some(Message::Apply),
+                        ))
+                        .spacing(spacing().space_xs),
+                );
+
+        if let Some(error) = &self.error {
+            column = column
+                .push(widget::text::caption(error.as_str()).class(cosmic::style::Text::Accent));
+        }
+
+        column
+            .push(
+                widget::row()
+                    .push(widget::horizontal_space())
+                    .push(widget::button::text(fl!("reset-color")).on_press(Message::Reset)),
+            )
+            .spacing(spacing().space_s)
+            .into()
+    }
+}
+
+/// A round button in `color`, ringed when it's the selected one.
+fn swatch<'a>(color: Color, is_selected: bool, index: usize) -> Element<'a, Message> {
+    widget::Space::new(SWATCH_SIZE, SWATCH_SIZE)
+        .apply(widget::container)
+        .class(cosmic::theme::Container::custom(move |theme| {
+            container::Style {
+                background: Some(Background::Color(color)),
+                border: Border {
+                    radius: (SWATCH_SIZE / 2.0).into(),
+                    width: if is_selected { 2.0 } else { 0.0 },
+                    color: theme.cosmic().on_bg_color().into(),
+                },
+                ..Default::default()
+            }
+        }))
+        .apply(widget::button::custom)
+        .padding(0)
+        .class(cosmic::style::Button::Text)
+        .on_press(Message::Swatch(index))
+        .into()
+}
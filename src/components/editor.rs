@@ -0,0 +1,548 @@
+use std::collections::HashMap;
+
+use chrono::{Datelike, NaiveDate};
+use cosmic::{
+    iced::{Alignment, Length},
+    theme::spacing,
+    widget::{self, calendar::CalendarModel},
+    Apply, Element,
+};
+use time::{Date, Duration, Month, OffsetDateTime, PrimitiveDateTime, Time, Weekday};
+use time_tz::{OffsetDateTimeExt, TimeZone, Tz};
+
+use crate::{
+    config::{calendar_key, ClockFormat, Config},
+    fl, i18n,
+    models::{zone, Calendar, Event, EventStatus, EventTime, Reminder, ReminderMethod, Source},
+    services::RecurrenceScope,
+};
+
+/// The choices offered when editing an occurrence of a recurring series, in order.
+const SCOPES: [RecurrenceScope; 3] = [
+    RecurrenceScope::This,
+    RecurrenceScope::ThisAndFollowing,
+    RecurrenceScope::All,
+];
+
+/// Minutes between the times offered for the start and end of an event.
+const TIME_STEP: usize = 15;
+
+/// State of the event editor shown in the context drawer.
+#[derive(Debug, Clone)]
+pub struct EventEditor {
+    /// The event being edited, or `None` when creating a new one.
+    event: Option<Event>,
+    /// Calendars new events can be saved to, along with the source they come from.
+    calendars: Vec<(Source, Calendar)>,
+    calendar_names: Vec<String>,
+    selected_calendar: Option<usize>,
+    title: String,
+    all_day: bool,
+    start_date: Date,
+    start_time: Time,
+    end_date: Date,
+    end_time: Time,
+    /// The end of the event whose date is being picked, along with the picker.
+    date_picker: Option<(Bound, CalendarModel)>,
+    /// The times offered for the start and end, and their labels.
+    times: Vec<Time>,
+    time_names: Vec<String>,
+    first_weekday: Weekday,
+    location: String,
+    notes: String,
+    /// Reminders a new event is created with.
+    reminders: Vec<Reminder>,
+    /// Zone the entered dates and times are interpreted in.
+    zone: &'static Tz,
+    /// Which occurrences changes apply to, when editing an occurrence of a series.
+    scope: usize,
+    scope_names: Vec<String>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Title(String),
+    Calendar(usize),
+    AllDay(bool),
+    /// Opens the date picker for an end of the event, or closes it if it's open.
+    PickDate(Bound),
+    /// A date was picked in the open date picker.
+    Date(NaiveDate),
+    PreviousMonth,
+    NextMonth,
+    /// Sets the start time to one of the times offered.
+    StartTime(usize),
+    EndTime(usize),
+    Location(String),
+    Notes(String),
+    Scope(usize),
+    Save,
+    Delete,
+}
+
+/// The start or end of the event being edited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Start,
+    End,
+}
+
+/// What the application should do after the editor handled a message.
+pub enum Action {
+    /// Create the event when it has no id yet, or update it otherwise.
+    Save(Source, Calendar, Event),
+    Delete(Source, Calendar, Event),
+    /// Apply changes to an occurrence of a series, given as it was and as edited,
+    /// to the occurrences the scope selects.
+    SaveOccurrence(Source, Calendar, Event, Event, RecurrenceScope),
+    DeleteOccurrence(Source, Calendar, Event, RecurrenceScope),
+}
+
+impl EventEditor {
+    /// An editor for a new event starting at `start`, with times shown in `zone`.
+    /// Its length, reminder and calendar are the defaults from `config`.
+    pub fn create(
+        start: OffsetDateTime,
+        zone: &'static Tz,
+        calendars: &[(Source, Calendar)],
+        config: &Config,
+    ) -> Self {
+        let start = start.to_timezone(zone);
+        let start = start
+            .replace_second(0)
+            .and_then(|start| start.replace_nanosecond(0))
+            .unwrap_or(start);
+        let end = (start + Duration::minutes(config.event_duration.into())).to_timezone(zone);
+        let calendars: Vec<(Source, Calendar)> = calendars
+            .iter()
+            .filter(|(_, calendar)| calendar.is_editable())
+            .cloned()
+            .collect();
+        let (times, time_names) = time_choices([start.time(), end.time()], config.clock);
+
+        Self {
+            event: None,
+            calendar_names: calendars
+                .iter()
+                .map(|(_, calendar)| calendar.name.clone())
+                .collect(),
+            selected_calendar: calendars
+                .iter()
+                .position(|(source, calendar)| {
+                    config.default_calendar.as_deref()
+                        == Some(calendar_key(source, &calendar.id).as_str())
+                })
+                .or((!calendars.is_empty()).then_some(0)),
+            calendars,
+            title: String::new(),
+            all_day: false,
+            start_date: start.date(),
+            start_time: start.time(),
+            end_date: end.date(),
+            end_time: end.time(),
+            date_picker: None,
+            times,
+            time_names,
+            first_weekday: first_weekday(config),
+            location: String::new(),
+            notes: String::new(),
+            reminders: config
+                .reminder
+                .map(|minutes| Reminder {
+                    minutes,
+                    method: ReminderMethod::Notification,
+                })
+                .into_iter()
+                .collect(),
+            zone,
+            scope: 0,
+            scope_names: scope_names(),
+            error: None,
+        }
+    }
+
+    /// An editor for an existing event, which stays in the calendar it belongs to.
+    pub fn edit(
+        event: Event,
+        source: Source,
+        calendar: Calendar,
+        zone: &'static Tz,
+        config: &Config,
+    ) -> Self {
+        let (start, end) = match (event.start, event.end) {
+            (EventTime::Date(start), EventTime::Date(end)) => (
+                zone::midnight(start, zone),
+                // All-day events end on the following day, but the editor shows the last day.
+                zone::midnight(end.previous_day().unwrap_or(end), zone),
+            ),
+            (start, end) => (start.in_zone(zone), end.in_zone(zone)),
+        };
+        let (times, time_names) = time_choices([start.time(), end.time()], config.clock);
+
+        Self {
+            calendar_names: vec![calendar.name.clone()],
+            selected_calendar: Some(0),
+            calendars: vec![(source, calendar)],
+            title: event.summary.clone(),
+            all_day: event.is_all_day(),
+            start_date: start.date(),
+            start_time: start.time(),
+            end_date: end.date(),
+            end_time: end.time(),
+            date_picker: None,
+            times,
+            time_names,
+            first_weekday: first_weekday(config),
+            location: event.location.clone().unwrap_or_default(),
+            notes: event.description.clone().unwrap_or_default(),
+            event: Some(event),
+            reminders: vec![],
+            zone,
+            scope: 0,
+            scope_names: scope_names(),
+            error: None,
+        }
+    }
+
+    pub fn is_new(&self) -> bool {
+        self.event.is_none()
+    }
+
+    /// The occurrence of a recurring series being edited, if it's one.
+    fn occurrence(&self) -> Option<&Event> {
+        self.event
+            .as_ref()
+            .filter(|event| event.series_id().is_some())
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Action> {
+        match message {
+            Message::Title(title) => self.title = title,
+            Message::Calendar(idx) => self.selected_calendar = Some(idx),
+            Message::AllDay(all_day) => self.all_day = all_day,
+            Message::PickDate(bound) => {
+                self.date_picker = match self.date_picker.take() {
+                    Some((open, _)) if open == bound => None,
+                    _ => {
+                        let date = to_naive(match bound {
+                            Bound::Start => self.start_date,
+                            Bound::End => self.end_date,
+                        })?;
+                        Some((bound, CalendarModel::new(date, date)))
+                    }
+                };
+            }
+            Message::Date(date) => {
+                let (bound, _) = self.date_picker.take()?;
+                let date = from_naive(date)?;
+                match bound {
+                    // The end moves along with the start, so the event keeps its length.
+                    Bound::Start => {
+                        self.end_date += date - self.start_date;
+                        self.start_date = date;
+                    }
+                    Bound::End => self.end_date = date,
+                }
+            }
+            Message::PreviousMonth => {
+                if let Some((_, picker)) = self.date_picker.as_mut() {
+                    picker.show_prev_month();
+                }
+            }
+            Message::NextMonth => {
+                if let Some((_, picker)) = self.date_picker.as_mut() {
+                    picker.show_next_month();
+                }
+            }
+            Message::StartTime(index) => self.start_time = *self.times.get(index)?,
+            Message::EndTime(index) => self.end_time = *self.times.get(index)?,
+            Message::Location(location) => self.location = location,
+            Message::Notes(notes) => self.notes = notes,
+            Message::Scope(scope) => self.scope = scope,
+            Message::Save => match self.build() {
+                Ok(action) => {
+                    self.error = None;
+                    return Some(action);
+                }
+                Err(err) => self.error = Some(err),
+            },
+            Message::Delete => {
+                let (source, calendar) = self.selected()?.clone();
+                let event = self.event.clone()?;
+                if event.series_id().is_some() {
+                    return Some(Action::DeleteOccurrence(
+                        source,
+                        calendar,
+                        event,
+                        SCOPES[self.scope],
+                    ));
+                }
+                return Some(Action::Delete(source, calendar, event));
+            }
+        }
+        None
+    }
+
+    /// Shows why saving or deleting the event failed and lets the user try again.
+    pub fn fail(&mut self, error: String) {
+        self.error = Some(error);
+    }
+
+    pub fn view<'a>(&'a self) -> Element<'a, Message> {
+        let calendar: Element<'a, Message> = if self.is_new() {
+            widget::dropdown(
+                &self.calendar_names,
+                self.selected_calendar,
+                Message::Calendar,
+            )
+            .into()
+        } else {
+            widget::text::body(self.calendar_names.first().cloned().unwrap_or_default()).into()
+        };
+
+        let times = widget::column()
+            .push(widget::settings::item(
+                fl!("all-day"),
+                widget::toggler(self.all_day).on_toggle(Message::AllDay),
+            ))
+            .push(widget::settings::item(
+                fl!("starts"),
+                self.date_time_inputs(Bound::Start),
+            ))
+            .push_maybe(self.date_picker(Bound::Start))
+            .push(widget::settings::item(
+                fl!("ends"),
+                self.date_time_inputs(Bound::End),
+            ))
+            .push_maybe(self.date_picker(Bound::End))
+            .spacing(spacing().space_xxs);
+
+        let mut buttons = widget::row().spacing(spacing().space_xs);
+        if !self.is_new() {
+            buttons =
+                buttons.push(widget::button::destructive(fl!("delete")).on_press(Message::Delete));
+        }
+        buttons = buttons.push(widget::horizontal_space()).push(
+            widget::button::suggested(fl!("save")).on_press_maybe(
+                (!self.title.trim().is_empty() && self.selected_calendar.is_some())
+                    .then_some(Message::Save),
+            ),
+        );
+
+        let mut column = widget::column()
+            .push(
+                widget::text_input(fl!("event-title"), &self.title)
+                    .on_input(Message::Title)
+                    .width(Length::Fill),
+            )
+            .push(widget::settings::item(fl!("calendar"), calendar))
+            .push(times);
+        if self.occurrence().is_some() {
+            column = column.push(widget::settings::item(
+                fl!("repeating-event"),
+                widget::dropdown(&self.scope_names, Some(self.scope), Message::Scope),
+            ));
+        }
+        column = column
+            .push(
+                widget::text_input(fl!("location"), &self.location)
+                    .on_input(Message::Location)
+                    .width(Length::Fill),
+            )
+            .push(
+                widget::text_input(fl!("notes"), &self.notes)
+                    .on_input(Message::Notes)
+                    .width(Length::Fill),
+            );
+
+        if self.calendars.is_empty() {
+            column = column.push(widget::text::caption(fl!("no-editable-calendars")));
+        }
+        if let Some(error) = &self.error {
+            column = column
+                .push(widget::text::caption(error.as_str()).class(cosmic::style::Text::Accent));
+        }
+
+        column.push(buttons).spacing(spacing().space_s).into()
+    }
+
+    /// A button opening the date picker for `bound`, and the time it's at unless the
+    /// event lasts all day.
+    fn date_time_inputs<'a>(&'a self, bound: Bound) -> Element<'a, Message> {
+        let (date, time, on_time): (_, _, fn(usize) -> Message) = match bound {
+            Bound::Start => (self.start_date, self.start_time, Message::StartTime),
+            Bound::End => (self.end_date, self.end_time, Message::EndTime),
+        };
+        let mut row = widget::row()
+            .push(
+                widget::button::standard(date_label(date))
+                    .selected(
+                        self.date_picker
+                            .as_ref()
+                            .is_some_and(|(open, _)| *open == bound),
+                    )
+                    .on_press(Message::PickDate(bound)),
+            )
+            .spacing(spacing().space_xxs)
+            .align_y(Alignment::Center);
+        if !self.all_day {
+            row = row.push(widget::dropdown(
+                &self.time_names,
+                self.times.iter().position(|t| *t == time),
+                on_time,
+            ));
+        }
+        row.into()
+    }
+
+    /// The date picker, when it's open for `bound`.
+    fn date_picker<'a>(&'a self, bound: Bound) -> Option<Element<'a, Message>> {
+        let (open, picker) = self.date_picker.as_ref()?;
+        if *open != bound {
+            return None;
+        }
+        Some(
+            widget::calendar(
+                picker,
+                Message::Date,
+                || Message::PreviousMonth,
+                || Message::NextMonth,
+                to_chrono_weekday(self.first_weekday),
+            )
+            .apply(widget::container)
+            .center_x(Length::Fill)
+            .into(),
+        )
+    }
+
+    fn selected(&self) -> Option<&(Source, Calendar)> {
+        self.calendars.get(self.selected_calendar?)
+    }
+
+    /// Validates the form and turns it into an event ready to be saved.
+    fn build(&self) -> Result<Action, String> {
+        let (source, calendar) = self
+            .selected()
+            .cloned()
+            .ok_or_else(|| fl!("no-editable-calendars"))?;
+
+        let (start, end) = if self.all_day {
+            let end = self
+                .end_date
+                .next_day()
+                .ok_or_else(|| fl!("end-before-start"))?;
+            (EventTime::Date(self.start_date), EventTime::Date(end))
+        } else {
+            let start = PrimitiveDateTime::new(self.start_date, self.start_time);
+            let end = PrimitiveDateTime::new(self.end_date, self.end_time);
+            (
+                EventTime::DateTime(zone::resolve(start, self.zone)),
+                EventTime::DateTime(zone::resolve(end, self.zone)),
+            )
+        };
+        if end.in_zone(self.zone) <= start.in_zone(self.zone) {
+            return Err(fl!("end-before-start"));
+        }
+
+        let mut event = self.event.clone().unwrap_or_else(|| Event {
+            id: String::new(),
+            calendar_id: calendar.id.clone(),
+            summary: String::new(),
+            description: None,
+            location: None,
+            start,
+            end,
+            // New events are scheduled in the zone of their calendar, or the one they were entered in.
+            time_zone: Some(
+                calendar
+                    .timezone
+                    .as_deref()
+                    .and_then(zone::named)
+                    .unwrap_or(self.zone)
+                    .name()
+                    .to_string(),
+            ),
+            attendees: vec![],
+            organizer: None,
+            status: EventStatus::Confirmed,
+            recurrence: vec![],
+            reminders: self.reminders.clone(),
+            provider: calendar.provider,
+            extra: HashMap::new(),
+        });
+        event.summary = self.title.trim().to_string();
+        event.start = start;
+        event.end = end;
+        event.location = Some(self.location.trim().to_string()).filter(|l| !l.is_empty());
+        event.description = Some(self.notes.trim().to_string()).filter(|n| !n.is_empty());
+
+        if let Some(occurrence) = self.occurrence() {
+            return Ok(Action::SaveOccurrence(
+                source,
+                calendar,
+                occurrence.clone(),
+                event,
+                SCOPES[self.scope],
+            ));
+        }
+        Ok(Action::Save(source, calendar, event))
+    }
+}
+
+fn scope_names() -> Vec<String> {
+    SCOPES
+        .iter()
+        .map(|scope| match scope {
+            RecurrenceScope::This => fl!("this-event"),
+            RecurrenceScope::ThisAndFollowing => fl!("this-and-following-events"),
+            RecurrenceScope::All => fl!("all-events"),
+        })
+        .collect()
+}
+
+/// The day weeks start on in the date picker, as configured or else for the region.
+fn first_weekday(config: &Config) -> Weekday {
+    config.first_weekday.unwrap_or_else(i18n::first_weekday)
+}
+
+/// The times offered for the start and end of an event: every [`TIME_STEP`] minutes,
+/// along with the `current` ones falling between, labelled in the `clock` format.
+fn time_choices(current: [Time; 2], clock: ClockFormat) -> (Vec<Time>, Vec<String>) {
+    let mut times: Vec<Time> = (0..24 * 60)
+        .step_by(TIME_STEP)
+        .filter_map(|minutes| Time::from_hms((minutes / 60) as u8, (minutes % 60) as u8, 0).ok())
+        .chain(current)
+        .collect();
+    times.sort();
+    times.dedup();
+    let names = times
+        .iter()
+        .map(|time| clock.format(time.hour(), time.minute()))
+        .collect();
+    (times, names)
+}
+
+/// A date as shown on the buttons opening the date picker, such as "Mon 3 March 2025".
+fn date_label(date: Date) -> String {
+    format!(
+        "{} {} {} {}",
+        i18n::weekday_short(date.weekday()),
+        date.day(),
+        i18n::month(date.month()),
+        date.year()
+    )
+}
+
+fn to_naive(date: Date) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(date.year(), date.month() as u32, date.day() as u32)
+}
+
+fn from_naive(date: NaiveDate) -> Option<Date> {
+    let month = Month::try_from(date.month() as u8).ok()?;
+    Date::from_calendar_date(date.year(), month, date.day() as u8).ok()
+}
+
+fn to_chrono_weekday(weekday: Weekday) -> chrono::Weekday {
+    chrono::Weekday::try_from(weekday.number_days_from_monday()).unwrap_or(chrono::Weekday::Mon)
+}
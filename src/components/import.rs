@@ -0,0 +1,196 @@
+use cosmic::{iced::Length, theme::spacing, widget, Element};
+
+use crate::{
+    fl,
+    ical::{detach_overrides, ParsedEvents, SkipReason, Skipped},
+    models::{Calendar, Event, Provider, Source},
+};
+
+/// Preview of an `.ics` file about to be imported, shown in the context drawer.
+#[derive(Debug, Clone)]
+pub struct ImportPreview {
+    file_name: String,
+    events: Vec<Event>,
+    skipped: Vec<Skipped>,
+    /// Calendars the events can be imported into, along with the source they come from.
+    calendars: Vec<(Source, Calendar)>,
+    calendar_names: Vec<String>,
+    selected_calendar: Option<usize>,
+    /// Set while the events are being written, to prevent importing them twice.
+    importing: bool,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Calendar(usize),
+    Import,
+}
+
+impl ImportPreview {
+    /// A preview of `parsed`, to be imported into one of `calendars`.
+    pub fn new(file_name: String, parsed: ParsedEvents, calendars: &[(Source, Calendar)]) -> Self {
+        let calendars = calendars.to_vec();
+        let mut events = parsed.events;
+        detach_overrides(&mut events);
+
+        Self {
+            file_name,
+            events,
+            skipped: parsed.skipped,
+            calendar_names: calendars
+                .iter()
+                .map(|(_, calendar)| calendar.name.clone())
+                .collect(),
+            selected_calendar: (!calendars.is_empty()).then_some(0),
+            calendars,
+            importing: false,
+            error: None,
+        }
+    }
+
+    /// A preview of a file that couldn't be parsed, which only shows the error.
+    pub fn unreadable(file_name: String) -> Self {
+        let mut preview = Self::new(file_name, ParsedEvents::default(), &[]);
+        preview.error = Some(fl!("import-unreadable"));
+        preview
+    }
+
+    /// Returns the calendar to import into and the events to write once the import
+    /// is confirmed.
+    pub fn update(&mut self, message: Message) -> Option<(Source, Calendar, Vec<Event>)> {
+        match message {
+            Message::Calendar(idx) => self.selected_calendar = Some(idx),
+            Message::Import => {
+                let (source, calendar) = self.calendars.get(self.selected_calendar?)?.clone();
+                self.importing = true;
+                self.error = None;
+                let events = self
+                    .split()
+                    .0
+                    .into_iter()
+                    .cloned()
+                    .map(|mut event| {
+                        event.calendar_id = calendar.id.clone();
+                        event.provider = calendar.provider;
+                        event
+                    })
+                    .collect();
+                return Some((source, calendar, events));
+            }
+        }
+        None
+    }
+
+    /// Shows how many events couldn't be written once the import is over.
+    pub fn fail(&mut self, failed: usize) {
+        self.importing = false;
+        self.error = Some(fl!("import-failed", count = failed));
+    }
+
+    /// The events the selected calendar can store, and the ones skipped along with
+    /// those that couldn't be read, such as series with a recurrence Graph can't express.
+    fn split(&self) -> (Vec<&Event>, Vec<Skipped>) {
+        let graph = self
+            .selected_calendar
+            .and_then(|idx| self.calendars.get(idx))
+            .is_some_and(|(_, calendar)| calendar.provider == Provider::Microsoft);
+        let mut events = vec![];
+        let mut skipped = self.skipped.clone();
+        for event in &self.events {
+            if graph && !event.fits_graph() {
+                skipped.push(Skipped {
+                    label: if event.summary.is_empty() {
+                        event.id.clone()
+                    } else {
+                        event.summary.clone()
+                    },
+                    reason: SkipReason::UnsupportedRecurrence,
+                });
+            } else {
+                events.push(event);
+            }
+        }
+        (events, skipped)
+    }
+
+    pub fn view<'a>(&'a self) -> Element<'a, Message> {
+        let mut column = widget::column().push(widget::text::title4(self.file_name.as_str()));
+        let (events, skipped) = self.split();
+
+        // Files that couldn't be read have nothing to preview besides the error.
+        let readable = !self.events.is_empty() || !self.skipped.is_empty();
+        if readable {
+            column = column
+                .push(widget::text::body(fl!(
+                    "import-summary",
+                    count = events.len()
+                )))
+                .push(widget::settings::item(
+                    fl!("calendar"),
+                    widget::dropdown(
+                        &self.calendar_names,
+                        self.selected_calendar,
+                        Message::Calendar,
+                    ),
+                ));
+            if self.calendars.is_empty() {
+                column = column.push(widget::text::caption(fl!("no-editable-calendars")));
+            }
+        }
+
+        if !skipped.is_empty() {
+            let count = skipped.len();
+            let skipped = skipped.into_iter().fold(
+                widget::column().spacing(spacing().space_xxxs),
+                |column, skipped| {
+                    column.push(widget::text::caption(format!(
+                        "{}: {}",
+                        if skipped.label.is_empty() {
+                            fl!("untitled")
+                        } else {
+                            skipped.label
+                        },
+                        reason(&skipped.reason)
+                    )))
+                },
+            );
+            column = column
+                .push(widget::text::heading(fl!("import-skipped", count = count)))
+                .push(widget::scrollable(skipped).height(Length::Shrink));
+        }
+
+        if let Some(error) = &self.error {
+            column = column
+                .push(widget::text::caption(error.as_str()).class(cosmic::style::Text::Accent));
+        }
+
+        column
+            .push(
+                widget::row().push(widget::horizontal_space()).push(
+                    widget::button::suggested(fl!("import")).on_press_maybe(
+                        (!self.importing && !events.is_empty() && self.selected_calendar.is_some())
+                            .then_some(Message::Import),
+                    ),
+                ),
+            )
+            .spacing(spacing().space_s)
+            .into()
+    }
+}
+
+fn reason(reason: &SkipReason) -> String {
+    match reason {
+        SkipReason::NotAnEvent(component) => {
+            fl!("skip-not-an-event", component = component.as_str())
+        }
+        SkipReason::MissingUid => fl!("skip-missing-uid"),
+        SkipReason::MissingStart => fl!("skip-missing-start"),
+        SkipReason::InvalidValue(property) => {
+            fl!("skip-invalid-value", property = property.as_str())
+        }
+        SkipReason::EndsBeforeStart => fl!("skip-ends-before-start"),
+        SkipReason::Cancelled => fl!("skip-cancelled"),
+        SkipReason::UnsupportedRecurrence => fl!("skip-unsupported-recurrence"),
+    }
+}
@@ -1,2 +1,12 @@
+pub mod caldav;
 mod calendar;
-pub use calendar::LocalCalendar;
+pub mod color;
+pub mod editor;
+pub mod import;
+pub mod subscription;
+pub use caldav::CalDavForm;
+pub use calendar::{weekdays, CalendarEvent, DayRange, LocalCalendar, AGENDA_ID};
+pub use color::ColorForm;
+pub use editor::EventEditor;
+pub use import::ImportPreview;
+pub use subscription::SubscriptionForm;
@@ -0,0 +1,72 @@
+use cosmic::{iced::Length, theme::spacing, widget, Element};
+
+use crate::{fl, models::Feed, services::subscription::feed_url};
+
+/// Form for subscribing to a published `.ics` feed, shown in the context drawer.
+#[derive(Debug, Clone, Default)]
+pub struct SubscriptionForm {
+    url: String,
+    /// Set while the feed is being downloaded, to prevent subscribing twice.
+    subscribing: bool,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Url(String),
+    Subscribe,
+}
+
+impl SubscriptionForm {
+    /// Returns the feed to subscribe to once the form is submitted.
+    pub fn update(&mut self, message: Message) -> Option<Feed> {
+        match message {
+            Message::Url(url) => self.url = url,
+            Message::Subscribe => {
+                let Ok(url) = feed_url(&self.url) else {
+                    self.error = Some(fl!("invalid-feed-url"));
+                    return None;
+                };
+                self.error = None;
+                self.subscribing = true;
+                return Some(Feed {
+                    url: url.to_string(),
+                });
+            }
+        }
+        None
+    }
+
+    /// Shows why the feed couldn't be loaded and lets the user try again.
+    pub fn fail(&mut self, error: String) {
+        self.subscribing = false;
+        self.error = Some(error);
+    }
+
+    pub fn view<'a>(&'a self) -> Element<'a, Message> {
+        let mut column = widget::column()
+            .push(widget::text::body(fl!("subscribe-description")))
+            .push(
+                widget::text_input(fl!("feed-url"), &self.url)
+                    .on_input(Message::Url)
+                    .width(Length::Fill),
+            );
+
+        if let Some(error) = &self.error {
+            column = column
+                .push(widget::text::caption(error.as_str()).class(cosmic::style::Text::Accent));
+        }
+
+        column
+            .push(
+                widget::row().push(widget::horizontal_space()).push(
+                    widget::button::suggested(fl!("subscribe")).on_press_maybe(
+                        (!self.subscribing && !self.url.trim().is_empty())
+                            .then_some(Message::Subscribe),
+                    ),
+                ),
+            )
+            .spacing(spacing().space_s)
+            .into()
+    }
+}
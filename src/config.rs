@@ -1,9 +1,99 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
+use std::collections::{BTreeMap, BTreeSet};
+
 use cosmic::cosmic_config::{self, cosmic_config_derive::CosmicConfigEntry, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+use time::Weekday;
+
+use crate::app::Tab;
+use crate::models::{CalDavAccount, Feed, Source};
+use crate::services::subscription::REFRESH_INTERVAL;
 
-#[derive(Debug, Default, Clone, CosmicConfigEntry, Eq, PartialEq)]
+#[derive(Debug, Clone, CosmicConfigEntry, Eq, PartialEq)]
 #[version = 1]
 pub struct Config {
-    demo: String,
+    /// CalDAV servers added in the app, which cosmic accounts doesn't manage.
+    pub caldav_accounts: Vec<CalDavAccount>,
+    /// Remote `.ics` feeds the user subscribed to.
+    pub subscriptions: Vec<Feed>,
+    /// IANA names of the extra time zones whose hours are shown next to the week and day grids.
+    pub secondary_zones: Vec<String>,
+    /// The day weeks start on, or `None` to follow the region of the locale.
+    pub first_weekday: Option<Weekday>,
+    /// Whether the month and week views show the number of each week.
+    pub show_week_numbers: bool,
+    /// The tab shown when the app starts.
+    pub default_view: Tab,
+    pub clock: ClockFormat,
+    /// The hours from the first up to the second which aren't shaded in the time grids.
+    pub working_hours: (u8, u8),
+    /// How long new events last, in minutes.
+    pub event_duration: u32,
+    /// How many minutes before their start new events remind of them, if at all.
+    pub reminder: Option<i64>,
+    /// The calendar new events are added to, as a [`calendar_key`].
+    pub default_calendar: Option<String>,
+    /// The calendars whose events aren't shown, as [`calendar_key`]s.
+    pub hidden_calendars: BTreeSet<String>,
+    /// Colors chosen for calendars instead of the ones their providers report,
+    /// as `#rrggbb` by [`calendar_key`].
+    pub calendar_colors: BTreeMap<String, String>,
+    /// How often the events of every calendar are fetched again, in minutes.
+    pub refresh_interval: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            caldav_accounts: vec![],
+            subscriptions: vec![],
+            secondary_zones: vec![],
+            first_weekday: None,
+            show_week_numbers: false,
+            default_view: Tab::Month,
+            clock: ClockFormat::default(),
+            working_hours: (9, 17),
+            event_duration: 60,
+            reminder: None,
+            default_calendar: None,
+            hidden_calendars: BTreeSet::new(),
+            calendar_colors: BTreeMap::new(),
+            refresh_interval: (REFRESH_INTERVAL.as_secs() / 60) as u32,
+        }
+    }
+}
+
+/// Identifies a calendar across sources, whose calendar ids may clash.
+pub fn calendar_key(source: &Source, calendar_id: &str) -> String {
+    format!("{}/{}", source.key(), calendar_id)
+}
+
+/// Whether times are shown with 24 hours or 12 and AM/PM.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClockFormat {
+    #[default]
+    TwentyFourHour,
+    TwelveHour,
+}
+
+impl ClockFormat {
+    /// A time of day as shown in labels, such as `14:30` or `2:30 PM`.
+    pub fn format(self, hour: u8, minute: u8) -> String {
+        match self {
+            ClockFormat::TwentyFourHour => format!("{:02}:{:02}", hour, minute),
+            ClockFormat::TwelveHour => {
+                let suffix = if hour % 24 < 12 {
+                    crate::fl!("am")
+                } else {
+                    crate::fl!("pm")
+                };
+                let hour = match hour % 12 {
+                    0 => 12,
+                    hour => hour,
+                };
+                format!("{}:{:02} {}", hour, minute, suffix)
+            }
+        }
+    }
 }
@@ -5,22 +5,77 @@ use thiserror::Error;
 pub enum Error {
     #[error("Invalid time component range: {0}")]
     Time(#[from] time::error::ComponentRange),
+    #[error("Time formatting error: {0}")]
+    TimeFormat(#[from] time::error::Format),
     #[error("Date calculation error: {0}")]
     DateCalculation(String),
     #[error("Zbus error: {0}")]
     Zbus(#[from] zbus::Error),
     #[error("Zbus error: {0}")]
     ZbusFdo(#[from] zbus::fdo::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
     #[error("Reqwest error: {0}")]
     Reqwest(#[from] reqwest::Error),
+    /// The provider answered a request with an unsuccessful status.
+    #[error("Failed to {action} ({status}): {message}")]
+    Http {
+        action: String,
+        status: reqwest::StatusCode,
+        message: String,
+    },
     #[error("Serde JSON error: {0}")]
     SerdeJson(#[from] serde_json::Error),
     #[error("Graph error: {0}")]
     GraphError(#[from] graph_rs_sdk::GraphFailure),
     #[error("Google Calendar error: {0}")]
     GCal(#[from] gcal_rs::ClientError),
+    #[error("Calendar \"{0}\" is read-only")]
+    ReadOnlyCalendar(String),
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("XML error: {0}")]
+    Xml(#[from] quick_xml::Error),
+    #[error("Unreadable event time: {0}")]
+    InvalidEventTime(String),
+    #[error("iCalendar error: {0}")]
+    ICalendar(String),
+    #[error("Keyring error: {0}")]
+    Keyring(#[from] oo7::Error),
+    #[error("No password is stored for {0}")]
+    MissingSecret(String),
+    #[error("Event \"{0}\" was changed elsewhere in the meantime")]
+    EventConflict(String),
+    #[error("The sync state expired, a full sync is needed")]
+    SyncTokenExpired,
     #[error("Unknown error: {0}")]
     Unknown(String),
 }
 
+impl Error {
+    /// Whether the provider couldn't be reached at all, as opposed to it refusing the request.
+    pub fn is_offline(&self) -> bool {
+        matches!(self, Error::Reqwest(err) if err.is_connect() || err.is_timeout())
+    }
+
+    /// Whether the provider refused the request for good, so sending it again can't
+    /// succeed. Server errors, rate limits, expired credentials and anything that
+    /// didn't get an answer may go through later.
+    pub fn is_refused(&self) -> bool {
+        match self {
+            Error::EventConflict(_) | Error::ReadOnlyCalendar(_) => true,
+            Error::Http { status, .. } => {
+                status.is_client_error()
+                    && !matches!(
+                        *status,
+                        reqwest::StatusCode::UNAUTHORIZED
+                            | reqwest::StatusCode::REQUEST_TIMEOUT
+                            | reqwest::StatusCode::TOO_MANY_REQUESTS
+                    )
+            }
+            _ => false,
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, Error>;
@@ -10,6 +10,7 @@ use i18n_embed::{
     DefaultLocalizer, LanguageLoader, Localizer,
 };
 use rust_embed::RustEmbed;
+use time::{Month, Weekday};
 
 /// Applies the requested language(s) to requested translations from the `fl!()` macro.
 pub fn init(requested_languages: &[LanguageIdentifier]) {
@@ -49,3 +50,82 @@ macro_rules! fl {
         i18n_embed_fl::fl!($crate::i18n::LANGUAGE_LOADER, $message_id, $($args) *)
     }};
 }
+
+/// The localized name of `weekday`, such as "Monday".
+pub fn weekday(weekday: Weekday) -> String {
+    match weekday {
+        Weekday::Monday => crate::fl!("monday"),
+        Weekday::Tuesday => crate::fl!("tuesday"),
+        Weekday::Wednesday => crate::fl!("wednesday"),
+        Weekday::Thursday => crate::fl!("thursday"),
+        Weekday::Friday => crate::fl!("friday"),
+        Weekday::Saturday => crate::fl!("saturday"),
+        Weekday::Sunday => crate::fl!("sunday"),
+    }
+}
+
+/// The localized abbreviation of `weekday`, such as "Mon".
+pub fn weekday_short(weekday: Weekday) -> String {
+    match weekday {
+        Weekday::Monday => crate::fl!("monday-short"),
+        Weekday::Tuesday => crate::fl!("tuesday-short"),
+        Weekday::Wednesday => crate::fl!("wednesday-short"),
+        Weekday::Thursday => crate::fl!("thursday-short"),
+        Weekday::Friday => crate::fl!("friday-short"),
+        Weekday::Saturday => crate::fl!("saturday-short"),
+        Weekday::Sunday => crate::fl!("sunday-short"),
+    }
+}
+
+/// The localized name of `month`, such as "January".
+pub fn month(month: Month) -> String {
+    match month {
+        Month::January => crate::fl!("january"),
+        Month::February => crate::fl!("february"),
+        Month::March => crate::fl!("march"),
+        Month::April => crate::fl!("april"),
+        Month::May => crate::fl!("may"),
+        Month::June => crate::fl!("june"),
+        Month::July => crate::fl!("july"),
+        Month::August => crate::fl!("august"),
+        Month::September => crate::fl!("september"),
+        Month::October => crate::fl!("october"),
+        Month::November => crate::fl!("november"),
+        Month::December => crate::fl!("december"),
+    }
+}
+
+/// Regions whose weeks start on Sunday or Saturday, by their ISO 3166 codes,
+/// as listed by CLDR. Weeks start on Monday everywhere else.
+const SUNDAY_REGIONS: &[&str] = &[
+    "AG", "AS", "BD", "BR", "BS", "BT", "BW", "BZ", "CA", "CN", "CO", "DM", "DO", "ET", "GT", "GU",
+    "HK", "HN", "ID", "IL", "IN", "JM", "JP", "KE", "KH", "KR", "LA", "MH", "MM", "MO", "MT", "MX",
+    "MZ", "NI", "NP", "PA", "PE", "PH", "PK", "PR", "PT", "PY", "SA", "SG", "SV", "TH", "TT", "TW",
+    "UM", "US", "VE", "VI", "WS", "YE", "ZA", "ZW",
+];
+const SATURDAY_REGIONS: &[&str] = &[
+    "AE", "AF", "BH", "DJ", "DZ", "EG", "IQ", "IR", "JO", "KW", "LY", "OM", "QA", "SD", "SY",
+];
+
+/// The day weeks start on in the region of the time locale, such as `US` in `en_US.UTF-8`.
+pub fn first_weekday() -> Weekday {
+    let locale = ["LC_ALL", "LC_TIME", "LANG"]
+        .into_iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+    let region = locale
+        .split(['.', '@'])
+        .next()
+        .and_then(|name| name.split_once('_'))
+        .map(|(_, region)| region.to_uppercase())
+        .unwrap_or_default();
+
+    if SUNDAY_REGIONS.contains(&region.as_str()) {
+        Weekday::Sunday
+    } else if SATURDAY_REGIONS.contains(&region.as_str()) {
+        Weekday::Saturday
+    } else {
+        Weekday::Monday
+    }
+}
@@ -0,0 +1,1087 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Reading and writing of iCalendar (RFC 5545) data, used by the CalDAV backend
+//! and to import `.ics` files.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+use time::{
+    macros::format_description, Date, Duration, OffsetDateTime, PrimitiveDateTime, UtcOffset,
+};
+
+use time_tz::{OffsetDateTimeExt, TimeZone, Tz};
+
+use self::zones::TimeZones;
+use crate::{
+    models::{
+        zone, Attendee, AttendeeResponse, Event, EventStatus, EventTime, Organizer, Provider,
+        Reminder, ReminderMethod,
+    },
+    Error, Result,
+};
+
+pub mod recurrence;
+mod zones;
+
+/// Identifies the app in the `PRODID` of the calendars it writes.
+const PRODID: &str = "-//cosmic-utils//Calendar//EN";
+
+/// Properties of a `VEVENT` that are written from the fields of an [`Event`].
+const MANAGED_PROPERTIES: &[&str] = &[
+    "UID",
+    "DTSTAMP",
+    "RECURRENCE-ID",
+    "DTSTART",
+    "DTEND",
+    "DURATION",
+    "SUMMARY",
+    "DESCRIPTION",
+    "LOCATION",
+    "STATUS",
+    "SEQUENCE",
+    "TRANSP",
+    "CLASS",
+    "URL",
+    "RRULE",
+    "RDATE",
+    "EXDATE",
+    "ORGANIZER",
+    "ATTENDEE",
+];
+
+/// A content line, e.g. `DTSTART;TZID=Europe/Paris:20240101T100000`.
+#[derive(Debug, Clone)]
+pub struct Property {
+    pub name: String,
+    pub params: Vec<(String, String)>,
+    pub value: String,
+}
+
+/// A `BEGIN:`/`END:` block along with its properties and nested blocks.
+#[derive(Debug, Clone, Default)]
+pub struct Component {
+    pub name: String,
+    pub properties: Vec<Property>,
+    pub components: Vec<Component>,
+}
+
+impl Property {
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Writes the property back as an unfolded content line.
+    pub fn to_line(&self) -> String {
+        let mut line = self.name.clone();
+        for (key, value) in &self.params {
+            line.push(';');
+            line.push_str(key);
+            line.push('=');
+            line.push_str(&param_value(value));
+        }
+        line.push(':');
+        line.push_str(&self.value);
+        line
+    }
+}
+
+impl Component {
+    pub fn property(&self, name: &str) -> Option<&Property> {
+        self.properties
+            .iter()
+            .find(|p| p.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn properties<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Property> {
+        self.properties
+            .iter()
+            .filter(move |p| p.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn components<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Component> {
+        self.components
+            .iter()
+            .filter(move |c| c.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Writes the component back as unfolded content lines.
+    pub fn to_lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("BEGIN:{}", self.name)];
+        lines.extend(self.properties.iter().map(Property::to_line));
+        lines.extend(self.components.iter().flat_map(Component::to_lines));
+        lines.push(format!("END:{}", self.name));
+        lines
+    }
+
+    /// The unescaped value of a text property, if it's present and not empty.
+    pub fn text(&self, name: &str) -> Option<String> {
+        self.property(name)
+            .map(|p| unescape(&p.value))
+            .filter(|text| !text.is_empty())
+    }
+}
+
+/// Parses every top-level component (normally one or more `VCALENDAR`s) in `input`.
+pub fn parse(input: &str) -> Result<Vec<Component>> {
+    let mut stack: Vec<Component> = vec![];
+    let mut components = vec![];
+
+    for line in unfold(input) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let property = parse_line(&line)?;
+        if property.name.eq_ignore_ascii_case("BEGIN") {
+            stack.push(Component {
+                name: property.value.to_uppercase(),
+                ..Default::default()
+            });
+        } else if property.name.eq_ignore_ascii_case("END") {
+            let component = stack
+                .pop()
+                .ok_or_else(|| Error::ICalendar(format!("unexpected END:{}", property.value)))?;
+            if !component.name.eq_ignore_ascii_case(&property.value) {
+                return Err(Error::ICalendar(format!(
+                    "BEGIN:{} closed by END:{}",
+                    component.name, property.value
+                )));
+            }
+            match stack.last_mut() {
+                Some(parent) => parent.components.push(component),
+                None => components.push(component),
+            }
+        } else if let Some(current) = stack.last_mut() {
+            current.properties.push(property);
+        }
+    }
+
+    if let Some(component) = stack.pop() {
+        return Err(Error::ICalendar(format!(
+            "BEGIN:{} is never closed",
+            component.name
+        )));
+    }
+    Ok(components)
+}
+
+/// The events read from iCalendar data, along with the components that were left out.
+#[derive(Debug, Clone, Default)]
+pub struct ParsedEvents {
+    pub events: Vec<Event>,
+    pub skipped: Vec<Skipped>,
+}
+
+/// A component that couldn't be turned into an event.
+#[derive(Debug, Clone)]
+pub struct Skipped {
+    /// The `SUMMARY` of the component, or its `UID` when it has no summary.
+    pub label: String,
+    pub reason: SkipReason,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    /// A to-do, journal entry or other component that isn't an event.
+    NotAnEvent(String),
+    MissingUid,
+    MissingStart,
+    /// The named property has a value that can't be read.
+    InvalidValue(String),
+    EndsBeforeStart,
+    Cancelled,
+    /// A recurrence the calendar it's imported into can't store.
+    UnsupportedRecurrence,
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkipReason::NotAnEvent(component) => write!(f, "{component} is not an event"),
+            SkipReason::MissingUid => write!(f, "no UID"),
+            SkipReason::MissingStart => write!(f, "no DTSTART"),
+            SkipReason::InvalidValue(property) => write!(f, "invalid {property}"),
+            SkipReason::EndsBeforeStart => write!(f, "ends before it starts"),
+            SkipReason::Cancelled => write!(f, "cancelled"),
+            SkipReason::UnsupportedRecurrence => write!(f, "unsupported recurrence"),
+        }
+    }
+}
+
+/// Reads the `VEVENT`s of `input` as events of `calendar_id`, noting every
+/// component that was skipped and why.
+pub fn read_events(input: &str, calendar_id: &str, provider: Provider) -> Result<ParsedEvents> {
+    let mut parsed = ParsedEvents::default();
+    for calendar in parse(input)? {
+        let zones = TimeZones::from_calendar(&calendar);
+        for component in &calendar.components {
+            let label = || {
+                component
+                    .text("SUMMARY")
+                    .or_else(|| component.text("UID"))
+                    .unwrap_or_default()
+            };
+            let result = match component.name.as_str() {
+                "VEVENT" => event_from_component(component, &zones, calendar_id, provider),
+                "VTIMEZONE" => continue,
+                name => Err(SkipReason::NotAnEvent(name.to_string())),
+            };
+            match result {
+                Ok(event) => parsed.events.push(event),
+                Err(reason) => parsed.skipped.push(Skipped {
+                    label: label(),
+                    reason,
+                }),
+            }
+        }
+    }
+    Ok(parsed)
+}
+
+/// Converts a single `VEVENT` into an event, resolving its times with `zones`.
+fn event_from_component(
+    vevent: &Component,
+    zones: &TimeZones,
+    calendar_id: &str,
+    provider: Provider,
+) -> std::result::Result<Event, SkipReason> {
+    let uid = vevent.text("UID").ok_or(SkipReason::MissingUid)?;
+    let dtstart = vevent.property("DTSTART").ok_or(SkipReason::MissingStart)?;
+    let start = parse_time(dtstart, zones)?;
+    let end = match (vevent.property("DTEND"), vevent.property("DURATION")) {
+        (Some(dtend), _) => parse_time(dtend, zones)?,
+        (None, Some(duration)) => add_duration(
+            start,
+            parse_duration(&duration.value)
+                .map_err(|_| SkipReason::InvalidValue("DURATION".to_string()))?,
+        ),
+        // Without an end, all-day events last a day and timed ones are instantaneous.
+        (None, None) => match start {
+            EventTime::Date(date) => EventTime::Date(date.next_day().unwrap_or(date)),
+            EventTime::DateTime(_) => start,
+        },
+    };
+    if end.at_offset(UtcOffset::UTC) < start.at_offset(UtcOffset::UTC) {
+        return Err(SkipReason::EndsBeforeStart);
+    }
+    let status = match vevent.property("STATUS").map(|p| p.value.to_uppercase()) {
+        Some(status) if status == "TENTATIVE" => EventStatus::Tentative,
+        Some(status) if status == "CANCELLED" => return Err(SkipReason::Cancelled),
+        _ => EventStatus::Confirmed,
+    };
+
+    let mut extra = HashMap::new();
+    for name in [
+        "SEQUENCE",
+        "TRANSP",
+        "CLASS",
+        "URL",
+        "LAST-MODIFIED",
+        "CREATED",
+    ] {
+        if let Some(property) = vevent.property(name) {
+            extra.insert(name.to_string(), Value::String(property.value.clone()));
+        }
+    }
+    if let Some(recurrence_id) = vevent.property("RECURRENCE-ID") {
+        extra.insert(
+            "RECURRENCE-ID".to_string(),
+            Value::String(recurrence_id.to_line()),
+        );
+    }
+
+    Ok(Event {
+        id: uid,
+        calendar_id: calendar_id.to_string(),
+        summary: vevent.text("SUMMARY").unwrap_or_default(),
+        description: vevent.text("DESCRIPTION"),
+        location: vevent.text("LOCATION"),
+        // Only IANA names are kept, zones like "W. Europe Standard Time" mean nothing to providers.
+        time_zone: dtstart
+            .param("TZID")
+            .filter(|tzid| tzid.contains('/'))
+            .map(str::to_string),
+        start,
+        end,
+        attendees: vevent
+            .properties("ATTENDEE")
+            .map(|p| Attendee {
+                email: strip_mailto(&p.value),
+                name: p.param("CN").map(str::to_string),
+                response: match p.param("PARTSTAT") {
+                    Some(status) if status.eq_ignore_ascii_case("ACCEPTED") => {
+                        AttendeeResponse::Accepted
+                    }
+                    Some(status) if status.eq_ignore_ascii_case("TENTATIVE") => {
+                        AttendeeResponse::Tentative
+                    }
+                    Some(status) if status.eq_ignore_ascii_case("DECLINED") => {
+                        AttendeeResponse::Declined
+                    }
+                    _ => AttendeeResponse::NeedsAction,
+                },
+                optional: p
+                    .param("ROLE")
+                    .is_some_and(|role| role.eq_ignore_ascii_case("OPT-PARTICIPANT")),
+            })
+            .collect(),
+        organizer: vevent.property("ORGANIZER").map(|p| Organizer {
+            email: Some(strip_mailto(&p.value)),
+            name: p.param("CN").map(str::to_string),
+        }),
+        status,
+        recurrence: vevent
+            .properties
+            .iter()
+            .filter(|p| {
+                ["RRULE", "RDATE", "EXDATE"]
+                    .iter()
+                    .any(|name| p.name.eq_ignore_ascii_case(name))
+            })
+            .map(Property::to_line)
+            .collect(),
+        reminders: vevent
+            .components("VALARM")
+            .filter_map(|valarm| reminder(valarm, start, end, zones))
+            .collect(),
+        provider,
+        extra,
+    })
+}
+
+/// Reads a `VALARM` as a reminder some minutes before the start of the event.
+/// Triggers relative to the end or at a fixed time are converted accordingly.
+fn reminder(
+    valarm: &Component,
+    start: EventTime,
+    end: EventTime,
+    zones: &TimeZones,
+) -> Option<Reminder> {
+    let trigger = valarm.property("TRIGGER")?;
+    let start = start.at_offset(UtcOffset::UTC);
+    let fires_at = if trigger
+        .param("VALUE")
+        .is_some_and(|value| value.eq_ignore_ascii_case("DATE-TIME"))
+    {
+        parse_time(trigger, zones).ok()?.at_offset(UtcOffset::UTC)
+    } else {
+        let related = match trigger.param("RELATED") {
+            Some(related) if related.eq_ignore_ascii_case("END") => end.at_offset(UtcOffset::UTC),
+            _ => start,
+        };
+        related + parse_duration(&trigger.value).ok()?
+    };
+    let method = match valarm.property("ACTION") {
+        Some(action) if action.value.eq_ignore_ascii_case("EMAIL") => ReminderMethod::Email,
+        _ => ReminderMethod::Notification,
+    };
+    Some(Reminder {
+        minutes: (start - fires_at).whole_minutes(),
+        method,
+    })
+}
+
+/// Turns overridden occurrences into events of their own, excluding them from their
+/// series with an `EXDATE`, for targets that can't store an override of a series.
+/// Detached occurrences take the id of the occurrence, so they don't share the UID
+/// of their series.
+pub fn detach_overrides(events: &mut [Event]) {
+    let overrides: Vec<(String, String)> = events
+        .iter_mut()
+        .filter_map(|event| {
+            let start = recurrence::recurrence_id(event);
+            let Some(Value::String(line)) = event.extra.remove("RECURRENCE-ID") else {
+                return None;
+            };
+            let uid = event.id.clone();
+            if let Some(start) = start {
+                event.id = recurrence::occurrence_id(&uid, start);
+            }
+            Some((uid, line))
+        })
+        .collect();
+
+    for (uid, line) in overrides {
+        let Some(series) = events
+            .iter_mut()
+            .find(|event| event.id == uid && !event.recurrence.is_empty())
+        else {
+            continue;
+        };
+        if let Some(rest) = line.strip_prefix("RECURRENCE-ID") {
+            series.recurrence.push(format!("EXDATE{rest}"));
+        }
+    }
+}
+
+/// Writes `event` as a `VCALENDAR` holding a single `VEVENT`.
+pub fn write_event(event: &Event) -> String {
+    write_calendar(None, std::slice::from_ref(event))
+}
+
+/// Writes `events` as a `VCALENDAR`, named `name` when given, with a `VTIMEZONE`
+/// for every zone the events are scheduled in.
+pub fn write_calendar(name: Option<&str>, events: &[Event]) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{PRODID}"),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+    if let Some(name) = name {
+        lines.push(format!("X-WR-CALNAME:{}", escape(name)));
+    }
+
+    let mut zones: Vec<&Tz> = events.iter().filter_map(event_zone).collect();
+    zones.sort_by_key(|tz| tz.name());
+    zones.dedup_by_key(|tz| tz.name());
+    for tz in zones {
+        let zone_events: Vec<&Event> = events
+            .iter()
+            .filter(|event| event_zone(event).is_some_and(|zone| zone.name() == tz.name()))
+            .collect();
+        let (from, to) = zone_years(&zone_events);
+        lines.extend(zones::vtimezone(tz, from, to));
+    }
+
+    for event in events {
+        lines.extend(event_lines(event));
+    }
+    lines.push("END:VCALENDAR".to_string());
+    fold(&lines)
+}
+
+/// Replaces the `VEVENT` of `event` in a stored calendar resource, or adds it when
+/// it's a new override of an occurrence. The other components, such as overridden
+/// occurrences and time zones, are kept as they are, and so are the properties of the
+/// event the app doesn't model.
+pub fn update_resource(input: &str, event: &Event) -> Result<String> {
+    let recurrence_id = match event.extra.get("RECURRENCE-ID") {
+        Some(Value::String(line)) => Some(line.as_str()),
+        _ => None,
+    };
+
+    let mut written = false;
+    let mut lines = vec![];
+    for calendar in parse(input)? {
+        lines.push(format!("BEGIN:{}", calendar.name));
+        lines.extend(calendar.properties.iter().map(Property::to_line));
+        // The event may have moved to a zone the resource doesn't define yet.
+        if let Some(tz) = event_zone(event) {
+            let defined = calendar.components("VTIMEZONE").any(|vtimezone| {
+                vtimezone.property("TZID").map(|tzid| tzid.value.as_str()) == Some(tz.name())
+            });
+            if !defined {
+                let (from, to) = zone_years(&[event]);
+                lines.extend(zones::vtimezone(tz, from, to));
+            }
+        }
+        for component in &calendar.components {
+            let is_edited = component.name == "VEVENT"
+                && component.text("UID").as_deref() == Some(event.id.as_str())
+                && component
+                    .property("RECURRENCE-ID")
+                    .map(Property::to_line)
+                    .as_deref()
+                    == recurrence_id;
+            if !is_edited {
+                lines.extend(component.to_lines());
+                continue;
+            }
+
+            written = true;
+            let mut event_lines = event_lines(event);
+            let end = event_lines.pop();
+            lines.extend(event_lines);
+            lines.extend(
+                component
+                    .properties
+                    .iter()
+                    .filter(|p| !MANAGED_PROPERTIES.contains(&p.name.as_str()))
+                    .map(Property::to_line),
+            );
+            lines.extend(
+                component
+                    .components
+                    .iter()
+                    .filter(|c| c.name != "VALARM")
+                    .flat_map(Component::to_lines),
+            );
+            lines.extend(end);
+        }
+        if !written {
+            written = true;
+            lines.extend(event_lines(event));
+        }
+        lines.push(format!("END:{}", calendar.name));
+    }
+    Ok(fold(&lines))
+}
+
+/// Removes the overrides of the series `uid` from a stored calendar resource when
+/// `remove` selects the original start of the occurrence they replace.
+pub fn remove_overrides(
+    input: &str,
+    uid: &str,
+    remove: impl Fn(EventTime) -> bool,
+) -> Result<String> {
+    let mut lines = vec![];
+    for calendar in parse(input)? {
+        let zones = TimeZones::from_calendar(&calendar);
+        lines.push(format!("BEGIN:{}", calendar.name));
+        lines.extend(calendar.properties.iter().map(Property::to_line));
+        for component in &calendar.components {
+            let is_removed = component.name == "VEVENT"
+                && component.text("UID").as_deref() == Some(uid)
+                && component
+                    .property("RECURRENCE-ID")
+                    .and_then(|recurrence_id| parse_time(recurrence_id, &zones).ok())
+                    .is_some_and(&remove);
+            if !is_removed {
+                lines.extend(component.to_lines());
+            }
+        }
+        lines.push(format!("END:{}", calendar.name));
+    }
+    Ok(fold(&lines))
+}
+
+/// The content lines of the `VEVENT` describing `event`.
+pub fn event_lines(event: &Event) -> Vec<String> {
+    let mut lines = vec![
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}", event.id),
+        format!("DTSTAMP:{}", format_utc(OffsetDateTime::now_utc())),
+    ];
+    if let Some(Value::String(recurrence_id)) = event.extra.get("RECURRENCE-ID") {
+        lines.push(recurrence_id.clone());
+    }
+    let zone = event_zone(event);
+    lines.push(time_line("DTSTART", &event.start, zone));
+    lines.push(time_line("DTEND", &event.end, zone));
+    lines.push(format!("SUMMARY:{}", escape(&event.summary)));
+    if let Some(description) = &event.description {
+        lines.push(format!("DESCRIPTION:{}", escape(description)));
+    }
+    if let Some(location) = &event.location {
+        lines.push(format!("LOCATION:{}", escape(location)));
+    }
+    lines.push(format!(
+        "STATUS:{}",
+        match event.status {
+            EventStatus::Confirmed => "CONFIRMED",
+            EventStatus::Tentative => "TENTATIVE",
+            EventStatus::Cancelled => "CANCELLED",
+        }
+    ));
+    for name in ["SEQUENCE", "TRANSP", "CLASS", "URL"] {
+        if let Some(Value::String(value)) = event.extra.get(name) {
+            lines.push(format!("{name}:{value}"));
+        }
+    }
+    lines.extend(event.recurrence.iter().cloned());
+    if let Some(organizer) = &event.organizer {
+        if let Some(email) = &organizer.email {
+            lines.push(match &organizer.name {
+                Some(name) => format!("ORGANIZER;CN={}:mailto:{email}", param_value(name)),
+                None => format!("ORGANIZER:mailto:{email}"),
+            });
+        }
+    }
+    for attendee in &event.attendees {
+        let mut line = "ATTENDEE".to_string();
+        if let Some(name) = &attendee.name {
+            line.push_str(&format!(";CN={}", param_value(name)));
+        }
+        line.push_str(&format!(
+            ";PARTSTAT={}",
+            match attendee.response {
+                AttendeeResponse::NeedsAction => "NEEDS-ACTION",
+                AttendeeResponse::Accepted => "ACCEPTED",
+                AttendeeResponse::Tentative => "TENTATIVE",
+                AttendeeResponse::Declined => "DECLINED",
+            }
+        ));
+        if attendee.optional {
+            line.push_str(";ROLE=OPT-PARTICIPANT");
+        }
+        line.push_str(&format!(":mailto:{}", attendee.email));
+        lines.push(line);
+    }
+    for reminder in &event.reminders {
+        lines.push("BEGIN:VALARM".to_string());
+        match reminder.method {
+            ReminderMethod::Notification => {
+                lines.push("ACTION:DISPLAY".to_string());
+                lines.push(format!("DESCRIPTION:{}", escape(&event.summary)));
+            }
+            ReminderMethod::Email => {
+                lines.push("ACTION:EMAIL".to_string());
+                lines.push(format!("SUMMARY:{}", escape(&event.summary)));
+                lines.push(format!("DESCRIPTION:{}", escape(&event.summary)));
+            }
+        }
+        lines.push(if reminder.minutes >= 0 {
+            format!("TRIGGER:-PT{}M", reminder.minutes)
+        } else {
+            format!("TRIGGER:PT{}M", -reminder.minutes)
+        });
+        lines.push("END:VALARM".to_string());
+    }
+    lines.push("END:VEVENT".to_string());
+    lines
+}
+
+/// Joins content lines with CRLF, folding them at 75 octets as RFC 5545 requires.
+pub fn fold(lines: &[String]) -> String {
+    let mut output = String::new();
+    for line in lines {
+        let mut width = 0;
+        for c in line.chars() {
+            if width + c.len_utf8() > 75 {
+                output.push_str("\r\n ");
+                width = 1;
+            }
+            output.push(c);
+            width += c.len_utf8();
+        }
+        output.push_str("\r\n");
+    }
+    output
+}
+
+/// Writes a start or end time, in the event's zone when it has one and in UTC otherwise.
+fn time_line(name: &str, time: &EventTime, zone: Option<&Tz>) -> String {
+    match (time, zone) {
+        (EventTime::Date(date), _) => format!("{name};VALUE=DATE:{}", format_date(*date)),
+        (EventTime::DateTime(date_time), Some(tz)) => format!(
+            "{name};TZID={}:{}",
+            tz.name(),
+            date_time
+                .to_timezone(tz)
+                .format(format_description!(
+                    "[year][month][day]T[hour][minute][second]"
+                ))
+                .unwrap_or_default()
+        ),
+        (EventTime::DateTime(date_time), None) => format!("{name}:{}", format_utc(*date_time)),
+    }
+}
+
+/// The IANA zone a timed event was scheduled in, if the time zone database knows it.
+fn event_zone(event: &Event) -> Option<&'static Tz> {
+    if event.is_all_day() {
+        return None;
+    }
+    event.zone()
+}
+
+/// The years a `VTIMEZONE` needs to cover for `events`. Recurring events may go on
+/// indefinitely, so their zones are written ten years past the current one.
+fn zone_years(events: &[&Event]) -> (i32, i32) {
+    let from = events
+        .iter()
+        .map(|event| event.start.at_offset(UtcOffset::UTC).year())
+        .min()
+        .unwrap_or_else(|| OffsetDateTime::now_utc().year());
+    let mut to = events
+        .iter()
+        .map(|event| event.end.at_offset(UtcOffset::UTC).year())
+        .max()
+        .unwrap_or(from);
+    if events.iter().any(|event| !event.recurrence.is_empty()) {
+        to = to.max(OffsetDateTime::now_utc().year() + 10);
+    }
+    (from, to.max(from))
+}
+
+pub fn format_date(date: Date) -> String {
+    date.format(format_description!("[year][month][day]"))
+        .unwrap_or_default()
+}
+
+pub fn format_utc(date_time: OffsetDateTime) -> String {
+    date_time
+        .to_offset(UtcOffset::UTC)
+        .format(format_description!(
+            "[year][month][day]T[hour][minute][second]Z"
+        ))
+        .unwrap_or_default()
+}
+
+/// Parses a `DATE` or `DATE-TIME` property value. Times with a `TZID` are resolved
+/// with the zones defined in the file, then with the time zone database, and
+/// otherwise in the system zone like floating times.
+fn parse_time(
+    property: &Property,
+    zones: &TimeZones,
+) -> std::result::Result<EventTime, SkipReason> {
+    let invalid = || SkipReason::InvalidValue(property.name.clone());
+    let value = property.value.trim();
+    let is_date = property
+        .param("VALUE")
+        .is_some_and(|v| v.eq_ignore_ascii_case("DATE"))
+        || value.len() == 8;
+    if is_date {
+        return Date::parse(value, format_description!("[year][month][day]"))
+            .map(EventTime::Date)
+            .map_err(|_| invalid());
+    }
+
+    let (local, utc) = match value.strip_suffix(['Z', 'z']) {
+        Some(value) => (value, true),
+        None => (value, false),
+    };
+    let local = PrimitiveDateTime::parse(
+        local,
+        format_description!("[year][month][day]T[hour][minute][second]"),
+    )
+    .map_err(|_| invalid())?;
+
+    if utc {
+        return Ok(EventTime::DateTime(local.assume_utc()));
+    }
+    let tzid = property.param("TZID");
+    if let Some(offset) = tzid.and_then(|tzid| zones.offset_at(tzid, local)) {
+        return Ok(EventTime::DateTime(local.assume_offset(offset)));
+    }
+    let zone = tzid.and_then(zone::named).unwrap_or_else(zone::system);
+    Ok(EventTime::DateTime(zone::resolve(local, zone)))
+}
+
+/// Parses a `DURATION` value such as `PT1H30M`, `-P1D` or `P2W`.
+pub fn parse_duration(value: &str) -> Result<Duration> {
+    let invalid = || Error::ICalendar(format!("invalid duration {value}"));
+    let value = value.trim();
+    let (sign, rest) = match value.as_bytes().first() {
+        Some(b'-') => (-1, &value[1..]),
+        Some(b'+') => (1, &value[1..]),
+        _ => (1, value),
+    };
+    let rest = rest.strip_prefix('P').ok_or_else(invalid)?;
+
+    let mut duration = Duration::ZERO;
+    let mut number = String::new();
+    let mut in_time = false;
+    for c in rest.chars() {
+        match c {
+            'T' => in_time = true,
+            '0'..='9' => number.push(c),
+            unit => {
+                let n: i64 = number.parse().map_err(|_| invalid())?;
+                number.clear();
+                duration += match (unit, in_time) {
+                    ('W', false) => Duration::weeks(n),
+                    ('D', false) => Duration::days(n),
+                    ('H', true) => Duration::hours(n),
+                    ('M', true) => Duration::minutes(n),
+                    ('S', true) => Duration::seconds(n),
+                    _ => return Err(invalid()),
+                };
+            }
+        }
+    }
+    Ok(duration * sign)
+}
+
+fn add_duration(start: EventTime, duration: Duration) -> EventTime {
+    match start {
+        EventTime::Date(date) => EventTime::Date(date + Duration::days(duration.whole_days())),
+        EventTime::DateTime(date_time) => EventTime::DateTime(date_time + duration),
+    }
+}
+
+fn strip_mailto(value: &str) -> String {
+    value
+        .strip_prefix("mailto:")
+        .or_else(|| value.strip_prefix("MAILTO:"))
+        .unwrap_or(value)
+        .to_string()
+}
+
+/// Joins folded lines, which continue on the next line after a single space or tab.
+fn unfold(input: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for line in input.lines() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+fn parse_line(line: &str) -> Result<Property> {
+    // Parameters end at semicolons and the value starts at the first colon, unless
+    // they're inside a quoted parameter value.
+    let mut in_quotes = false;
+    let mut head = vec![];
+    let mut start = 0;
+    let mut value = None;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ';' if !in_quotes => {
+                head.push(&line[start..i]);
+                start = i + 1;
+            }
+            ':' if !in_quotes => {
+                head.push(&line[start..i]);
+                value = Some(&line[i + 1..]);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let value = value.ok_or_else(|| Error::ICalendar(format!("invalid content line: {line}")))?;
+
+    let mut parts = head.into_iter();
+    let name = parts.next().unwrap_or_default().to_uppercase();
+    let params = parts
+        .filter_map(|param| {
+            let (key, value) = param.split_once('=')?;
+            Some((key.to_uppercase(), value.trim_matches('"').to_string()))
+        })
+        .collect();
+
+    Ok(Property {
+        name,
+        params,
+        value: value.to_string(),
+    })
+}
+
+/// Writes a parameter value, quoted when it holds a character that would end it.
+/// Values can't hold DQUOTE or line breaks even when quoted, so those are replaced,
+/// except for the quotes between the items of a list like `MEMBER="a","b"`.
+fn param_value(value: &str) -> String {
+    if value.contains("\",\"") {
+        return value
+            .split("\",\"")
+            .map(|item| format!("\"{}\"", param_text(item)))
+            .collect::<Vec<_>>()
+            .join(",");
+    }
+    let value = param_text(value);
+    if value.contains([':', ';', ',']) {
+        format!("\"{value}\"")
+    } else {
+        value
+    }
+}
+
+fn param_text(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            '"' => '\'',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n' | 'N') => output.push('\n'),
+                Some(other) => output.push(other),
+                None => {}
+            }
+        } else {
+            output.push(c);
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+    use crate::models::fixtures::event;
+
+    /// A daily series with two overridden occurrences and a property the app doesn't know.
+    const RESOURCE: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+PRODID:-//Test//EN\r
+BEGIN:VEVENT\r
+UID:standup\r
+DTSTAMP:20240101T000000Z\r
+DTSTART:20240304T100000Z\r
+DTEND:20240304T110000Z\r
+SUMMARY:Standup\r
+RRULE:FREQ=DAILY;COUNT=5\r
+X-CUSTOM:kept\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:standup\r
+DTSTAMP:20240101T000000Z\r
+RECURRENCE-ID:20240305T100000Z\r
+DTSTART:20240305T120000Z\r
+DTEND:20240305T130000Z\r
+SUMMARY:Late standup\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:standup\r
+DTSTAMP:20240101T000000Z\r
+RECURRENCE-ID:20240306T100000Z\r
+DTSTART:20240306T090000Z\r
+DTEND:20240306T100000Z\r
+SUMMARY:Early standup\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+    fn read(input: &str) -> Vec<Event> {
+        read_events(input, "work", Provider::CalDav).unwrap().events
+    }
+
+    fn summaries(events: &[Event]) -> Vec<&str> {
+        events.iter().map(|event| event.summary.as_str()).collect()
+    }
+
+    #[test]
+    fn folded_lines_round_trip() {
+        let mut written = event(
+            "work",
+            "trip",
+            datetime!(2024-03-04 10:00 UTC),
+            datetime!(2024-03-04 11:00 UTC),
+        );
+        written.summary = "Wycieczka do Łodzi, Gdańska i Żółkiewki; ".repeat(5);
+        let output = write_event(&written);
+
+        assert!(output.split("\r\n").all(|line| line.len() <= 75));
+        let read = read(&output);
+        assert_eq!(summaries(&read), [written.summary.as_str()]);
+    }
+
+    #[test]
+    fn continuation_lines_are_unfolded() {
+        let input = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:trip\r\n\
+            DTSTART:20240304T100000Z\r\nSUMMARY:A long\r\n  trip\r\n\tby train\r\n\
+            END:VEVENT\r\nEND:VCALENDAR\r\n";
+        assert_eq!(summaries(&read(input)), ["A long tripby train"]);
+    }
+
+    #[test]
+    fn quoted_parameters_keep_separators() {
+        let input = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:review\r\n\
+            DTSTART:20240304T100000Z\r\n\
+            ORGANIZER;CN=\"Sales: EMEA\":mailto:sales@example.com\r\n\
+            ATTENDEE;CN=\"Doe; John\";PARTSTAT=ACCEPTED:mailto:john@example.com\r\n\
+            END:VEVENT\r\nEND:VCALENDAR\r\n";
+        let read = read(input);
+
+        let organizer = read[0].organizer.as_ref().unwrap();
+        assert_eq!(organizer.name.as_deref(), Some("Sales: EMEA"));
+        assert_eq!(organizer.email.as_deref(), Some("sales@example.com"));
+        let attendee = &read[0].attendees[0];
+        assert_eq!(attendee.name.as_deref(), Some("Doe; John"));
+        assert_eq!(attendee.response, AttendeeResponse::Accepted);
+        assert_eq!(attendee.email, "john@example.com");
+    }
+
+    #[test]
+    fn quotes_in_names_are_replaced() {
+        let mut written = event(
+            "work",
+            "review",
+            datetime!(2024-03-04 10:00 UTC),
+            datetime!(2024-03-04 11:00 UTC),
+        );
+        written.attendees.push(Attendee {
+            email: "ann@example.com".to_string(),
+            name: Some("Ann \"The Boss\", PhD".to_string()),
+            response: AttendeeResponse::NeedsAction,
+            optional: false,
+        });
+        let output = write_event(&written);
+
+        assert!(output.contains("ATTENDEE;CN=\"Ann 'The Boss', PhD\";"));
+        let attendee = &read(&output)[0].attendees[0];
+        assert_eq!(attendee.name.as_deref(), Some("Ann 'The Boss', PhD"));
+        assert_eq!(attendee.email, "ann@example.com");
+    }
+
+    #[test]
+    fn zoned_times_resolve_with_the_written_vtimezone() {
+        let mut summer = event(
+            "work",
+            "summer",
+            datetime!(2024-07-01 10:00 +2),
+            datetime!(2024-07-01 11:00 +2),
+        );
+        summer.time_zone = Some("Europe/Warsaw".to_string());
+        let mut winter = event(
+            "work",
+            "winter",
+            datetime!(2024-01-15 10:00 +1),
+            datetime!(2024-01-15 11:00 +1),
+        );
+        winter.time_zone = Some("Europe/Warsaw".to_string());
+        let output = write_calendar(None, &[summer.clone(), winter.clone()]);
+
+        assert_eq!(output.matches("BEGIN:VTIMEZONE").count(), 1);
+        assert!(output.contains("DTSTART;TZID=Europe/Warsaw:20240701T100000"));
+        for (read, written) in read(&output).iter().zip([summer, winter]) {
+            assert_eq!(read.start, written.start);
+            assert_eq!(read.end, written.end);
+            assert_eq!(read.time_zone.as_deref(), Some("Europe/Warsaw"));
+        }
+    }
+
+    #[test]
+    fn updating_a_series_keeps_its_overrides() {
+        let mut series = read(RESOURCE).remove(0);
+        series.summary = "Daily".to_string();
+        let output = update_resource(RESOURCE, &series).unwrap();
+
+        assert!(output.contains("X-CUSTOM:kept"));
+        assert_eq!(
+            summaries(&read(&output)),
+            ["Daily", "Late standup", "Early standup"]
+        );
+    }
+
+    #[test]
+    fn updating_an_override_replaces_only_it() {
+        let mut late = read(RESOURCE).remove(1);
+        late.summary = "Later standup".to_string();
+        let output = update_resource(RESOURCE, &late).unwrap();
+
+        let read = read(&output);
+        assert_eq!(
+            summaries(&read),
+            ["Standup", "Later standup", "Early standup"]
+        );
+        assert_eq!(
+            recurrence::recurrence_id(&read[1]),
+            Some(EventTime::DateTime(datetime!(2024-03-05 10:00 UTC)))
+        );
+    }
+
+    #[test]
+    fn removing_overrides_keeps_the_others() {
+        let removed = EventTime::DateTime(datetime!(2024-03-06 10:00 UTC));
+        let output = remove_overrides(RESOURCE, "standup", |start| start == removed).unwrap();
+
+        assert_eq!(summaries(&read(&output)), ["Standup", "Late standup"]);
+    }
+}
@@ -0,0 +1,1013 @@
+//! Expansion of recurring series into their occurrences, following the `RRULE`,
+//! `RDATE` and `EXDATE` lines of an event. Rules are evaluated in the local time of
+//! the zone the series was scheduled in, so occurrences keep their time of day
+//! across daylight saving changes.
+
+use serde_json::Value;
+use time::{
+    macros::format_description, Date, Duration, Month, OffsetDateTime, PrimitiveDateTime, Time,
+    UtcOffset, Weekday,
+};
+use time_tz::{OffsetDateTimeExt, Tz};
+
+use super::{
+    event_zone, format_date, format_utc, parse_line, time_line,
+    zones::{parse_local, parse_weekday},
+    Property,
+};
+use crate::{
+    models::{zone, Event, EventTime},
+    Error, Result,
+};
+
+/// How many periods of a rule are looked at before giving up, for rules
+/// that never produce an occurrence.
+const MAX_PERIODS: usize = 100_000;
+
+/// Expands the recurring series among `events` into their occurrences overlapping
+/// `[start, end)`. Occurrences get an id of their own and point back to their series
+/// with `recurringEventId`, like the instances Google returns, and overridden ones
+/// take the place of the occurrence named by their `RECURRENCE-ID`. Events that
+/// don't recur are kept when they overlap the range.
+pub fn expand(events: Vec<Event>, start: OffsetDateTime, end: OffsetDateTime) -> Vec<Event> {
+    let (overrides, events): (Vec<Event>, Vec<Event>) = events
+        .into_iter()
+        .partition(|event| event.extra.contains_key("RECURRENCE-ID"));
+    let overrides: Vec<(Option<EventTime>, Event)> = overrides
+        .into_iter()
+        .map(|event| (recurrence_id(&event), event))
+        .collect();
+
+    let mut expanded = vec![];
+    for event in events {
+        if !is_recurring(&event) {
+            if event.overlaps(start, end) {
+                expanded.push(event);
+            }
+            continue;
+        }
+
+        let zone = Zone::of(&event);
+        let replaced: Vec<EventTime> = overrides
+            .iter()
+            .filter(|(_, o)| o.id == event.id && o.calendar_id == event.calendar_id)
+            .filter_map(|(time, _)| *time)
+            .collect();
+        match occurrences(&event, zone, start, end) {
+            Ok(times) => expanded.extend(
+                times
+                    .into_iter()
+                    .filter(|time| !replaced.iter().any(|r| zone.same_time(r, time)))
+                    .map(|time| occurrence(&event, time, zone))
+                    .filter(|occurrence| occurrence.overlaps(start, end)),
+            ),
+            Err(err) => {
+                tracing::warn!("Failed to expand {}: {}", event.summary, err);
+                if event.overlaps(start, end) {
+                    expanded.push(event);
+                }
+            }
+        }
+    }
+
+    for (time, mut event) in overrides {
+        if !event.overlaps(start, end) {
+            continue;
+        }
+        if let Some(time) = time {
+            event.extra.insert(
+                "recurringEventId".to_string(),
+                Value::String(event.id.clone()),
+            );
+            event.id = occurrence_id(&event.id, time);
+        }
+        expanded.push(event);
+    }
+    expanded
+}
+
+/// Whether `event` is a series, as opposed to a single event or an occurrence.
+pub fn is_recurring(event: &Event) -> bool {
+    event.recurrence.iter().any(|line| {
+        let name = line.split([':', ';']).next().unwrap_or_default();
+        name.eq_ignore_ascii_case("RRULE") || name.eq_ignore_ascii_case("RDATE")
+    })
+}
+
+/// The id of the occurrence of series `id` starting at `start`,
+/// in the form Google uses for its instances.
+pub fn occurrence_id(id: &str, start: EventTime) -> String {
+    match start {
+        EventTime::Date(date) => format!("{id}_{}", format_date(date)),
+        EventTime::DateTime(date_time) => format!("{id}_{}", format_utc(date_time)),
+    }
+}
+
+/// The start of the occurrence an override replaces, read from its `RECURRENCE-ID`.
+pub fn recurrence_id(event: &Event) -> Option<EventTime> {
+    let Some(Value::String(line)) = event.extra.get("RECURRENCE-ID") else {
+        return None;
+    };
+    let property = parse_line(line).ok()?;
+    times(&property, Zone::of(event)).ok()?.into_iter().next()
+}
+
+/// Whether the occurrence originally starting at `start` is the first one of `series`.
+pub fn starts_series(series: &Event, start: EventTime) -> bool {
+    Zone::of(series).same_time(&series.start, &start)
+}
+
+/// Leaves the occurrence originally starting at `start` out of `series`.
+pub fn exclude(series: &mut Event, start: EventTime) {
+    let zone = Zone::of(series);
+    series
+        .recurrence
+        .push(time_line("EXDATE", &start, zone.tz()));
+}
+
+/// `series` with the changes made to its occurrence originally starting at `start`
+/// applied to every occurrence. Times move by as much as the occurrence was moved.
+pub fn apply_changes(series: &Event, start: EventTime, changed: &Event) -> Event {
+    let mut updated = series.clone();
+    updated.summary = changed.summary.clone();
+    updated.description = changed.description.clone();
+    updated.location = changed.location.clone();
+    updated.start = shift(series.start, start, changed.start);
+    let length = changed.end.at_offset(UtcOffset::UTC) - changed.start.at_offset(UtcOffset::UTC);
+    updated.end = match updated.start {
+        EventTime::Date(date) => EventTime::Date(date + length),
+        EventTime::DateTime(date_time) => EventTime::DateTime(date_time + length),
+    };
+    updated
+}
+
+/// Splits `series` at its occurrence originally starting at `start`, into the series
+/// ending right before it and a new, unsaved series of the remaining occurrences.
+pub fn split(series: &Event, start: EventTime) -> Result<(Event, Event)> {
+    let zone = Zone::of(series);
+    let is_before =
+        |time: &EventTime| time.at_offset(UtcOffset::UTC) < start.at_offset(UtcOffset::UTC);
+    let until = match start {
+        EventTime::Date(date) => format_date(date.previous_day().unwrap_or(date)),
+        EventTime::DateTime(date_time) => format_utc(date_time - Duration::SECOND),
+    };
+
+    let mut ended = series.clone();
+    ended.recurrence.clear();
+    let mut following = series.clone();
+    following.recurrence.clear();
+    following.id.clear();
+    following
+        .extra
+        .retain(|key, _| !STORED_KEYS.contains(&key.as_str()));
+    let length = series.end.at_offset(UtcOffset::UTC) - series.start.at_offset(UtcOffset::UTC);
+    following.start = start;
+    following.end = match start {
+        EventTime::Date(date) => EventTime::Date(date + length),
+        EventTime::DateTime(date_time) => EventTime::DateTime(date_time + length),
+    };
+
+    for line in &series.recurrence {
+        let property = parse_line(line)?;
+        match property.name.as_str() {
+            "RRULE" => {
+                let ending = with_part(
+                    &with_part(&property.value, "COUNT", None),
+                    "UNTIL",
+                    Some(&until),
+                );
+                ended.recurrence.push(
+                    Property {
+                        value: ending,
+                        ..property.clone()
+                    }
+                    .to_line(),
+                );
+
+                let first = zone.local(series.start.at_offset(UtcOffset::UTC));
+                let rule = Rule::parse(&property.value, first, zone)?;
+                let remaining = rule.count.map(|count| {
+                    count
+                        .saturating_sub(count_before(&rule, series, start, zone))
+                        .max(1)
+                });
+                let continuing = match remaining {
+                    Some(count) => with_part(&property.value, "COUNT", Some(&count.to_string())),
+                    None => property.value.clone(),
+                };
+                following.recurrence.push(
+                    Property {
+                        value: continuing,
+                        ..property.clone()
+                    }
+                    .to_line(),
+                );
+            }
+            "RDATE" | "EXDATE" => {
+                let (mut earlier, mut later) = (vec![], vec![]);
+                for value in property.value.split(',') {
+                    let single = Property {
+                        value: value.to_string(),
+                        ..property.clone()
+                    };
+                    match times(&single, zone)?.first() {
+                        Some(time) if is_before(time) => earlier.push(value),
+                        _ => later.push(value),
+                    }
+                }
+                for (event, values) in [(&mut ended, earlier), (&mut following, later)] {
+                    if !values.is_empty() {
+                        event.recurrence.push(
+                            Property {
+                                value: values.join(","),
+                                ..property.clone()
+                            }
+                            .to_line(),
+                        );
+                    }
+                }
+            }
+            _ => {
+                ended.recurrence.push(line.clone());
+                following.recurrence.push(line.clone());
+            }
+        }
+    }
+    Ok((ended, following))
+}
+
+/// How many occurrences of `series` its first `RRULE` produces before the one
+/// originally starting at `start`, which those left of a `COUNT` are reduced by.
+pub fn occurrences_before(series: &Event, start: EventTime) -> Result<u32> {
+    let zone = Zone::of(series);
+    let first = zone.local(series.start.at_offset(UtcOffset::UTC));
+    let Some(line) = series.recurrence.iter().find(|line| {
+        line.get(..5)
+            .is_some_and(|name| name.eq_ignore_ascii_case("RRULE"))
+    }) else {
+        return Ok(0);
+    };
+    let rule = Rule::parse(&parse_line(line)?.value, first, zone)?;
+    Ok(count_before(&rule, series, start, zone))
+}
+
+/// Keys of `extra` that tie an event to the copy its provider stores,
+/// which a series split off from it doesn't inherit.
+const STORED_KEYS: &[&str] = &[
+    "etag",
+    "href",
+    "htmlLink",
+    "iCalUID",
+    "iCalUId",
+    "changeKey",
+    "webLink",
+    "type",
+    "recurringEventId",
+    "seriesMasterId",
+    "originalStartTime",
+    "originalStart",
+    "RECURRENCE-ID",
+    "SEQUENCE",
+    "CREATED",
+    "LAST-MODIFIED",
+];
+
+fn count_before(rule: &Rule, series: &Event, start: EventTime, zone: Zone) -> u32 {
+    let first = zone.local(series.start.at_offset(UtcOffset::UTC));
+    let end = zone.local(start.at_offset(UtcOffset::UTC));
+    // The start of the series is the first occurrence.
+    1 + rule.expand(first, end).len() as u32
+}
+
+/// `rule` with its `key` part set to `value`, or left out when there's no value.
+fn with_part(rule: &str, key: &str, value: Option<&str>) -> String {
+    let mut parts: Vec<String> = rule
+        .split(';')
+        .filter(|part| {
+            let name = part.split('=').next().unwrap_or_default();
+            !part.is_empty() && !name.eq_ignore_ascii_case(key)
+        })
+        .map(str::to_string)
+        .collect();
+    if let Some(value) = value {
+        parts.push(format!("{key}={value}"));
+    }
+    parts.join(";")
+}
+
+/// Moves `time` by as much as `from` was moved to become `to`. Switching between
+/// all-day and timed keeps the day and takes the time of day of `to`.
+fn shift(time: EventTime, from: EventTime, to: EventTime) -> EventTime {
+    let offset = match to {
+        EventTime::DateTime(date_time) => date_time.offset(),
+        EventTime::Date(_) => UtcOffset::UTC,
+    };
+    let days = to.date_at(offset) - from.date_at(offset);
+    match (time, to) {
+        (EventTime::DateTime(time), EventTime::DateTime(to)) => {
+            EventTime::DateTime(time + (to - from.at_offset(offset)))
+        }
+        (time, EventTime::Date(_)) => EventTime::Date(time.date_at(offset) + days),
+        (EventTime::Date(date), EventTime::DateTime(to)) => EventTime::DateTime(
+            PrimitiveDateTime::new(date + days, to.time()).assume_offset(offset),
+        ),
+    }
+}
+
+/// The starts of the occurrences of `event` that may overlap `[start, end)`, in order.
+fn occurrences(
+    event: &Event,
+    zone: Zone,
+    start: OffsetDateTime,
+    end: OffsetDateTime,
+) -> Result<Vec<EventTime>> {
+    let all_day = event.is_all_day();
+    let first = zone.local(event.start.at_offset(UtcOffset::UTC));
+    // Rules are evaluated in local time, so the end is widened to cover any offset.
+    let local_end = zone.local(end) + Duration::days(2);
+
+    // The first occurrence is always the start of the series, whether or not it
+    // matches the rules.
+    let mut included = vec![event.start];
+    let mut excluded = vec![];
+    for line in &event.recurrence {
+        let property = parse_line(line)?;
+        match property.name.as_str() {
+            "RRULE" => {
+                let rule = Rule::parse(&property.value, first, zone)?;
+                included.extend(
+                    rule.expand(first, local_end)
+                        .into_iter()
+                        .map(|local| zone.time(local, all_day)),
+                );
+            }
+            "RDATE" => included.extend(times(&property, zone)?),
+            "EXDATE" => excluded.extend(times(&property, zone)?),
+            _ => {}
+        }
+    }
+
+    let length = event.end.at_offset(UtcOffset::UTC) - event.start.at_offset(UtcOffset::UTC);
+    let from = start - length - Duration::DAY;
+    let mut times: Vec<EventTime> = included
+        .into_iter()
+        .map(|time| match time {
+            EventTime::DateTime(date_time) if all_day => {
+                EventTime::Date(zone.local(date_time).date())
+            }
+            time => time,
+        })
+        .filter(|time| !excluded.iter().any(|e| zone.same_time(e, time)))
+        .filter(|time| {
+            let time = time.at_offset(UtcOffset::UTC);
+            time >= from && time < end
+        })
+        .collect();
+    times.sort_by_key(|time| time.at_offset(UtcOffset::UTC));
+    times.dedup_by(|a, b| zone.same_time(a, b));
+    Ok(times)
+}
+
+/// The occurrence of `series` starting at `start`, lasting as long as the series does.
+fn occurrence(series: &Event, start: EventTime, zone: Zone) -> Event {
+    let length = series.end.at_offset(UtcOffset::UTC) - series.start.at_offset(UtcOffset::UTC);
+    let mut event = series.clone();
+    event.id = occurrence_id(&series.id, start);
+    event.start = start;
+    event.end = match start {
+        EventTime::Date(date) => EventTime::Date(date + length),
+        EventTime::DateTime(date_time) => EventTime::DateTime(date_time + length),
+    };
+    event.recurrence.clear();
+    event.extra.insert(
+        "recurringEventId".to_string(),
+        Value::String(series.id.clone()),
+    );
+    event.extra.insert(
+        "RECURRENCE-ID".to_string(),
+        Value::String(time_line("RECURRENCE-ID", &start, zone.tz())),
+    );
+    event
+}
+
+/// The values of an `RDATE`, `EXDATE` or `RECURRENCE-ID` property. Periods
+/// only count for their start, and local times without a known `TZID` are taken
+/// to be in the zone of the series.
+fn times(property: &Property, zone: Zone) -> Result<Vec<EventTime>> {
+    let zone = property.param("TZID").and_then(Zone::named).unwrap_or(zone);
+    let is_date = property
+        .param("VALUE")
+        .is_some_and(|v| v.eq_ignore_ascii_case("DATE"));
+
+    property
+        .value
+        .split(',')
+        .map(|value| {
+            let value = value.split('/').next().unwrap_or_default().trim();
+            let time = if is_date || value.len() == 8 {
+                Date::parse(value, format_description!("[year][month][day]"))
+                    .ok()
+                    .map(EventTime::Date)
+            } else if let Some(utc) = value.strip_suffix(['Z', 'z']) {
+                parse_local(utc).map(|local| EventTime::DateTime(local.assume_utc()))
+            } else {
+                parse_local(value).map(|local| EventTime::DateTime(zone.resolve(local)))
+            };
+            time.ok_or_else(|| Error::ICalendar(format!("invalid {} value {value}", property.name)))
+        })
+        .collect()
+}
+
+/// The zone a series is evaluated in.
+#[derive(Debug, Clone, Copy)]
+enum Zone {
+    Named(&'static Tz),
+    /// Series without a known IANA zone keep the offset of their first occurrence.
+    Fixed(UtcOffset),
+}
+
+impl Zone {
+    fn of(event: &Event) -> Self {
+        event_zone(event).map(Zone::Named).unwrap_or_else(|| {
+            Zone::Fixed(match event.start {
+                EventTime::DateTime(date_time) => date_time.offset(),
+                EventTime::Date(_) => UtcOffset::UTC,
+            })
+        })
+    }
+
+    fn named(name: &str) -> Option<Self> {
+        zone::named(name).map(Zone::Named)
+    }
+
+    fn tz(self) -> Option<&'static Tz> {
+        match self {
+            Zone::Named(tz) => Some(tz),
+            Zone::Fixed(_) => None,
+        }
+    }
+
+    /// The wall-clock time at `date_time` in this zone.
+    fn local(self, date_time: OffsetDateTime) -> PrimitiveDateTime {
+        let date_time = match self {
+            Zone::Named(tz) => date_time.to_timezone(tz),
+            Zone::Fixed(offset) => date_time.to_offset(offset),
+        };
+        PrimitiveDateTime::new(date_time.date(), date_time.time())
+    }
+
+    /// The instant a wall-clock time in this zone stands for, see [`zone::resolve`].
+    fn resolve(self, local: PrimitiveDateTime) -> OffsetDateTime {
+        match self {
+            Zone::Named(tz) => zone::resolve(local, tz),
+            Zone::Fixed(offset) => local.assume_offset(offset),
+        }
+    }
+
+    fn time(self, local: PrimitiveDateTime, all_day: bool) -> EventTime {
+        if all_day {
+            EventTime::Date(local.date())
+        } else {
+            EventTime::DateTime(self.resolve(local))
+        }
+    }
+
+    /// Whether two starts name the same occurrence. A date matches any time on that day,
+    /// since `EXDATE`s of all-day series are sometimes written as midnight.
+    fn same_time(self, a: &EventTime, b: &EventTime) -> bool {
+        match (a, b) {
+            (EventTime::Date(a), EventTime::Date(b)) => a == b,
+            (EventTime::DateTime(a), EventTime::DateTime(b)) => a == b,
+            (EventTime::Date(date), EventTime::DateTime(date_time))
+            | (EventTime::DateTime(date_time), EventTime::Date(date)) => {
+                self.local(*date_time).date() == *date
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// A parsed `RRULE`, with the parts left out filled in from the start of the series.
+#[derive(Debug, Clone)]
+struct Rule {
+    frequency: Frequency,
+    interval: u32,
+    count: Option<u32>,
+    /// The last local time an occurrence may start at.
+    until: Option<PrimitiveDateTime>,
+    by_month: Vec<u8>,
+    by_week_no: Vec<i8>,
+    by_year_day: Vec<i16>,
+    by_month_day: Vec<i8>,
+    /// Weekdays, along with which of them in the month or year when not zero.
+    by_day: Vec<(i8, Weekday)>,
+    by_hour: Vec<u8>,
+    by_minute: Vec<u8>,
+    by_second: Vec<u8>,
+    by_set_pos: Vec<i16>,
+    week_start: Weekday,
+}
+
+impl Rule {
+    fn parse(value: &str, first: PrimitiveDateTime, zone: Zone) -> Result<Self> {
+        let invalid = || Error::ICalendar(format!("invalid recurrence rule {value}"));
+        let mut frequency = None;
+        let mut rule = Rule {
+            frequency: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_month: vec![],
+            by_week_no: vec![],
+            by_year_day: vec![],
+            by_month_day: vec![],
+            by_day: vec![],
+            by_hour: vec![],
+            by_minute: vec![],
+            by_second: vec![],
+            by_set_pos: vec![],
+            week_start: Weekday::Monday,
+        };
+
+        for part in value.split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part.split_once('=').ok_or_else(invalid)?;
+            let value = value.to_uppercase();
+            match key.to_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        other => {
+                            return Err(Error::ICalendar(format!(
+                                "unsupported recurrence frequency {other}"
+                            )))
+                        }
+                    })
+                }
+                "INTERVAL" => {
+                    rule.interval = value
+                        .parse()
+                        .ok()
+                        .filter(|interval| *interval > 0)
+                        .ok_or_else(invalid)?
+                }
+                "COUNT" => rule.count = Some(value.parse().map_err(|_| invalid())?),
+                "UNTIL" => rule.until = Some(parse_until(&value, zone).ok_or_else(invalid)?),
+                "BYMONTH" => rule.by_month = parse_list(&value).ok_or_else(invalid)?,
+                "BYWEEKNO" => rule.by_week_no = parse_list(&value).ok_or_else(invalid)?,
+                "BYYEARDAY" => rule.by_year_day = parse_list(&value).ok_or_else(invalid)?,
+                "BYMONTHDAY" => rule.by_month_day = parse_list(&value).ok_or_else(invalid)?,
+                "BYHOUR" => rule.by_hour = parse_list(&value).ok_or_else(invalid)?,
+                "BYMINUTE" => rule.by_minute = parse_list(&value).ok_or_else(invalid)?,
+                "BYSECOND" => rule.by_second = parse_list(&value).ok_or_else(invalid)?,
+                "BYSETPOS" => rule.by_set_pos = parse_list(&value).ok_or_else(invalid)?,
+                "BYDAY" => {
+                    rule.by_day = value
+                        .split(',')
+                        .map(parse_day)
+                        .collect::<Option<_>>()
+                        .ok_or_else(invalid)?
+                }
+                "WKST" => rule.week_start = parse_weekday(&value).ok_or_else(invalid)?,
+                _ => {}
+            }
+        }
+        rule.frequency = frequency.ok_or_else(invalid)?;
+
+        // A rule that doesn't say which days it falls on repeats the day of the start.
+        if rule.by_week_no.is_empty()
+            && rule.by_year_day.is_empty()
+            && rule.by_month_day.is_empty()
+            && rule.by_day.is_empty()
+        {
+            match rule.frequency {
+                Frequency::Yearly => {
+                    if rule.by_month.is_empty() {
+                        rule.by_month = vec![first.month() as u8];
+                    }
+                    rule.by_month_day = vec![first.day() as i8];
+                }
+                Frequency::Monthly => rule.by_month_day = vec![first.day() as i8],
+                Frequency::Weekly => rule.by_day = vec![(0, first.weekday())],
+                Frequency::Daily => {}
+            }
+        }
+        if rule.by_hour.is_empty() {
+            rule.by_hour = vec![first.hour()];
+        }
+        if rule.by_minute.is_empty() {
+            rule.by_minute = vec![first.minute()];
+        }
+        if rule.by_second.is_empty() {
+            rule.by_second = vec![first.second()];
+        }
+        Ok(rule)
+    }
+
+    /// The local starts of the occurrences after `first` that begin before `end`,
+    /// in order.
+    fn expand(&self, first: PrimitiveDateTime, end: PrimitiveDateTime) -> Vec<PrimitiveDateTime> {
+        let mut occurrences = vec![];
+        // The start of the series counts towards COUNT as well.
+        let mut count = 1;
+        let mut period = self.period_start(first.date());
+        for _ in 0..MAX_PERIODS {
+            if period.midnight() >= end {
+                break;
+            }
+            for time in self.candidates(period) {
+                if time <= first {
+                    continue;
+                }
+                if time >= end
+                    || self.until.is_some_and(|until| time > until)
+                    || self.count.is_some_and(|limit| count >= limit)
+                {
+                    return occurrences;
+                }
+                occurrences.push(time);
+                count += 1;
+            }
+            let Some(next) = self.next_period(period) else {
+                break;
+            };
+            period = next;
+        }
+        occurrences
+    }
+
+    /// The first day of the period `date` falls in.
+    fn period_start(&self, date: Date) -> Date {
+        match self.frequency {
+            Frequency::Daily => date,
+            Frequency::Weekly => {
+                date - Duration::days(days_between(self.week_start, date.weekday()))
+            }
+            Frequency::Monthly => date.replace_day(1).unwrap_or(date),
+            Frequency::Yearly => Date::from_ordinal_date(date.year(), 1).unwrap_or(date),
+        }
+    }
+
+    fn next_period(&self, period: Date) -> Option<Date> {
+        let interval = self.interval as i64;
+        match self.frequency {
+            Frequency::Daily => period.checked_add(Duration::days(interval)),
+            Frequency::Weekly => period.checked_add(Duration::weeks(interval)),
+            Frequency::Monthly => {
+                let months = period.year() as i64 * 12 + period.month() as i64 - 1 + interval;
+                let month = Month::try_from((months.rem_euclid(12) + 1) as u8).ok()?;
+                Date::from_calendar_date(months.div_euclid(12) as i32, month, 1).ok()
+            }
+            Frequency::Yearly => {
+                Date::from_calendar_date(period.year() + self.interval as i32, Month::January, 1)
+                    .ok()
+            }
+        }
+    }
+
+    /// The times the rule produces within the period starting on `period`, in order.
+    fn candidates(&self, period: Date) -> Vec<PrimitiveDateTime> {
+        let days = match self.frequency {
+            Frequency::Daily => 1,
+            Frequency::Weekly => 7,
+            Frequency::Monthly => period.month().length(period.year()) as i64,
+            Frequency::Yearly => time::util::days_in_year(period.year()) as i64,
+        };
+
+        let mut times = vec![];
+        for date in (0..days).filter_map(|day| period.checked_add(Duration::days(day))) {
+            if !self.matches(date) {
+                continue;
+            }
+            for &hour in &self.by_hour {
+                for &minute in &self.by_minute {
+                    for &second in &self.by_second {
+                        if let Ok(time) = Time::from_hms(hour, minute, second) {
+                            times.push(PrimitiveDateTime::new(date, time));
+                        }
+                    }
+                }
+            }
+        }
+        times.sort();
+        times.dedup();
+        if self.by_set_pos.is_empty() {
+            return times;
+        }
+
+        let len = times.len() as i64;
+        let mut selected: Vec<PrimitiveDateTime> = self
+            .by_set_pos
+            .iter()
+            .filter_map(|&position| {
+                let index = match position {
+                    position if position > 0 => position as i64 - 1,
+                    position => len + position as i64,
+                };
+                (0..len).contains(&index).then(|| times[index as usize])
+            })
+            .collect();
+        selected.sort();
+        selected.dedup();
+        selected
+    }
+
+    /// Whether `date` passes every `BY*` part of the rule that limits or expands days.
+    fn matches(&self, date: Date) -> bool {
+        let year_length = time::util::days_in_year(date.year()) as i64;
+        let month_length = date.month().length(date.year()) as i64;
+        let (year_day, month_day) = (date.ordinal() as i64, date.day() as i64);
+
+        let in_list = |list: &[i64], position: i64, length: i64| {
+            list.is_empty()
+                || list
+                    .iter()
+                    .any(|&n| n == position || n == position - length - 1)
+        };
+
+        if !self.by_month.is_empty() && !self.by_month.contains(&(date.month() as u8)) {
+            return false;
+        }
+        if !self.by_week_no.is_empty() {
+            let Some((week, weeks)) = week_number(date, self.week_start) else {
+                return false;
+            };
+            let list: Vec<i64> = self.by_week_no.iter().map(|&n| n as i64).collect();
+            if !in_list(&list, week, weeks) {
+                return false;
+            }
+        }
+        let list: Vec<i64> = self.by_year_day.iter().map(|&n| n as i64).collect();
+        if !in_list(&list, year_day, year_length) {
+            return false;
+        }
+        let list: Vec<i64> = self.by_month_day.iter().map(|&n| n as i64).collect();
+        if !in_list(&list, month_day, month_length) {
+            return false;
+        }
+
+        // Ordinals count weekdays within the month for monthly rules and yearly ones
+        // limited to some months, and within the year for other yearly rules.
+        let (position, length) = match self.frequency {
+            Frequency::Monthly => (month_day, month_length),
+            Frequency::Yearly if !self.by_month.is_empty() => (month_day, month_length),
+            Frequency::Yearly => (year_day, year_length),
+            Frequency::Daily | Frequency::Weekly => (0, 0),
+        };
+        self.by_day.is_empty()
+            || self.by_day.iter().any(|&(n, weekday)| {
+                weekday == date.weekday()
+                    && match n as i64 {
+                        n if n == 0 || length == 0 => true,
+                        n if n > 0 => (position - 1) / 7 + 1 == n,
+                        n => (length - position) / 7 + 1 == -n,
+                    }
+            })
+    }
+}
+
+/// Parses a `BYDAY` entry such as `MO`, `2TU` or `-1FR`.
+fn parse_day(value: &str) -> Option<(i8, Weekday)> {
+    let (n, day) = value.trim().split_at(value.trim().len().checked_sub(2)?);
+    let n = match n {
+        "" | "+" => 0,
+        n => n.parse().ok().filter(|n| *n != 0)?,
+    };
+    Some((n, parse_weekday(day)?))
+}
+
+fn parse_list<T: std::str::FromStr>(value: &str) -> Option<Vec<T>> {
+    value.split(',').map(|n| n.trim().parse().ok()).collect()
+}
+
+/// Parses `UNTIL` into the last local time an occurrence may start at.
+fn parse_until(value: &str, zone: Zone) -> Option<PrimitiveDateTime> {
+    if value.len() == 8 {
+        let date = Date::parse(value, format_description!("[year][month][day]")).ok()?;
+        return Some(PrimitiveDateTime::new(date, Time::MAX));
+    }
+    match value.strip_suffix('Z') {
+        Some(utc) => Some(zone.local(parse_local(utc)?.assume_utc())),
+        None => parse_local(value),
+    }
+}
+
+/// How many days after `from` the next `to` is, between 0 and 6.
+fn days_between(from: Weekday, to: Weekday) -> i64 {
+    (to.number_days_from_monday() as i64 - from.number_days_from_monday() as i64).rem_euclid(7)
+}
+
+/// The week of its year `date` falls in, along with how many weeks that year has.
+/// Weeks start on `week_start`, and the first one is the first with at least four
+/// days in the year, so the first and last days of a year may belong to another.
+fn week_number(date: Date, week_start: Weekday) -> Option<(i64, i64)> {
+    let first_week = |year: i32| {
+        let january = Date::from_calendar_date(year, Month::January, 1).ok()?;
+        let offset = days_between(week_start, january.weekday());
+        Some(match offset {
+            0..=3 => january - Duration::days(offset),
+            _ => january + Duration::days(7 - offset),
+        })
+    };
+
+    let mut year = date.year();
+    if date < first_week(year)? {
+        year -= 1;
+    } else if date >= first_week(year + 1)? {
+        year += 1;
+    }
+    let start = first_week(year)?;
+    let weeks = (first_week(year + 1)? - start).whole_weeks();
+    Some(((date - start).whole_weeks() + 1, weeks))
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::{date, datetime, offset};
+
+    use super::*;
+
+    fn new_york() -> Zone {
+        Zone::named("America/New_York").unwrap()
+    }
+
+    /// The local starts `rule` gives after `first` and before `end`.
+    fn expand(
+        rule: &str,
+        first: PrimitiveDateTime,
+        end: PrimitiveDateTime,
+    ) -> Vec<PrimitiveDateTime> {
+        Rule::parse(rule, first, new_york())
+            .unwrap()
+            .expand(first, end)
+    }
+
+    #[test]
+    fn every_other_week_on_tuesday_and_thursday() {
+        let first = datetime!(1997-09-02 9:00);
+        assert_eq!(
+            expand(
+                "FREQ=WEEKLY;INTERVAL=2;COUNT=8;WKST=SU;BYDAY=TU,TH",
+                first,
+                datetime!(1998-01-01 0:00),
+            ),
+            [
+                datetime!(1997-09-04 9:00),
+                datetime!(1997-09-16 9:00),
+                datetime!(1997-09-18 9:00),
+                datetime!(1997-09-30 9:00),
+                datetime!(1997-10-02 9:00),
+                datetime!(1997-10-14 9:00),
+                datetime!(1997-10-16 9:00),
+            ]
+        );
+    }
+
+    #[test]
+    fn last_friday_of_the_month() {
+        let first = datetime!(1997-09-26 9:00);
+        assert_eq!(
+            expand("FREQ=MONTHLY;BYDAY=-1FR", first, datetime!(1998-03-01 0:00)),
+            [
+                datetime!(1997-10-31 9:00),
+                datetime!(1997-11-28 9:00),
+                datetime!(1997-12-26 9:00),
+                datetime!(1998-01-30 9:00),
+                datetime!(1998-02-27 9:00),
+            ]
+        );
+    }
+
+    #[test]
+    fn last_work_day_of_the_month() {
+        let first = datetime!(1997-09-29 9:00);
+        assert_eq!(
+            expand(
+                "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1",
+                first,
+                datetime!(1998-04-01 0:00),
+            ),
+            [
+                datetime!(1997-09-30 9:00),
+                datetime!(1997-10-31 9:00),
+                datetime!(1997-11-28 9:00),
+                datetime!(1997-12-31 9:00),
+                datetime!(1998-01-30 9:00),
+                datetime!(1998-02-27 9:00),
+                datetime!(1998-03-31 9:00),
+            ]
+        );
+    }
+
+    #[test]
+    fn count_includes_the_start() {
+        let first = datetime!(1997-09-02 9:00);
+        let starts = expand("FREQ=DAILY;COUNT=10", first, datetime!(1998-01-01 0:00));
+        assert_eq!(
+            starts,
+            (3..=11)
+                .map(|day| PrimitiveDateTime::new(
+                    Date::from_calendar_date(1997, Month::September, day).unwrap(),
+                    first.time()
+                ))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn until_is_inclusive_and_in_utc() {
+        let first = datetime!(1997-09-02 9:00);
+        let starts = expand(
+            "FREQ=DAILY;UNTIL=19971224T000000Z",
+            first,
+            datetime!(1998-01-01 0:00),
+        );
+        // 113 occurrences in all, counting the start.
+        assert_eq!(starts.len(), 112);
+        assert_eq!(starts.first(), Some(&datetime!(1997-09-03 9:00)));
+        assert_eq!(starts.last(), Some(&datetime!(1997-12-23 9:00)));
+    }
+
+    #[test]
+    fn week_start_changes_which_weeks_are_skipped() {
+        let first = datetime!(1997-08-05 9:00);
+        let end = datetime!(1998-01-01 0:00);
+        assert_eq!(
+            expand(
+                "FREQ=WEEKLY;INTERVAL=2;COUNT=4;BYDAY=TU,SU;WKST=MO",
+                first,
+                end
+            ),
+            [
+                datetime!(1997-08-10 9:00),
+                datetime!(1997-08-19 9:00),
+                datetime!(1997-08-24 9:00),
+            ]
+        );
+        assert_eq!(
+            expand(
+                "FREQ=WEEKLY;INTERVAL=2;COUNT=4;BYDAY=TU,SU;WKST=SU",
+                first,
+                end
+            ),
+            [
+                datetime!(1997-08-17 9:00),
+                datetime!(1997-08-19 9:00),
+                datetime!(1997-08-31 9:00),
+            ]
+        );
+    }
+
+    #[test]
+    fn daily_series_keeps_its_time_across_daylight_saving() {
+        let zone = new_york();
+        let first = datetime!(2026-03-06 9:00);
+        let starts: Vec<OffsetDateTime> = expand(
+            "FREQ=DAILY;UNTIL=20261103T000000Z",
+            first,
+            datetime!(2027-01-01 0:00),
+        )
+        .into_iter()
+        .map(|local| zone.resolve(local))
+        .collect();
+
+        assert!(starts
+            .iter()
+            .all(|start| zone.local(*start).time() == first.time()));
+        let at = |date: Date| {
+            starts
+                .iter()
+                .find(|start| zone.local(**start).date() == date)
+                .copied()
+        };
+        assert_eq!(
+            at(date!(2026 - 03 - 07)),
+            Some(datetime!(2026-03-07 9:00).assume_offset(offset!(-5)))
+        );
+        assert_eq!(
+            at(date!(2026 - 03 - 08)),
+            Some(datetime!(2026-03-08 9:00).assume_offset(offset!(-4)))
+        );
+        assert_eq!(
+            at(date!(2026 - 10 - 31)),
+            Some(datetime!(2026-10-31 9:00).assume_offset(offset!(-4)))
+        );
+        assert_eq!(
+            at(date!(2026 - 11 - 01)),
+            Some(datetime!(2026-11-01 9:00).assume_offset(offset!(-5)))
+        );
+        assert_eq!(
+            starts.last(),
+            Some(&datetime!(2026-11-02 9:00).assume_offset(offset!(-5)))
+        );
+    }
+}
@@ -0,0 +1,312 @@
+//! Resolution of local times qualified with a `TZID`, using the `VTIMEZONE`
+//! definitions shipped alongside the events, and generation of those definitions
+//! from the time zone database when writing.
+
+use std::collections::HashMap;
+
+use time::{
+    macros::format_description, Date, Duration, Month, OffsetDateTime, PrimitiveDateTime,
+    UtcOffset, Weekday,
+};
+use time_tz::{Offset, TimeZone, Tz};
+
+use super::Component;
+
+/// The time zones defined in one `VCALENDAR`, by `TZID`.
+#[derive(Debug, Default)]
+pub struct TimeZones {
+    zones: HashMap<String, Vec<Observance>>,
+}
+
+/// A `STANDARD` or `DAYLIGHT` block: from its onsets on, local time is `offset_to`.
+#[derive(Debug)]
+struct Observance {
+    start: PrimitiveDateTime,
+    offset_from: UtcOffset,
+    offset_to: UtcOffset,
+    rule: Option<YearlyRule>,
+    dates: Vec<PrimitiveDateTime>,
+}
+
+/// The subset of `RRULE` used by time zone transitions, e.g. `FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU`.
+#[derive(Debug)]
+struct YearlyRule {
+    month: Month,
+    /// The nth weekday of the month, counting from the end when negative.
+    weekday: Option<(i8, Weekday)>,
+    month_day: Option<i8>,
+    until: Option<PrimitiveDateTime>,
+}
+
+impl TimeZones {
+    pub fn from_calendar(calendar: &Component) -> Self {
+        let zones = calendar
+            .components("VTIMEZONE")
+            .filter_map(|vtimezone| {
+                let tzid = vtimezone.property("TZID")?.value.clone();
+                let observances = vtimezone
+                    .components
+                    .iter()
+                    .filter_map(Observance::from_component)
+                    .collect::<Vec<_>>();
+                (!observances.is_empty()).then_some((tzid, observances))
+            })
+            .collect();
+        Self { zones }
+    }
+
+    /// The UTC offset in effect at `local` in the zone `tzid`,
+    /// or `None` when the calendar doesn't define that zone.
+    pub fn offset_at(&self, tzid: &str, local: PrimitiveDateTime) -> Option<UtcOffset> {
+        let observances = self.zones.get(tzid)?;
+
+        let latest = observances
+            .iter()
+            .flat_map(|observance| {
+                observance
+                    .onsets_around(local.year())
+                    .into_iter()
+                    .filter(|onset| *onset <= local)
+                    .map(|onset| (onset, observance.offset_to))
+            })
+            .max_by_key(|(onset, _)| *onset);
+
+        match latest {
+            Some((_, offset)) => Some(offset),
+            // Before the first transition, the zone is in the offset it transitions from.
+            None => observances
+                .iter()
+                .min_by_key(|observance| observance.start)
+                .map(|observance| observance.offset_from),
+        }
+    }
+}
+
+impl Observance {
+    fn from_component(component: &Component) -> Option<Self> {
+        if component.name != "STANDARD" && component.name != "DAYLIGHT" {
+            return None;
+        }
+        Some(Observance {
+            start: parse_local(&component.property("DTSTART")?.value)?,
+            offset_from: parse_offset(&component.property("TZOFFSETFROM")?.value)?,
+            offset_to: parse_offset(&component.property("TZOFFSETTO")?.value)?,
+            rule: component
+                .property("RRULE")
+                .and_then(|rrule| YearlyRule::parse(&rrule.value)),
+            dates: component
+                .properties("RDATE")
+                .flat_map(|rdate| rdate.value.split(','))
+                .filter_map(parse_local)
+                .collect(),
+        })
+    }
+
+    /// The transitions of this observance in `year` and the year before,
+    /// which is enough to find the one in effect at any time of `year`.
+    fn onsets_around(&self, year: i32) -> Vec<PrimitiveDateTime> {
+        let mut onsets = vec![self.start];
+        onsets.extend(self.dates.iter().copied());
+        if let Some(rule) = &self.rule {
+            onsets.extend(
+                [year - 1, year]
+                    .into_iter()
+                    .filter_map(|year| rule.onset(year, self.start))
+                    .filter(|onset| *onset >= self.start)
+                    .filter(|onset| rule.until.is_none_or(|until| *onset <= until)),
+            );
+        }
+        onsets
+    }
+}
+
+impl YearlyRule {
+    fn parse(value: &str) -> Option<Self> {
+        let parts: HashMap<&str, &str> = value
+            .split(';')
+            .filter_map(|part| part.split_once('='))
+            .collect();
+        if parts.get("FREQ") != Some(&"YEARLY") {
+            return None;
+        }
+
+        let month = Month::try_from(parts.get("BYMONTH")?.parse::<u8>().ok()?).ok()?;
+        let weekday = match parts.get("BYDAY") {
+            Some(by_day) => {
+                let (n, day) = by_day.split_at(by_day.len().checked_sub(2)?);
+                let n = match n {
+                    "" | "+" => 1,
+                    n => n.trim_start_matches('+').parse().ok()?,
+                };
+                Some((n, parse_weekday(day)?))
+            }
+            None => None,
+        };
+
+        Some(YearlyRule {
+            month,
+            weekday,
+            month_day: parts.get("BYMONTHDAY").and_then(|day| day.parse().ok()),
+            until: parts
+                .get("UNTIL")
+                .and_then(|until| parse_local(until.trim_end_matches('Z'))),
+        })
+    }
+
+    /// When the transition happens in `year`, at the time of day of the first one.
+    fn onset(&self, year: i32, start: PrimitiveDateTime) -> Option<PrimitiveDateTime> {
+        let first = Date::from_calendar_date(year, self.month, 1).ok()?;
+        let last = last_day_of_month(first)?;
+
+        let date = match (self.weekday, self.month_day) {
+            (Some((n, weekday)), _) if n > 0 => {
+                let offset = (weekday.number_days_from_monday() as i64
+                    - first.weekday().number_days_from_monday() as i64)
+                    .rem_euclid(7);
+                first + Duration::days(offset) + Duration::weeks(n as i64 - 1)
+            }
+            (Some((n, weekday)), _) => {
+                let offset = (last.weekday().number_days_from_monday() as i64
+                    - weekday.number_days_from_monday() as i64)
+                    .rem_euclid(7);
+                last - Duration::days(offset) - Duration::weeks(-(n as i64) - 1)
+            }
+            (None, Some(day)) if day > 0 => first.replace_day(day as u8).ok()?,
+            (None, Some(day)) => last - Duration::days(-(day as i64) - 1),
+            (None, None) => first.replace_day(start.day()).ok()?,
+        };
+
+        (date.month() == self.month).then(|| PrimitiveDateTime::new(date, start.time()))
+    }
+}
+
+/// Writes a `VTIMEZONE` for `tz` with every transition between the start of
+/// `from_year` and the end of `to_year`, as one observance per transition.
+pub fn vtimezone(tz: &Tz, from_year: i32, to_year: i32) -> Vec<String> {
+    let Some((mut time, end)) = Date::from_calendar_date(from_year, Month::January, 1)
+        .ok()
+        .zip(Date::from_calendar_date(to_year + 1, Month::January, 1).ok())
+        .map(|(from, to)| (from.midnight().assume_utc(), to.midnight().assume_utc()))
+    else {
+        return vec![];
+    };
+
+    let mut lines = vec!["BEGIN:VTIMEZONE".to_string(), format!("TZID:{}", tz.name())];
+    let initial = tz.get_offset_utc(&time);
+    lines.extend(observance(
+        time,
+        initial.to_utc(),
+        initial.to_utc(),
+        initial.name(),
+        initial.is_dst(),
+    ));
+
+    // Zones change offset at most a few times a year and never twice in a day,
+    // so checking daily and narrowing down on changes finds every transition.
+    while time < end {
+        let next = time + Duration::DAY;
+        let (before, after) = (tz.get_offset_utc(&time), tz.get_offset_utc(&next));
+        if before.to_utc() != after.to_utc() {
+            let (mut low, mut high) = (time, next);
+            while high - low > Duration::SECOND {
+                let middle = low + (high - low) / 2;
+                if tz.get_offset_utc(&middle).to_utc() == before.to_utc() {
+                    low = middle;
+                } else {
+                    high = middle;
+                }
+            }
+            lines.extend(observance(
+                high,
+                before.to_utc(),
+                after.to_utc(),
+                after.name(),
+                after.is_dst(),
+            ));
+        }
+        time = next;
+    }
+
+    lines.push("END:VTIMEZONE".to_string());
+    lines
+}
+
+/// A `STANDARD` or `DAYLIGHT` block starting at `onset`, written in the local time
+/// before the transition as RFC 5545 requires.
+fn observance(
+    onset: OffsetDateTime,
+    offset_from: UtcOffset,
+    offset_to: UtcOffset,
+    name: &str,
+    is_dst: bool,
+) -> Vec<String> {
+    let kind = if is_dst { "DAYLIGHT" } else { "STANDARD" };
+    let local = onset
+        .to_offset(offset_from)
+        .format(format_description!(
+            "[year][month][day]T[hour][minute][second]"
+        ))
+        .unwrap_or_default();
+    vec![
+        format!("BEGIN:{kind}"),
+        format!("DTSTART:{local}"),
+        format!("TZOFFSETFROM:{}", format_offset(offset_from)),
+        format!("TZOFFSETTO:{}", format_offset(offset_to)),
+        format!("TZNAME:{name}"),
+        format!("END:{kind}"),
+    ]
+}
+
+fn format_offset(offset: UtcOffset) -> String {
+    let (hours, minutes, seconds) = offset.as_hms();
+    let sign = if offset.is_negative() { '-' } else { '+' };
+    let mut formatted = format!("{sign}{:02}{:02}", hours.abs(), minutes.abs());
+    if seconds != 0 {
+        formatted.push_str(&format!("{:02}", seconds.abs()));
+    }
+    formatted
+}
+
+fn last_day_of_month(first: Date) -> Option<Date> {
+    let next_month = match first.month() {
+        Month::December => Date::from_calendar_date(first.year() + 1, Month::January, 1),
+        month => Date::from_calendar_date(first.year(), month.next(), 1),
+    };
+    next_month.ok()?.previous_day()
+}
+
+pub(super) fn parse_local(value: &str) -> Option<PrimitiveDateTime> {
+    PrimitiveDateTime::parse(
+        value.trim(),
+        format_description!("[year][month][day]T[hour][minute][second]"),
+    )
+    .ok()
+}
+
+/// Parses a `TZOFFSETFROM`/`TZOFFSETTO` value such as `+0100` or `-053000`.
+fn parse_offset(value: &str) -> Option<UtcOffset> {
+    let value = value.trim();
+    let sign = match value.get(..1)? {
+        "+" => 1,
+        "-" => -1,
+        _ => return None,
+    };
+    let digits = value.get(1..)?;
+    let hours: i8 = digits.get(..2)?.parse().ok()?;
+    let minutes: i8 = digits.get(2..4)?.parse().ok()?;
+    let seconds: i8 = digits.get(4..6).map_or(Some(0), |s| s.parse().ok())?;
+    UtcOffset::from_hms(sign * hours, sign * minutes, sign * seconds).ok()
+}
+
+pub(super) fn parse_weekday(day: &str) -> Option<Weekday> {
+    Some(match day {
+        "MO" => Weekday::Monday,
+        "TU" => Weekday::Tuesday,
+        "WE" => Weekday::Wednesday,
+        "TH" => Weekday::Thursday,
+        "FR" => Weekday::Friday,
+        "SA" => Weekday::Saturday,
+        "SU" => Weekday::Sunday,
+        _ => return None,
+    })
+}
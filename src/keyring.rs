@@ -0,0 +1,44 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Secrets kept in the Secret Service, or the keyring portal inside a sandbox, rather
+//! than in the app config, such as the passwords of CalDAV servers.
+
+use std::collections::HashMap;
+
+use cosmic::Application;
+
+use crate::{app::AppModel, models::CalDavAccount, Error, Result};
+
+/// Identifies the password of `account` among the items of the keyring.
+fn caldav_attributes(account: &CalDavAccount) -> HashMap<&str, &str> {
+    HashMap::from([
+        ("application", AppModel::APP_ID),
+        ("type", "caldav"),
+        ("url", account.url.as_str()),
+        ("username", account.username.as_str()),
+    ])
+}
+
+/// The password stored for `account` when it was added.
+pub async fn caldav_password(account: &CalDavAccount) -> Result<String> {
+    let keyring = oo7::Keyring::new().await?;
+    let items = keyring.search_items(caldav_attributes(account)).await?;
+    let item = items
+        .first()
+        .ok_or_else(|| Error::MissingSecret(format!("{}@{}", account.username, account.url)))?;
+    Ok(String::from_utf8_lossy(&item.secret().await?).into_owned())
+}
+
+/// Stores the password of `account`, replacing the one it had.
+pub async fn set_caldav_password(account: &CalDavAccount, password: &str) -> Result<()> {
+    let keyring = oo7::Keyring::new().await?;
+    keyring
+        .create_item(
+            &format!("CalDAV password for {}@{}", account.username, account.url),
+            caldav_attributes(account),
+            password,
+            true,
+        )
+        .await?;
+    Ok(())
+}
@@ -1,12 +1,17 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 mod app;
+mod cache;
 mod components;
 mod config;
 mod error;
 mod i18n;
+mod ical;
+mod keyring;
 mod models;
+mod outbox;
 mod services;
+mod sync;
 
 pub use error::{Error, Result};
 
@@ -1,11 +1,11 @@
 use std::collections::HashMap;
 
-use accounts::models::Provider;
 use gcal_rs::CalendarListItem;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::services;
+use super::Provider;
+use crate::{services, Error, Result};
 
 /// A unified Calendar model that works across providers.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,7 +18,7 @@ pub struct Calendar {
     pub description: Option<String>,
     /// Timezone associated with the calendar.
     pub timezone: Option<String>,
-    /// Background color (Google: "backgroundColor", Microsoft: "color").
+    /// Background color as `#rrggbb` (Google: "backgroundColor", Microsoft: "hexColor").
     pub color: Option<String>,
     /// Access/role (Google: "accessRole", Microsoft: "canEdit"/"owner").
     pub access_role: String,
@@ -28,6 +28,25 @@ pub struct Calendar {
     pub extra: HashMap<String, Value>,
 }
 
+impl Calendar {
+    /// Whether events in this calendar can be created, changed or deleted.
+    pub fn is_editable(&self) -> bool {
+        ["owner", "writer"]
+            .iter()
+            .any(|role| self.access_role.eq_ignore_ascii_case(role))
+    }
+
+    /// Fails with [`Error::ReadOnlyCalendar`] unless the calendar is editable,
+    /// so services can refuse writes before reaching the provider.
+    pub fn check_editable(&self) -> Result<()> {
+        if self.is_editable() {
+            Ok(())
+        } else {
+            Err(Error::ReadOnlyCalendar(self.name.clone()))
+        }
+    }
+}
+
 impl From<services::microsoft::models::Calendar> for Calendar {
     fn from(mc: services::microsoft::models::Calendar) -> Self {
         let mut extra = HashMap::new();
@@ -44,6 +63,7 @@ impl From<services::microsoft::models::Calendar> for Calendar {
             Value::Bool(mc.is_tallying_responses),
         );
         extra.insert("isRemovable".to_string(), Value::Bool(mc.is_removable));
+        extra.insert("color".to_string(), Value::String(mc.color.clone()));
         extra.insert(
             "allowedOnlineMeetingProviders".to_string(),
             Value::Array(
@@ -63,10 +83,10 @@ impl From<services::microsoft::models::Calendar> for Calendar {
             name: mc.name,
             description: None,
             timezone: None,
-            color: if mc.color == "auto" {
+            color: if mc.hex_color.is_empty() {
                 None
             } else {
-                Some(mc.color)
+                Some(mc.hex_color)
             },
             access_role: if mc.can_edit {
                 "owner".to_string()
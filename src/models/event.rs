@@ -0,0 +1,997 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use time::{
+    format_description::well_known::Rfc3339, macros::format_description, Date, OffsetDateTime,
+    PrimitiveDateTime, UtcOffset, Weekday,
+};
+use time_tz::{OffsetDateTimeExt, Tz};
+
+use super::{zone, Provider};
+use crate::{
+    services::{google, microsoft},
+    Error, Result,
+};
+
+/// A unified Event model that works across providers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    /// Unique identifier of the event within its calendar (Google: "id", Microsoft: "id").
+    pub id: String,
+    /// Identifier of the calendar the event belongs to.
+    /// Providers don't echo it back on event items, so services fill it in after fetching.
+    pub calendar_id: String,
+    /// Title of the event (Google: "summary", Microsoft: "subject").
+    pub summary: String,
+    /// Optional notes (Google: "description", Microsoft: "body").
+    pub description: Option<String>,
+    /// Optional location (Google: "location", Microsoft: "location.displayName").
+    pub location: Option<String>,
+    /// When the event starts.
+    pub start: EventTime,
+    /// When the event ends (exclusive, for all-day events this is the day after the last one).
+    pub end: EventTime,
+    /// IANA time zone the event was scheduled in, if the provider reports one.
+    pub time_zone: Option<String>,
+    /// People invited to the event.
+    pub attendees: Vec<Attendee>,
+    /// Who owns the event.
+    pub organizer: Option<Organizer>,
+    /// Whether the event is confirmed, tentative or cancelled.
+    pub status: EventStatus,
+    /// Recurrence lines in RFC 5545 form, e.g. `RRULE:FREQ=WEEKLY;BYDAY=MO`.
+    pub recurrence: Vec<String>,
+    /// Reminders set on the event.
+    pub reminders: Vec<Reminder>,
+    /// The source provider (Google, Microsoft, etc.)
+    pub provider: Provider,
+    /// A bag for provider-specific raw fields, mirroring `Calendar::extra`.
+    pub extra: HashMap<String, Value>,
+}
+
+/// The start or end of an event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EventTime {
+    /// A specific point in time.
+    DateTime(OffsetDateTime),
+    /// A whole day, with no time component.
+    Date(Date),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attendee {
+    pub email: String,
+    pub name: Option<String>,
+    pub response: AttendeeResponse,
+    pub optional: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Organizer {
+    pub email: Option<String>,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AttendeeResponse {
+    #[default]
+    NeedsAction,
+    Accepted,
+    Tentative,
+    Declined,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EventStatus {
+    #[default]
+    Confirmed,
+    Tentative,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reminder {
+    /// How long before the start of the event the reminder fires.
+    pub minutes: i64,
+    pub method: ReminderMethod,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReminderMethod {
+    #[default]
+    Notification,
+    Email,
+}
+
+impl Event {
+    pub fn is_all_day(&self) -> bool {
+        matches!(self.start, EventTime::Date(_))
+    }
+
+    /// The IANA zone the event was scheduled in, if the time zone database knows it.
+    pub fn zone(&self) -> Option<&'static Tz> {
+        self.time_zone
+            .as_deref()
+            .filter(|name| *name != "UTC")
+            .and_then(zone::named)
+    }
+
+    /// Whether any part of the event falls within `[start, end)`.
+    pub fn overlaps(&self, start: OffsetDateTime, end: OffsetDateTime) -> bool {
+        let offset = start.offset();
+        self.start.at_offset(offset) < end && self.end.at_offset(offset) > start
+    }
+
+    /// The recurring series this event is an occurrence of, if it's one.
+    pub fn series_id(&self) -> Option<&str> {
+        ["recurringEventId", "seriesMasterId"]
+            .iter()
+            .find_map(|key| self.extra.get(*key)?.as_str())
+    }
+
+    /// When this occurrence was scheduled in its series before any change moved it,
+    /// which is what identifies it within the series.
+    pub fn original_start(&self) -> EventTime {
+        let google = self
+            .extra
+            .get("originalStartTime")
+            .and_then(|time| {
+                serde_json::from_value::<google::models::EventDateTime>(time.clone()).ok()
+            })
+            .and_then(|time| google_event_time(&time).ok());
+        let microsoft = self
+            .extra
+            .get("originalStart")
+            .and_then(Value::as_str)
+            .and_then(|time| OffsetDateTime::parse(time, &Rfc3339).ok())
+            .map(|time| match self.start {
+                EventTime::Date(_) => EventTime::Date(time.date()),
+                EventTime::DateTime(_) => EventTime::DateTime(time),
+            });
+        google
+            .or(microsoft)
+            .or_else(|| crate::ical::recurrence::recurrence_id(self))
+            .unwrap_or(self.start)
+    }
+
+    /// Whether Graph can store the recurrence of the event, if it has any.
+    pub fn fits_graph(&self) -> bool {
+        self.recurrence.is_empty() || graph_recurrence(self).is_some()
+    }
+}
+
+impl EventTime {
+    /// The calendar date this time falls on, seen from `offset`.
+    pub fn date_at(&self, offset: UtcOffset) -> Date {
+        match self {
+            EventTime::DateTime(date_time) => date_time.to_offset(offset).date(),
+            EventTime::Date(date) => *date,
+        }
+    }
+
+    /// The instant this time represents, seen from `offset`.
+    /// All-day dates are taken to start at midnight in that offset.
+    pub fn at_offset(&self, offset: UtcOffset) -> OffsetDateTime {
+        match self {
+            EventTime::DateTime(date_time) => date_time.to_offset(offset),
+            EventTime::Date(date) => date.midnight().assume_offset(offset),
+        }
+    }
+
+    /// The calendar date this time falls on, seen from `zone`.
+    pub fn date_in(&self, zone: &Tz) -> Date {
+        match self {
+            EventTime::DateTime(date_time) => date_time.to_timezone(zone).date(),
+            EventTime::Date(date) => *date,
+        }
+    }
+
+    /// The instant this time represents, seen from `zone`.
+    /// All-day dates are taken to start at midnight in that zone.
+    pub fn in_zone(&self, zone: &Tz) -> OffsetDateTime {
+        match self {
+            EventTime::DateTime(date_time) => date_time.to_timezone(zone),
+            EventTime::Date(date) => zone::midnight(*date, zone),
+        }
+    }
+}
+
+impl TryFrom<google::models::Event> for Event {
+    type Error = Error;
+
+    fn try_from(ge: google::models::Event) -> Result<Self> {
+        let mut extra = HashMap::new();
+
+        if let Some(etag) = &ge.etag {
+            extra.insert("etag".to_string(), Value::String(etag.clone()));
+        }
+        if let Some(html_link) = &ge.html_link {
+            extra.insert("htmlLink".to_string(), Value::String(html_link.clone()));
+        }
+        if let Some(ical_uid) = &ge.ical_uid {
+            extra.insert("iCalUID".to_string(), Value::String(ical_uid.clone()));
+        }
+        if let Some(color_id) = &ge.color_id {
+            extra.insert("colorId".to_string(), Value::String(color_id.clone()));
+        }
+        if let Some(recurring_event_id) = &ge.recurring_event_id {
+            extra.insert(
+                "recurringEventId".to_string(),
+                Value::String(recurring_event_id.clone()),
+            );
+        }
+        if let Some(original_start_time) = &ge.original_start_time {
+            extra.insert(
+                "originalStartTime".to_string(),
+                serde_json::to_value(original_start_time).unwrap_or(Value::Null),
+            );
+        }
+        if let Some(transparency) = &ge.transparency {
+            extra.insert(
+                "transparency".to_string(),
+                Value::String(transparency.clone()),
+            );
+        }
+        if let Some(visibility) = &ge.visibility {
+            extra.insert("visibility".to_string(), Value::String(visibility.clone()));
+        }
+        if let Some(hangout_link) = &ge.hangout_link {
+            extra.insert(
+                "hangoutLink".to_string(),
+                Value::String(hangout_link.clone()),
+            );
+        }
+        if let Some(sequence) = ge.sequence {
+            extra.insert("sequence".to_string(), Value::from(sequence));
+        }
+        if let Some(created) = &ge.created {
+            extra.insert("created".to_string(), Value::String(created.clone()));
+        }
+        if let Some(updated) = &ge.updated {
+            extra.insert("updated".to_string(), Value::String(updated.clone()));
+        }
+
+        let reminders = match &ge.reminders {
+            Some(reminders) => {
+                extra.insert(
+                    "useDefaultReminders".to_string(),
+                    Value::Bool(reminders.use_default),
+                );
+                reminders
+                    .overrides
+                    .iter()
+                    .flatten()
+                    .map(|r| Reminder {
+                        minutes: r.minutes,
+                        method: match r.method.as_str() {
+                            "email" => ReminderMethod::Email,
+                            _ => ReminderMethod::Notification,
+                        },
+                    })
+                    .collect()
+            }
+            None => vec![],
+        };
+
+        Ok(Event {
+            id: ge.id.unwrap_or_default(),
+            calendar_id: String::new(),
+            summary: ge.summary.unwrap_or_default(),
+            description: ge.description,
+            location: ge.location,
+            time_zone: ge.start.time_zone.clone(),
+            start: google_event_time(&ge.start)?,
+            end: google_event_time(&ge.end)?,
+            attendees: ge
+                .attendees
+                .into_iter()
+                .flatten()
+                .map(|a| Attendee {
+                    email: a.email,
+                    name: a.display_name,
+                    response: match a.response_status.as_deref() {
+                        Some("accepted") => AttendeeResponse::Accepted,
+                        Some("tentative") => AttendeeResponse::Tentative,
+                        Some("declined") => AttendeeResponse::Declined,
+                        _ => AttendeeResponse::NeedsAction,
+                    },
+                    optional: a.optional.unwrap_or(false),
+                })
+                .collect(),
+            organizer: ge.organizer.map(|o| Organizer {
+                email: o.email,
+                name: o.display_name,
+            }),
+            status: match ge.status.as_deref() {
+                Some("tentative") => EventStatus::Tentative,
+                Some("cancelled") => EventStatus::Cancelled,
+                _ => EventStatus::Confirmed,
+            },
+            recurrence: ge.recurrence.unwrap_or_default(),
+            reminders,
+            provider: Provider::Google,
+            extra,
+        })
+    }
+}
+
+impl TryFrom<microsoft::models::Event> for Event {
+    type Error = Error;
+
+    fn try_from(me: microsoft::models::Event) -> Result<Self> {
+        let mut extra = HashMap::new();
+
+        if let Some(etag) = &me.odata_etag {
+            extra.insert("etag".to_string(), Value::String(etag.clone()));
+        }
+        if let Some(change_key) = &me.change_key {
+            extra.insert("changeKey".to_string(), Value::String(change_key.clone()));
+        }
+        if let Some(ical_uid) = &me.ical_uid {
+            extra.insert("iCalUId".to_string(), Value::String(ical_uid.clone()));
+        }
+        if let Some(series_master_id) = &me.series_master_id {
+            extra.insert(
+                "seriesMasterId".to_string(),
+                Value::String(series_master_id.clone()),
+            );
+        }
+        if let Some(original_start) = &me.original_start {
+            extra.insert(
+                "originalStart".to_string(),
+                Value::String(original_start.clone()),
+            );
+        }
+        if let Some(event_type) = &me.event_type {
+            extra.insert("type".to_string(), Value::String(event_type.clone()));
+        }
+        if let Some(show_as) = &me.show_as {
+            extra.insert("showAs".to_string(), Value::String(show_as.clone()));
+        }
+        if let Some(web_link) = &me.web_link {
+            extra.insert("webLink".to_string(), Value::String(web_link.clone()));
+        }
+        if let Some(online_meeting_url) = &me.online_meeting_url {
+            extra.insert(
+                "onlineMeetingUrl".to_string(),
+                Value::String(online_meeting_url.clone()),
+            );
+        }
+
+        let description = match me.body {
+            Some(body) if body.content_type.eq_ignore_ascii_case("text") => Some(body.content),
+            _ => me.body_preview,
+        }
+        .filter(|description| !description.is_empty());
+
+        Ok(Event {
+            id: me.id.unwrap_or_default(),
+            calendar_id: String::new(),
+            summary: me.subject.unwrap_or_default(),
+            description,
+            location: me
+                .location
+                .map(|l| l.display_name)
+                .filter(|l| !l.is_empty()),
+            start: microsoft_event_time(&me.start, me.is_all_day)?,
+            end: microsoft_event_time(&me.end, me.is_all_day)?,
+            // Windows names like "Pacific Standard Time" are left out, as for iCalendar.
+            time_zone: me
+                .original_start_time_zone
+                .filter(|time_zone| time_zone.contains('/')),
+            attendees: me
+                .attendees
+                .into_iter()
+                .flatten()
+                .map(|a| Attendee {
+                    email: a.email_address.address.unwrap_or_default(),
+                    name: a.email_address.name,
+                    response: match a.status.as_ref().map(|s| s.response.as_str()) {
+                        Some("accepted" | "organizer") => AttendeeResponse::Accepted,
+                        Some("tentativelyAccepted") => AttendeeResponse::Tentative,
+                        Some("declined") => AttendeeResponse::Declined,
+                        _ => AttendeeResponse::NeedsAction,
+                    },
+                    optional: a.attendee_type.as_deref() == Some("optional"),
+                })
+                .collect(),
+            organizer: me.organizer.map(|o| Organizer {
+                email: o.email_address.address,
+                name: o.email_address.name,
+            }),
+            status: if me.is_cancelled {
+                EventStatus::Cancelled
+            } else if me.show_as.as_deref() == Some("tentative") {
+                EventStatus::Tentative
+            } else {
+                EventStatus::Confirmed
+            },
+            recurrence: me
+                .recurrence
+                .as_ref()
+                .map(graph_recurrence_to_rrule)
+                .into_iter()
+                .collect(),
+            reminders: match (me.is_reminder_on, me.reminder_minutes_before_start) {
+                (Some(true), Some(minutes)) => vec![Reminder {
+                    minutes,
+                    method: ReminderMethod::Notification,
+                }],
+                _ => vec![],
+            },
+            provider: Provider::Microsoft,
+            extra,
+        })
+    }
+}
+
+impl TryFrom<&Event> for google::models::Event {
+    type Error = Error;
+
+    fn try_from(event: &Event) -> Result<Self> {
+        let use_default_reminders = event.reminders.is_empty()
+            && event
+                .extra
+                .get("useDefaultReminders")
+                .and_then(Value::as_bool)
+                .unwrap_or(true);
+
+        // Events fetched from Google keep the UID they were given; imported ones carry
+        // the UID from their file as their id until Google assigns its own.
+        let ical_uid = match event.extra.get("iCalUID").and_then(Value::as_str) {
+            Some(uid) => Some(uid.to_string()),
+            None => (!event.id.is_empty() && !crate::outbox::is_pending(event))
+                .then(|| event.id.clone()),
+        };
+
+        Ok(google::models::Event {
+            ical_uid,
+            summary: Some(event.summary.clone()),
+            // Empty strings rather than `None`, so clearing a field also clears it on a patch.
+            description: Some(event.description.clone().unwrap_or_default()),
+            location: Some(event.location.clone().unwrap_or_default()),
+            start: google_date_time(&event.start, event.time_zone.as_ref())?,
+            end: google_date_time(&event.end, event.time_zone.as_ref())?,
+            status: Some(
+                match event.status {
+                    EventStatus::Confirmed => "confirmed",
+                    EventStatus::Tentative => "tentative",
+                    EventStatus::Cancelled => "cancelled",
+                }
+                .to_string(),
+            ),
+            recurrence: (!event.recurrence.is_empty()).then(|| event.recurrence.clone()),
+            attendees: Some(
+                event
+                    .attendees
+                    .iter()
+                    .map(|a| google::models::Attendee {
+                        email: a.email.clone(),
+                        display_name: a.name.clone(),
+                        response_status: Some(
+                            match a.response {
+                                AttendeeResponse::NeedsAction => "needsAction",
+                                AttendeeResponse::Accepted => "accepted",
+                                AttendeeResponse::Tentative => "tentative",
+                                AttendeeResponse::Declined => "declined",
+                            }
+                            .to_string(),
+                        ),
+                        optional: Some(a.optional),
+                        organizer: None,
+                        is_self: None,
+                    })
+                    .collect(),
+            ),
+            reminders: Some(google::models::Reminders {
+                use_default: use_default_reminders,
+                overrides: (!use_default_reminders).then(|| {
+                    event
+                        .reminders
+                        .iter()
+                        .map(|r| google::models::ReminderOverride {
+                            method: match r.method {
+                                ReminderMethod::Notification => "popup",
+                                ReminderMethod::Email => "email",
+                            }
+                            .to_string(),
+                            minutes: r.minutes,
+                        })
+                        .collect()
+                }),
+            }),
+            ..Default::default()
+        })
+    }
+}
+
+impl TryFrom<&Event> for microsoft::models::Event {
+    type Error = Error;
+
+    /// Recurrence Graph's structured patterns can't express isn't written, so series
+    /// keep the pattern they already have on the server; see [`Event::fits_graph`].
+    fn try_from(event: &Event) -> Result<Self> {
+        Ok(microsoft::models::Event {
+            subject: Some(event.summary.clone()),
+            body: Some(microsoft::models::ItemBody {
+                content_type: "text".to_string(),
+                content: event.description.clone().unwrap_or_default(),
+            }),
+            location: Some(microsoft::models::Location {
+                display_name: event.location.clone().unwrap_or_default(),
+            }),
+            start: microsoft_date_time(&event.start)?,
+            end: microsoft_date_time(&event.end)?,
+            is_all_day: event.is_all_day(),
+            show_as: (event.status == EventStatus::Tentative).then(|| "tentative".to_string()),
+            attendees: Some(
+                event
+                    .attendees
+                    .iter()
+                    .map(|a| microsoft::models::Attendee {
+                        email_address: microsoft::models::EmailAddress {
+                            name: a.name.clone(),
+                            address: Some(a.email.clone()),
+                        },
+                        attendee_type: Some(
+                            if a.optional { "optional" } else { "required" }.to_string(),
+                        ),
+                        status: None,
+                    })
+                    .collect(),
+            ),
+            recurrence: graph_recurrence(event),
+            is_reminder_on: Some(!event.reminders.is_empty()),
+            reminder_minutes_before_start: event.reminders.first().map(|r| r.minutes),
+            ..Default::default()
+        })
+    }
+}
+
+/// Google sends either `date` for all-day events or an RFC 3339 `dateTime`.
+fn google_event_time(time: &google::models::EventDateTime) -> Result<EventTime> {
+    if let Some(date) = time.date.as_deref().and_then(parse_date) {
+        return Ok(EventTime::Date(date));
+    }
+    time.date_time
+        .as_deref()
+        .and_then(|date_time| OffsetDateTime::parse(date_time, &Rfc3339).ok())
+        .map(EventTime::DateTime)
+        .ok_or_else(|| Error::InvalidEventTime(format!("{time:?}")))
+}
+
+/// Graph sends a local `dateTime` without an offset, together with a `timeZone`.
+/// Events are requested in UTC, so any other zone is treated as UTC as well.
+fn microsoft_event_time(
+    time: &microsoft::models::DateTimeTimeZone,
+    all_day: bool,
+) -> Result<EventTime> {
+    let format = format_description!(
+        "[year]-[month]-[day]T[hour]:[minute]:[second][optional [.[subsecond]]]"
+    );
+    let date_time = PrimitiveDateTime::parse(&time.date_time, &format)
+        .map_err(|_| Error::InvalidEventTime(format!("{time:?}")))?
+        .assume_utc();
+    Ok(if all_day {
+        EventTime::Date(date_time.date())
+    } else {
+        EventTime::DateTime(date_time)
+    })
+}
+
+/// The Graph pattern of a series, or `None` when the event isn't recurring or Graph
+/// can't express its recurrence: RDATE and EXDATE lines, several rules, or rule parts
+/// like BYHOUR, or weekdays that don't all share one index within the month.
+fn graph_recurrence(event: &Event) -> Option<microsoft::models::PatternedRecurrence> {
+    let [line] = event.recurrence.as_slice() else {
+        return None;
+    };
+    let rule = line.strip_prefix("RRULE:")?;
+    let zone = event.zone();
+    let first = match zone {
+        Some(zone) => event.start.date_in(zone),
+        None => event.start.date_at(UtcOffset::UTC),
+    };
+
+    let mut parts = HashMap::new();
+    for part in rule.split(';').filter(|part| !part.is_empty()) {
+        let (key, value) = part.split_once('=')?;
+        parts.insert(key.to_uppercase(), value.to_uppercase());
+    }
+    const PARTS: [&str; 9] = [
+        "FREQ",
+        "INTERVAL",
+        "COUNT",
+        "UNTIL",
+        "BYDAY",
+        "BYMONTHDAY",
+        "BYMONTH",
+        "BYSETPOS",
+        "WKST",
+    ];
+    if parts.keys().any(|key| !PARTS.contains(&key.as_str())) {
+        return None;
+    }
+
+    let interval = match parts.get("INTERVAL") {
+        Some(value) => value.parse::<u32>().ok().filter(|interval| *interval > 0)?,
+        None => 1,
+    };
+    let days = match parts.get("BYDAY") {
+        Some(value) => value
+            .split(',')
+            .map(graph_day)
+            .collect::<Option<Vec<_>>>()?,
+        None => vec![],
+    };
+    let month_day = match parts.get("BYMONTHDAY") {
+        Some(value) => Some(
+            value
+                .parse::<u8>()
+                .ok()
+                .filter(|day| (1..=31).contains(day))?,
+        ),
+        None => None,
+    };
+    let month = match parts.get("BYMONTH") {
+        Some(value) => Some(
+            value
+                .parse::<u8>()
+                .ok()
+                .filter(|month| (1..=12).contains(month))?,
+        ),
+        None => None,
+    };
+    let set_pos = match parts.get("BYSETPOS") {
+        Some(value) => Some(value.parse::<i8>().ok()?),
+        None => None,
+    };
+    let week_start = match parts.get("WKST") {
+        Some(value) => Some(graph_day(value).filter(|(n, _)| *n == 0)?.1),
+        None => None,
+    };
+    // Graph gives all the weekdays of a relative pattern a single index.
+    let index = match (days.iter().find(|(n, _)| *n != 0), set_pos) {
+        (None, set_pos) => set_pos,
+        (Some((n, _)), None) if days.iter().all(|(other, _)| other == n) => Some(*n),
+        _ => return None,
+    };
+    let day_names: Vec<String> = days.iter().map(|(_, day)| day.to_string()).collect();
+
+    let relative = index.is_some() && !days.is_empty() && month_day.is_none();
+    let (pattern_type, days_of_week, day_of_month, month) =
+        match (parts.get("FREQ")?.as_str(), index) {
+            ("DAILY", None) if days.is_empty() && month_day.is_none() && month.is_none() => {
+                ("daily", vec![], 0, 0)
+            }
+            ("WEEKLY", None) if month_day.is_none() && month.is_none() => {
+                let days = if days.is_empty() {
+                    vec![graph_weekday(first.weekday()).to_string()]
+                } else {
+                    day_names
+                };
+                ("weekly", days, 0, 0)
+            }
+            ("MONTHLY", None) if days.is_empty() && month.is_none() => (
+                "absoluteMonthly",
+                vec![],
+                month_day.unwrap_or(first.day()),
+                0,
+            ),
+            ("MONTHLY", Some(_)) if relative && month.is_none() => {
+                ("relativeMonthly", day_names, 0, 0)
+            }
+            // A day of the month without a month would repeat every month.
+            ("YEARLY", None) if days.is_empty() && (month.is_some() || month_day.is_none()) => (
+                "absoluteYearly",
+                vec![],
+                month_day.unwrap_or(first.day()),
+                month.unwrap_or(first.month() as u8),
+            ),
+            ("YEARLY", Some(_)) if relative => ("relativeYearly", day_names, 0, month?),
+            _ => return None,
+        };
+    let index = match index {
+        Some(1) => Some("first"),
+        Some(2) => Some("second"),
+        Some(3) => Some("third"),
+        Some(4) => Some("fourth"),
+        Some(-1) => Some("last"),
+        None => None,
+        Some(_) => return None,
+    };
+
+    let count = match parts.get("COUNT") {
+        Some(value) => Some(value.parse::<u32>().ok().filter(|count| *count > 0)?),
+        None => None,
+    };
+    let until = match parts.get("UNTIL") {
+        Some(value) => Some(graph_until(value, zone)?),
+        None => None,
+    };
+    let range_type = match (count, until) {
+        (Some(_), Some(_)) => return None,
+        (Some(_), None) => "numbered",
+        (None, Some(_)) => "endDate",
+        (None, None) => "noEnd",
+    };
+
+    Some(microsoft::models::PatternedRecurrence {
+        pattern: microsoft::models::RecurrencePattern {
+            pattern_type: pattern_type.to_string(),
+            interval,
+            month,
+            day_of_month,
+            days_of_week,
+            first_day_of_week: week_start
+                .or((pattern_type == "weekly").then_some("monday"))
+                .map(str::to_string),
+            index: index.map(str::to_string),
+        },
+        range: microsoft::models::RecurrenceRange {
+            range_type: range_type.to_string(),
+            start_date: format_date(first).ok()?,
+            end_date: until.map(format_date).transpose().ok()?,
+            number_of_occurrences: count,
+            recurrence_time_zone: event.time_zone.clone().filter(|_| zone.is_some()),
+        },
+    })
+}
+
+/// A `BYDAY` entry as the index within the month and the Graph name of the weekday.
+fn graph_day(value: &str) -> Option<(i8, &'static str)> {
+    let (n, day) = value.split_at(value.len().checked_sub(2)?);
+    let n = match n {
+        "" | "+" => 0,
+        n => n.parse().ok().filter(|n| *n != 0)?,
+    };
+    let day = match day {
+        "MO" => Weekday::Monday,
+        "TU" => Weekday::Tuesday,
+        "WE" => Weekday::Wednesday,
+        "TH" => Weekday::Thursday,
+        "FR" => Weekday::Friday,
+        "SA" => Weekday::Saturday,
+        "SU" => Weekday::Sunday,
+        _ => return None,
+    };
+    Some((n, graph_weekday(day)))
+}
+
+fn graph_weekday(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Monday => "monday",
+        Weekday::Tuesday => "tuesday",
+        Weekday::Wednesday => "wednesday",
+        Weekday::Thursday => "thursday",
+        Weekday::Friday => "friday",
+        Weekday::Saturday => "saturday",
+        Weekday::Sunday => "sunday",
+    }
+}
+
+/// The last day of a series ending at `UNTIL`, which Graph takes as a date in the
+/// zone of the series. UTC times are moved into that zone first.
+fn graph_until(value: &str, zone: Option<&Tz>) -> Option<Date> {
+    let utc = format_description!("[year][month][day]T[hour][minute][second]Z");
+    if let Ok(until) = PrimitiveDateTime::parse(value, &utc) {
+        let until = until.assume_utc();
+        return Some(match zone {
+            Some(zone) => until.to_timezone(zone).date(),
+            None => until.date(),
+        });
+    }
+    Date::parse(value.get(..8)?, &format_description!("[year][month][day]")).ok()
+}
+
+fn google_date_time(
+    time: &EventTime,
+    time_zone: Option<&String>,
+) -> Result<google::models::EventDateTime> {
+    Ok(match time {
+        EventTime::Date(date) => google::models::EventDateTime {
+            date: Some(format_date(*date)?),
+            ..Default::default()
+        },
+        EventTime::DateTime(date_time) => google::models::EventDateTime {
+            date: None,
+            date_time: Some(date_time.format(&Rfc3339)?),
+            time_zone: time_zone.cloned(),
+        },
+    })
+}
+
+fn microsoft_date_time(time: &EventTime) -> Result<microsoft::models::DateTimeTimeZone> {
+    let date_time = time.at_offset(UtcOffset::UTC);
+    Ok(microsoft::models::DateTimeTimeZone {
+        date_time: date_time.format(format_description!(
+            "[year]-[month]-[day]T[hour]:[minute]:[second]"
+        ))?,
+        time_zone: "UTC".to_string(),
+    })
+}
+
+fn parse_date(date: &str) -> Option<Date> {
+    Date::parse(date, &format_description!("[year]-[month]-[day]")).ok()
+}
+
+fn format_date(date: Date) -> Result<String> {
+    Ok(date.format(format_description!("[year]-[month]-[day]"))?)
+}
+
+/// Converts Graph's structured recurrence into an RFC 5545 `RRULE` line.
+fn graph_recurrence_to_rrule(recurrence: &microsoft::models::PatternedRecurrence) -> String {
+    let pattern = &recurrence.pattern;
+    let range = &recurrence.range;
+
+    let by_day = pattern
+        .days_of_week
+        .iter()
+        .filter_map(|day| match day.as_str() {
+            "sunday" => Some("SU"),
+            "monday" => Some("MO"),
+            "tuesday" => Some("TU"),
+            "wednesday" => Some("WE"),
+            "thursday" => Some("TH"),
+            "friday" => Some("FR"),
+            "saturday" => Some("SA"),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    let set_pos = match pattern.index.as_deref() {
+        Some("second") => 2,
+        Some("third") => 3,
+        Some("fourth") => 4,
+        Some("last") => -1,
+        _ => 1,
+    };
+
+    let mut parts = vec![];
+    match pattern.pattern_type.as_str() {
+        "daily" => parts.push("FREQ=DAILY".to_string()),
+        "weekly" => {
+            parts.push("FREQ=WEEKLY".to_string());
+            parts.push(format!("BYDAY={by_day}"));
+        }
+        "absoluteMonthly" => {
+            parts.push("FREQ=MONTHLY".to_string());
+            parts.push(format!("BYMONTHDAY={}", pattern.day_of_month));
+        }
+        "relativeMonthly" => {
+            parts.push("FREQ=MONTHLY".to_string());
+            parts.push(format!("BYDAY={by_day}"));
+            parts.push(format!("BYSETPOS={set_pos}"));
+        }
+        "absoluteYearly" => {
+            parts.push("FREQ=YEARLY".to_string());
+            parts.push(format!("BYMONTH={}", pattern.month));
+            parts.push(format!("BYMONTHDAY={}", pattern.day_of_month));
+        }
+        "relativeYearly" => {
+            parts.push("FREQ=YEARLY".to_string());
+            parts.push(format!("BYMONTH={}", pattern.month));
+            parts.push(format!("BYDAY={by_day}"));
+            parts.push(format!("BYSETPOS={set_pos}"));
+        }
+        other => {
+            tracing::warn!("Unknown Graph recurrence pattern: {other}");
+            parts.push("FREQ=DAILY".to_string());
+        }
+    }
+    if pattern.interval > 1 {
+        parts.push(format!("INTERVAL={}", pattern.interval));
+    }
+    if let Some(first_day) = &pattern.first_day_of_week {
+        if let Some(wkst) = first_day.get(..2) {
+            parts.push(format!("WKST={}", wkst.to_uppercase()));
+        }
+    }
+    match range.range_type.as_str() {
+        "endDate" => {
+            if let Some(end) = range.end_date.as_deref().and_then(parse_date) {
+                parts.push(format!(
+                    "UNTIL={:04}{:02}{:02}T235959Z",
+                    end.year(),
+                    end.month() as u8,
+                    end.day()
+                ));
+            }
+        }
+        "numbered" => {
+            if let Some(count) = range.number_of_occurrences {
+                parts.push(format!("COUNT={count}"));
+            }
+        }
+        _ => {}
+    }
+
+    format!("RRULE:{}", parts.join(";"))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use time::macros::{date, datetime};
+
+    use super::*;
+
+    fn google_event(start: Value, end: Value) -> google::models::Event {
+        serde_json::from_value(json!({ "id": "a", "start": start, "end": end })).unwrap()
+    }
+
+    fn microsoft_event(start: &str, end: &str, all_day: bool) -> microsoft::models::Event {
+        serde_json::from_value(json!({
+            "id": "a",
+            "start": { "dateTime": start, "timeZone": "UTC" },
+            "end": { "dateTime": end, "timeZone": "UTC" },
+            "isAllDay": all_day,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn reads_google_times() {
+        let event = Event::try_from(google_event(
+            json!({ "dateTime": "2024-03-04T09:00:00+01:00" }),
+            json!({ "dateTime": "2024-03-04T10:00:00+01:00" }),
+        ))
+        .unwrap();
+        assert_eq!(
+            event.start,
+            EventTime::DateTime(datetime!(2024-03-04 9:00 +1))
+        );
+
+        let event = Event::try_from(google_event(
+            json!({ "date": "2024-03-04" }),
+            json!({ "date": "2024-03-05" }),
+        ))
+        .unwrap();
+        assert_eq!(event.end, EventTime::Date(date!(2024 - 03 - 05)));
+    }
+
+    #[test]
+    fn refuses_unreadable_google_times() {
+        let event = google_event(
+            json!({ "dateTime": "next tuesday" }),
+            json!({ "dateTime": "2024-03-04T10:00:00Z" }),
+        );
+        assert!(matches!(
+            Event::try_from(event),
+            Err(Error::InvalidEventTime(_))
+        ));
+        assert!(Event::try_from(google_event(json!({}), json!({}))).is_err());
+    }
+
+    #[test]
+    fn reads_microsoft_times() {
+        let event = Event::try_from(microsoft_event(
+            "2024-03-04T09:00:00.0000000",
+            "2024-03-04T10:00:00.0000000",
+            false,
+        ))
+        .unwrap();
+        assert_eq!(
+            event.start,
+            EventTime::DateTime(datetime!(2024-03-04 9:00 UTC))
+        );
+
+        let event = Event::try_from(microsoft_event(
+            "2024-03-04T00:00:00.0000000",
+            "2024-03-05T00:00:00.0000000",
+            true,
+        ))
+        .unwrap();
+        assert_eq!(event.start, EventTime::Date(date!(2024 - 03 - 04)));
+    }
+
+    #[test]
+    fn refuses_unreadable_microsoft_times() {
+        let event = microsoft_event("2024-03-04", "2024-03-04T10:00:00", false);
+        assert!(matches!(
+            Event::try_from(event),
+            Err(Error::InvalidEventTime(_))
+        ));
+    }
+}
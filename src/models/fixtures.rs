@@ -0,0 +1,61 @@
+//! Events and calendars to build tests on.
+
+use std::collections::HashMap;
+
+use time::{Date, OffsetDateTime};
+
+use super::{Calendar, Event, EventStatus, EventTime, Provider};
+
+/// A writable local calendar with the id `id`.
+pub fn calendar(id: &str) -> Calendar {
+    Calendar {
+        id: id.to_string(),
+        name: id.to_string(),
+        description: None,
+        timezone: None,
+        color: None,
+        access_role: "owner".to_string(),
+        provider: Provider::Local,
+        extra: HashMap::new(),
+    }
+}
+
+/// An event of the calendar `calendar_id` from `start` to `end`.
+pub fn event(calendar_id: &str, id: &str, start: OffsetDateTime, end: OffsetDateTime) -> Event {
+    with_times(
+        calendar_id,
+        id,
+        EventTime::DateTime(start),
+        EventTime::DateTime(end),
+    )
+}
+
+/// An event of the calendar `calendar_id` lasting all day on `date`.
+pub fn all_day(calendar_id: &str, id: &str, date: Date) -> Event {
+    with_times(
+        calendar_id,
+        id,
+        EventTime::Date(date),
+        EventTime::Date(date.next_day().expect("date is not the last one")),
+    )
+}
+
+fn with_times(calendar_id: &str, id: &str, start: EventTime, end: EventTime) -> Event {
+    Event {
+        id: id.to_string(),
+        calendar_id: calendar_id.to_string(),
+        summary: id.to_string(),
+        description: None,
+        location: None,
+        start,
+        end,
+        time_zone: None,
+        attendees: vec![],
+        organizer: None,
+        status: EventStatus::Confirmed,
+        recurrence: vec![],
+        reminders: vec![],
+        provider: Provider::Local,
+        extra: HashMap::new(),
+    }
+}
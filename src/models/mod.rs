@@ -1,2 +1,11 @@
 mod calendar;
+mod event;
+#[cfg(test)]
+pub mod fixtures;
+mod source;
+pub mod zone;
 pub use calendar::Calendar;
+pub use event::{
+    Attendee, AttendeeResponse, Event, EventStatus, EventTime, Organizer, Reminder, ReminderMethod,
+};
+pub use source::{CalDavAccount, Feed, Provider, Source};
@@ -0,0 +1,81 @@
+use accounts::models::Account;
+use serde::{Deserialize, Serialize};
+
+use crate::fl;
+
+/// The backend a calendar or event comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Provider {
+    Google,
+    Microsoft,
+    CalDav,
+    Local,
+    Subscription,
+}
+
+impl From<accounts::models::Provider> for Provider {
+    fn from(provider: accounts::models::Provider) -> Self {
+        match provider {
+            accounts::models::Provider::Google => Provider::Google,
+            accounts::models::Provider::Microsoft => Provider::Microsoft,
+        }
+    }
+}
+
+/// A CalDAV server the user added to the app, such as Nextcloud or Radicale.
+/// Its password is kept in the keyring rather than in the config, see [`crate::keyring`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct CalDavAccount {
+    /// The server or principal URL, e.g. `https://cloud.example.com/remote.php/dav`.
+    pub url: String,
+    pub username: String,
+}
+
+/// A published `.ics` feed the user subscribed to, such as a holiday or on-call calendar.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Feed {
+    /// The feed URL, with `webcal://` already turned into `https://`.
+    pub url: String,
+}
+
+/// Where a set of calendars comes from: an online account managed by cosmic accounts,
+/// a server or feed configured in the app itself, or the calendars stored on this computer.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Source {
+    Local,
+    Account(Account),
+    CalDav(CalDavAccount),
+    Subscription(Feed),
+}
+
+impl Source {
+    /// A stable identifier for the source, used to key cached data.
+    pub fn key(&self) -> String {
+        match self {
+            Source::Local => "local".to_string(),
+            Source::Account(account) => format!("account:{}", account.id),
+            Source::CalDav(account) => format!("caldav:{}@{}", account.username, account.url),
+            Source::Subscription(feed) => format!("subscription:{}", feed.url),
+        }
+    }
+
+    /// The label shown for the source in the nav bar.
+    pub fn name(&self) -> String {
+        match self {
+            Source::Local => fl!("local-calendars"),
+            Source::Account(account) => account.username.clone(),
+            Source::CalDav(account) => match reqwest::Url::parse(&account.url) {
+                Ok(url) => format!(
+                    "{}@{}",
+                    account.username,
+                    url.host_str().unwrap_or_default()
+                ),
+                Err(_) => account.username.clone(),
+            },
+            Source::Subscription(feed) => match reqwest::Url::parse(&feed.url) {
+                Ok(url) => url.host_str().unwrap_or_default().to_string(),
+                Err(_) => feed.url.clone(),
+            },
+        }
+    }
+}
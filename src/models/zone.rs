@@ -0,0 +1,64 @@
+//! IANA time zone lookups and the conversions between wall-clock times and instants
+//! that the models and the calendar grid share.
+
+use std::sync::LazyLock;
+
+use time::{Date, Duration, OffsetDateTime, PrimitiveDateTime};
+use time_tz::{
+    timezones, Offset, OffsetDateTimeExt, OffsetResult, PrimitiveDateTimeExt, TimeZone, Tz,
+};
+
+static SYSTEM: LazyLock<&'static Tz> = LazyLock::new(|| {
+    time_tz::system::get_timezone().unwrap_or_else(|err| {
+        tracing::warn!("Failed to get the system time zone, using UTC: {}", err);
+        timezones::db::UTC
+    })
+});
+
+/// The zone of the system, which the calendar is displayed in.
+/// Falls back to UTC when it can't be determined.
+pub fn system() -> &'static Tz {
+    *SYSTEM
+}
+
+/// Looks up an IANA zone such as `Europe/Warsaw` in the time zone database.
+pub fn named(name: &str) -> Option<&'static Tz> {
+    timezones::get_by_name(name.trim())
+}
+
+/// The current time in `zone`.
+pub fn now(zone: &Tz) -> OffsetDateTime {
+    OffsetDateTime::now_utc().to_timezone(zone)
+}
+
+/// The instant a wall-clock time in `zone` stands for. A time repeated when clocks
+/// go back means its first instance, and a time skipped when they go forward is
+/// moved ahead by the length of the gap, as RFC 5545 prescribes.
+pub fn resolve(local: PrimitiveDateTime, zone: &Tz) -> OffsetDateTime {
+    match local.assume_timezone(zone) {
+        OffsetResult::Some(date_time) => date_time,
+        OffsetResult::Ambiguous(a, b) => a.min(b),
+        OffsetResult::None => {
+            let before = zone.get_offset_utc(&(local.assume_utc() - Duration::DAY));
+            local.assume_offset(before.to_utc())
+        }
+    }
+}
+
+/// The instant `date` starts in `zone`. Days are not always 24 hours long,
+/// so the next day has to be resolved on its own rather than added.
+pub fn midnight(date: Date, zone: &Tz) -> OffsetDateTime {
+    resolve(date.midnight(), zone)
+}
+
+/// Names of every zone in the time zone database, sorted.
+pub fn names() -> Vec<&'static str> {
+    let mut names: Vec<&'static str> = timezones::iter().map(|zone| zone.name()).collect();
+    names.sort_unstable();
+    names
+}
+
+/// The abbreviation `zone` goes by at `at`, such as `CEST`.
+pub fn abbreviation(zone: &Tz, at: OffsetDateTime) -> String {
+    zone.get_offset_utc(&at).name().to_string()
+}
@@ -0,0 +1,453 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Writes of events that keep working offline. Changes that can't reach their provider
+//! are queued in a database under the data directory, which outlives the cache, and
+//! shown as if they were saved, then replayed in order once the provider can be
+//! reached again. Providers refuse replayed changes to events that were edited
+//! elsewhere in the meantime, based on the etag the change was made from.
+
+use std::{collections::HashSet, time::Duration};
+
+use time::OffsetDateTime;
+use tokio::sync::Mutex;
+
+use crate::{
+    cache::{self, Cache},
+    models::{Calendar, Event, Source},
+    services::CalendarServiceFactory,
+    Result,
+};
+
+/// How often queued changes are retried.
+pub const RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Prefix of the ids given to events created offline until their provider assigns one.
+const PENDING_PREFIX: &str = "pending-";
+
+/// Keeps replays from running concurrently and sending a change twice.
+static REPLAY_LOCK: Mutex<()> = Mutex::const_new(());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Create,
+    Update,
+    Delete,
+}
+
+/// A change waiting in the outbox.
+#[derive(Debug, Clone)]
+pub struct PendingChange {
+    pub id: i64,
+    /// The [`Source::key`] of the source the change goes to.
+    pub source: String,
+    pub operation: Operation,
+    pub calendar: Calendar,
+    pub event: Event,
+}
+
+/// What replaying the outbox did.
+#[derive(Debug, Clone, Default)]
+pub struct Replayed {
+    /// Events as stored by their provider, along with their source and the id they were
+    /// shown with before.
+    pub saved: Vec<(Source, String, Event)>,
+    /// Titles of the events whose changes were refused and dropped.
+    pub failed: Vec<String>,
+    /// How many changes are still waiting for their provider.
+    pub remaining: usize,
+}
+
+impl Operation {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Operation::Create => "create",
+            Operation::Update => "update",
+            Operation::Delete => "delete",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "create" => Some(Operation::Create),
+            "update" => Some(Operation::Update),
+            "delete" => Some(Operation::Delete),
+            _ => None,
+        }
+    }
+}
+
+/// Whether `event` was created offline and doesn't exist at its provider yet.
+pub fn is_pending(event: &Event) -> bool {
+    event.id.starts_with(PENDING_PREFIX)
+}
+
+/// Creates or updates `event`, queuing the change when its provider can't be reached.
+pub async fn save(source: Source, calendar: Calendar, event: Event) -> Result<Event> {
+    let operation = if event.id.is_empty() {
+        Operation::Create
+    } else {
+        Operation::Update
+    };
+    write(source, calendar, event, operation).await
+}
+
+/// Creates `event` under the id it already has, such as the UID of an imported one,
+/// queuing it when its provider can't be reached. Google and Graph pick ids of their
+/// own; Google keeps the id as the event's UID, while Graph doesn't keep it at all.
+pub async fn create(source: Source, calendar: Calendar, event: Event) -> Result<Event> {
+    write(source, calendar, event, Operation::Create).await
+}
+
+async fn write(
+    source: Source,
+    calendar: Calendar,
+    event: Event,
+    operation: Operation,
+) -> Result<Event> {
+    if !is_pending(&event) {
+        let saved = async {
+            let mut service = CalendarServiceFactory::get_service(&source).await?;
+            match operation {
+                Operation::Create => service.create_event(&calendar, &event).await,
+                _ => service.update_event(&calendar, &event).await,
+            }
+        }
+        .await;
+        match saved {
+            Ok(saved) => {
+                let cached = saved.clone();
+                if let Err(err) = cache::with(move |cache| cache.save_event(&source, &cached)).await
+                {
+                    tracing::warn!("Failed to cache event: {}", err);
+                }
+                return Ok(saved);
+            }
+            Err(err) if err.is_offline() => {
+                tracing::info!("Queuing changes to {} until online: {}", event.summary, err);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    calendar.check_editable()?;
+    let mut queued = event;
+    if queued.id.is_empty() {
+        queued.id = format!("{PENDING_PREFIX}{}", uuid::Uuid::new_v4());
+        queued.calendar_id = calendar.id.clone();
+        queued.provider = calendar.provider;
+    }
+    cache::with(move |cache| {
+        cache.enqueue(&source, operation, &calendar, &queued)?;
+        cache.save_event(&source, &queued)?;
+        Ok(queued)
+    })
+    .await
+}
+
+/// Deletes `event`, queuing the change when its provider can't be reached.
+pub async fn delete(source: Source, calendar: Calendar, event: Event) -> Result<Event> {
+    if !is_pending(&event) {
+        let deleted = async {
+            let mut service = CalendarServiceFactory::get_service(&source).await?;
+            service.delete_event(&calendar, &event).await
+        }
+        .await;
+        match deleted {
+            Ok(()) => {
+                let cached = event.clone();
+                if let Err(err) =
+                    cache::with(move |cache| cache.remove_event(&source, &cached)).await
+                {
+                    tracing::warn!("Failed to cache event: {}", err);
+                }
+                return Ok(event);
+            }
+            Err(err) if err.is_offline() => {
+                tracing::info!(
+                    "Queuing deletion of {} until online: {}",
+                    event.summary,
+                    err
+                );
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    calendar.check_editable()?;
+    cache::with(move |cache| {
+        cache.enqueue(&source, Operation::Delete, &calendar, &event)?;
+        cache.remove_event(&source, &event)?;
+        Ok(event)
+    })
+    .await
+}
+
+/// Sends the queued changes to their providers, in the order they were made. Changes
+/// to a source that can't be reached, fails for now, or isn't among `sources` yet stay
+/// queued along with every later change to it. Only changes the provider refuses for
+/// good are dropped.
+pub async fn replay(sources: Vec<Source>) -> Result<Replayed> {
+    let _lock = REPLAY_LOCK.lock().await;
+    let pending = cache::with(|cache| cache.pending_changes()).await?;
+
+    let mut replayed = Replayed::default();
+    let mut blocked = HashSet::new();
+    for change in pending {
+        let source = sources.iter().find(|source| source.key() == change.source);
+        let Some(source) = source.filter(|_| !blocked.contains(&change.source)) else {
+            blocked.insert(change.source);
+            replayed.remaining += 1;
+            continue;
+        };
+
+        let outcome = outcome(&change, send(source, &change).await);
+        let (settled_source, settled_change, settled) =
+            (source.clone(), change.clone(), outcome.clone());
+        cache::with(move |cache| settle(cache, &settled_source, &settled_change, &settled)).await?;
+        match outcome {
+            Outcome::Sent(Some(saved)) => {
+                replayed
+                    .saved
+                    .push((source.clone(), change.event.id, saved));
+            }
+            Outcome::Sent(None) => {}
+            // Later changes to the source may depend on this one, so they wait too.
+            Outcome::Kept => {
+                blocked.insert(change.source);
+                replayed.remaining += 1;
+            }
+            Outcome::Dropped => replayed.failed.push(change.event.summary),
+        }
+    }
+    Ok(replayed)
+}
+
+/// What became of a queued change sent to its provider.
+#[derive(Debug, Clone)]
+enum Outcome {
+    /// The provider took it, and stores the event like this unless it was deleted.
+    Sent(Option<Event>),
+    /// It may go through later, so it stays queued.
+    Kept,
+    /// The provider refused it for good, so it's dropped.
+    Dropped,
+}
+
+fn outcome(change: &PendingChange, sent: Result<Option<Event>>) -> Outcome {
+    match sent {
+        Ok(saved) => Outcome::Sent(saved),
+        Err(err) if err.is_refused() => {
+            tracing::warn!(
+                "Dropping queued change to {}: {}",
+                change.event.summary,
+                err
+            );
+            Outcome::Dropped
+        }
+        Err(err) => {
+            if !err.is_offline() {
+                tracing::warn!(
+                    "Keeping queued change to {} for later: {}",
+                    change.event.summary,
+                    err
+                );
+            }
+            Outcome::Kept
+        }
+    }
+}
+
+/// Updates the outbox and the cached events of `source` after `change` was sent.
+fn settle(
+    cache: &mut Cache,
+    source: &Source,
+    change: &PendingChange,
+    outcome: &Outcome,
+) -> Result<()> {
+    match outcome {
+        Outcome::Sent(stored) => {
+            cache.remove_pending(change.id)?;
+            cache.remove_event(source, &change.event)?;
+            if let Some(stored) = stored {
+                cache.save_event(source, stored)?;
+            }
+        }
+        Outcome::Kept => {}
+        Outcome::Dropped => {
+            cache.remove_pending(change.id)?;
+            if change.operation == Operation::Create {
+                cache.remove_event(source, &change.event)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Sends one queued change, returning the event as stored unless it was deleted.
+async fn send(source: &Source, change: &PendingChange) -> Result<Option<Event>> {
+    let mut service = CalendarServiceFactory::get_service(source).await?;
+    match change.operation {
+        Operation::Create => {
+            // Only ids made up while queuing are left for the provider to choose.
+            let mut event = change.event.clone();
+            if is_pending(&event) {
+                event.id.clear();
+            }
+            Ok(Some(service.create_event(&change.calendar, &event).await?))
+        }
+        Operation::Update => Ok(Some(
+            service
+                .update_event(&change.calendar, &change.event)
+                .await?,
+        )),
+        Operation::Delete => {
+            service
+                .delete_event(&change.calendar, &change.event)
+                .await?;
+            Ok(None)
+        }
+    }
+}
+
+/// Applies the queued changes to a calendar on top of `events` just fetched for
+/// `[start, end)`, so changes made offline stay visible until they're replayed.
+pub async fn overlay(
+    source: &Source,
+    calendar_id: &str,
+    start: OffsetDateTime,
+    end: OffsetDateTime,
+    mut events: Vec<Event>,
+) -> Vec<Event> {
+    let pending = match cache::with(|cache| cache.pending_changes()).await {
+        Ok(pending) => pending,
+        Err(err) => {
+            tracing::warn!("Failed to read queued changes: {}", err);
+            return events;
+        }
+    };
+
+    let key = source.key();
+    for change in pending {
+        if change.source != key || change.event.calendar_id != calendar_id {
+            continue;
+        }
+        events.retain(|event| event.id != change.event.id);
+        if change.operation != Operation::Delete && change.event.overlaps(start, end) {
+            events.push(change.event);
+        }
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::StatusCode;
+    use time::macros::datetime;
+
+    use super::*;
+    use crate::{
+        models::fixtures::{calendar, event},
+        Error,
+    };
+
+    fn http(status: StatusCode) -> Error {
+        Error::Http {
+            action: "save event".to_string(),
+            status,
+            message: String::new(),
+        }
+    }
+
+    /// Queues the creation of an event in `cache`, as saving it offline does.
+    fn queue_creation(cache: &mut Cache) -> PendingChange {
+        let event = event(
+            "work",
+            "pending-1",
+            datetime!(2024-03-04 10:00 UTC),
+            datetime!(2024-03-04 11:00 UTC),
+        );
+        let calendar = calendar("work");
+        cache.save_event(&Source::Local, &event).unwrap();
+        cache
+            .enqueue(&Source::Local, Operation::Create, &calendar, &event)
+            .unwrap();
+        cache.pending_changes().unwrap().remove(0)
+    }
+
+    fn cached(cache: &Cache) -> Vec<Event> {
+        cache
+            .events(
+                &Source::Local,
+                "work",
+                datetime!(2024-03-04 00:00 UTC),
+                datetime!(2024-03-05 00:00 UTC),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn retryable_errors_keep_the_change() {
+        let mut cache = Cache::open_in_memory().unwrap();
+        let change = queue_creation(&mut cache);
+        let errors = [
+            http(StatusCode::INTERNAL_SERVER_ERROR),
+            http(StatusCode::SERVICE_UNAVAILABLE),
+            http(StatusCode::TOO_MANY_REQUESTS),
+            http(StatusCode::UNAUTHORIZED),
+            http(StatusCode::REQUEST_TIMEOUT),
+        ];
+        for err in errors {
+            let outcome = outcome(&change, Err(err));
+            assert!(matches!(outcome, Outcome::Kept), "{outcome:?}");
+            settle(&mut cache, &Source::Local, &change, &outcome).unwrap();
+        }
+
+        assert_eq!(cache.pending_changes().unwrap().len(), 1);
+        assert_eq!(cached(&cache).len(), 1);
+    }
+
+    #[test]
+    fn refused_changes_are_dropped() {
+        let change = queue_creation(&mut Cache::open_in_memory().unwrap());
+        let errors = [
+            http(StatusCode::BAD_REQUEST),
+            http(StatusCode::FORBIDDEN),
+            http(StatusCode::NOT_FOUND),
+            Error::EventConflict(change.event.summary.clone()),
+        ];
+        for err in errors {
+            let outcome = outcome(&change, Err(err));
+            assert!(matches!(outcome, Outcome::Dropped), "{outcome:?}");
+        }
+    }
+
+    #[test]
+    fn dropping_a_creation_removes_its_event() {
+        let mut cache = Cache::open_in_memory().unwrap();
+        let change = queue_creation(&mut cache);
+        settle(&mut cache, &Source::Local, &change, &Outcome::Dropped).unwrap();
+
+        assert!(cache.pending_changes().unwrap().is_empty());
+        assert!(cached(&cache).is_empty());
+    }
+
+    #[test]
+    fn sent_changes_store_the_provider_event() {
+        let mut cache = Cache::open_in_memory().unwrap();
+        let change = queue_creation(&mut cache);
+        let mut stored = change.event.clone();
+        stored.id = "abc".to_string();
+        settle(
+            &mut cache,
+            &Source::Local,
+            &change,
+            &Outcome::Sent(Some(stored)),
+        )
+        .unwrap();
+
+        assert!(cache.pending_changes().unwrap().is_empty());
+        let cached = cached(&cache);
+        assert_eq!(cached.len(), 1);
+        assert_eq!(cached[0].id, "abc");
+    }
+}
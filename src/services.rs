@@ -1,32 +1,227 @@
-use crate::{models::Calendar, Result};
+use crate::{
+    ical::recurrence,
+    keyring,
+    models::{Calendar, Event, EventTime, Source},
+    Error, Result,
+};
+pub mod caldav;
 pub mod google;
+pub mod local;
 pub mod microsoft;
+pub mod subscription;
 
-use accounts::{
-    models::{Account, Provider},
-    AccountsClient,
-};
+use accounts::{models::Provider, AccountsClient};
 use async_trait::async_trait;
+pub use caldav::CalDavCalendarService;
 pub use google::GoogleCalendarService;
+pub use local::LocalCalendarService;
 pub use microsoft::MicrosoftCalendarService;
+pub use subscription::SubscriptionService;
+use time::OffsetDateTime;
 
 #[async_trait]
 pub trait CalendarService: Send + Sync {
     async fn fetch_calendars(&mut self) -> Result<Vec<Calendar>>;
+
+    /// Fetches the events of a calendar that overlap `[start, end)`,
+    /// with recurring series expanded into their individual occurrences.
+    async fn fetch_events(
+        &mut self,
+        calendar_id: &str,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+    ) -> Result<Vec<Event>>;
+
+    /// Fetches every event of a calendar for export. Recurring series come back once,
+    /// with their recurrence lines, and modified occurrences as separate events
+    /// carrying a `RECURRENCE-ID` line in `extra`.
+    async fn fetch_all_events(&mut self, calendar_id: &str) -> Result<Vec<Event>>;
+
+    /// Fetches a single event as stored, such as the series an occurrence belongs to.
+    async fn fetch_event(&mut self, calendar_id: &str, event_id: &str) -> Result<Event>;
+
+    /// Fetches the events of a calendar within `[start, end)` that changed since the sync
+    /// that returned `token`, or all of them when there's no token yet. Providers without
+    /// incremental sync return `None`, and [`Error::SyncTokenExpired`] means the token
+    /// is no longer accepted and a full sync has to be done.
+    async fn sync_events(
+        &mut self,
+        _calendar_id: &str,
+        _start: OffsetDateTime,
+        _end: OffsetDateTime,
+        _token: Option<&str>,
+    ) -> Result<Option<SyncChanges>> {
+        Ok(None)
+    }
+
+    /// Creates `event` in `calendar`, returning it as stored by the provider.
+    async fn create_event(&mut self, calendar: &Calendar, event: &Event) -> Result<Event>;
+
+    /// Saves changes to an existing event, returning it as stored by the provider.
+    async fn update_event(&mut self, calendar: &Calendar, event: &Event) -> Result<Event>;
+
+    async fn delete_event(&mut self, calendar: &Calendar, event: &Event) -> Result<()>;
+
+    /// Saves the changes made to an occurrence of a recurring series to the occurrences
+    /// `scope` selects. `occurrence` is the occurrence as fetched and `changed` as edited.
+    async fn update_occurrence(
+        &mut self,
+        calendar: &Calendar,
+        occurrence: &Event,
+        changed: &Event,
+        scope: RecurrenceScope,
+    ) -> Result<()> {
+        if scope == RecurrenceScope::This {
+            return self.update_instance(calendar, occurrence, changed).await;
+        }
+        let series = self.fetch_series(calendar, occurrence).await?;
+        let start = occurrence.original_start();
+        if scope == RecurrenceScope::All || recurrence::starts_series(&series, start) {
+            let updated = recurrence::apply_changes(&series, start, changed);
+            self.update_event(calendar, &updated).await?;
+            return Ok(());
+        }
+        self.split_series(calendar, &series, start, Some(changed))
+            .await
+    }
+
+    /// Deletes the occurrences of a recurring series `scope` selects.
+    async fn delete_occurrence(
+        &mut self,
+        calendar: &Calendar,
+        occurrence: &Event,
+        scope: RecurrenceScope,
+    ) -> Result<()> {
+        if scope == RecurrenceScope::This {
+            return self.delete_instance(calendar, occurrence).await;
+        }
+        let series = self.fetch_series(calendar, occurrence).await?;
+        let start = occurrence.original_start();
+        if scope == RecurrenceScope::All || recurrence::starts_series(&series, start) {
+            return self.delete_event(calendar, &series).await;
+        }
+        self.split_series(calendar, &series, start, None).await
+    }
+
+    /// Saves changes to a single occurrence. Providers that list occurrences as events
+    /// of their own, like Google and Microsoft, turn them into exceptions of the series.
+    async fn update_instance(
+        &mut self,
+        calendar: &Calendar,
+        _occurrence: &Event,
+        changed: &Event,
+    ) -> Result<()> {
+        self.update_event(calendar, changed).await?;
+        Ok(())
+    }
+
+    /// Deletes a single occurrence, cancelling it in its series.
+    async fn delete_instance(&mut self, calendar: &Calendar, occurrence: &Event) -> Result<()> {
+        self.delete_event(calendar, occurrence).await
+    }
+
+    /// Ends `series` before its occurrence originally starting at `start` and, when
+    /// the occurrences from there on were `changed`, saves them as a new series.
+    ///
+    /// The series is ended first, through an update guarded by the etag it was fetched
+    /// with, so a series changed elsewhere in the meantime is left alone and the new
+    /// one is only created once the old one stopped short of it.
+    async fn split_series(
+        &mut self,
+        calendar: &Calendar,
+        series: &Event,
+        start: EventTime,
+        changed: Option<&Event>,
+    ) -> Result<()> {
+        let (ended, following) = recurrence::split(series, start)?;
+        self.update_event(calendar, &ended).await?;
+        if let Some(changed) = changed {
+            let following = recurrence::apply_changes(&following, start, changed);
+            self.create_event(calendar, &following).await?;
+        }
+        Ok(())
+    }
+
+    /// Fetches the series `occurrence` belongs to.
+    async fn fetch_series(&mut self, calendar: &Calendar, occurrence: &Event) -> Result<Event> {
+        let series_id = occurrence.series_id().ok_or_else(|| {
+            Error::Unknown(format!("{} is not part of a series", occurrence.summary))
+        })?;
+        self.fetch_event(&calendar.id, series_id).await
+    }
+}
+
+/// Which occurrences of a recurring series a change applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecurrenceScope {
+    This,
+    ThisAndFollowing,
+    All,
+}
+
+/// What changed in a calendar since its last sync.
+#[derive(Debug, Default)]
+pub struct SyncChanges {
+    /// Whether this lists every event, replacing whatever was stored before.
+    pub full: bool,
+    /// Events that were created or changed.
+    pub changed: Vec<Event>,
+    /// Ids of events that were deleted.
+    pub removed: Vec<String>,
+    /// Passed to the next sync to only get what changed after this one.
+    pub token: String,
+}
+
+/// Turns an unsuccessful response into an error carrying the provider's message.
+pub(crate) async fn check_response(
+    response: reqwest::Response,
+    action: &str,
+) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        Ok(response)
+    } else {
+        Err(Error::Http {
+            action: action.to_string(),
+            status,
+            message: response.text().await?,
+        })
+    }
+}
+
+/// Reads an event a provider listed, leaving it out with a warning when its times
+/// can't be read, so one bad item doesn't fail the whole listing.
+pub(crate) fn readable_event<T>(item: T) -> Option<Event>
+where
+    Event: TryFrom<T, Error = Error>,
+{
+    Event::try_from(item)
+        .inspect_err(|err| tracing::warn!("Skipping event: {err}"))
+        .ok()
 }
 
 pub struct CalendarServiceFactory;
 
 impl CalendarServiceFactory {
-    pub async fn get_service(account: &Account) -> Result<Box<dyn CalendarService>> {
-        let client = AccountsClient::new().await?;
-        match account.provider {
-            Provider::Google => Ok(Box::new(
-                GoogleCalendarService::new(account, &client).await?,
-            )),
-            Provider::Microsoft => Ok(Box::new(
-                MicrosoftCalendarService::new(account, &client).await?,
-            )),
+    pub async fn get_service(source: &Source) -> Result<Box<dyn CalendarService>> {
+        match source {
+            Source::Local => Ok(Box::new(LocalCalendarService::new()?)),
+            Source::Account(account) => {
+                let client = AccountsClient::new().await?;
+                match account.provider {
+                    Provider::Google => Ok(Box::new(
+                        GoogleCalendarService::new(account, &client).await?,
+                    )),
+                    Provider::Microsoft => Ok(Box::new(
+                        MicrosoftCalendarService::new(account, &client).await?,
+                    )),
+                }
+            }
+            Source::CalDav(account) => {
+                let password = keyring::caldav_password(account).await?;
+                Ok(Box::new(CalDavCalendarService::new(account, password)?))
+            }
+            Source::Subscription(feed) => Ok(Box::new(SubscriptionService::new(feed)?)),
         }
     }
 }
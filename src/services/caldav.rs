@@ -0,0 +1,875 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use reqwest::{header, Method, StatusCode, Url};
+use serde_json::Value;
+use time::{OffsetDateTime, UtcOffset};
+
+use crate::{
+    ical::{self, recurrence},
+    models::{CalDavAccount, Calendar, Event, EventTime, Provider},
+    services::{check_response, CalendarService},
+    Error, Result,
+};
+
+mod xml;
+
+/// Asks for the principal of the logged in user.
+const PRINCIPAL_QUERY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:">
+  <d:prop><d:current-user-principal/></d:prop>
+</d:propfind>"#;
+
+/// Asks a principal where its calendars live.
+const HOME_SET_QUERY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop><c:calendar-home-set/></d:prop>
+</d:propfind>"#;
+
+/// Lists the collections of a calendar home along with what the app shows of them.
+const CALENDARS_QUERY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav" xmlns:cs="http://calendarserver.org/ns/" xmlns:ic="http://apple.com/ns/ical/">
+  <d:prop>
+    <d:resourcetype/>
+    <d:displayname/>
+    <d:current-user-privilege-set/>
+    <c:calendar-description/>
+    <c:supported-calendar-component-set/>
+    <cs:getctag/>
+    <ic:calendar-color/>
+  </d:prop>
+</d:propfind>"#;
+
+/// A calendar service for servers speaking CalDAV (RFC 4791), such as Nextcloud and Radicale.
+///
+/// Calendars are identified by the path of their collection and events by their UID.
+/// The path and ETag of the resource holding each event are kept in its `extra` bag
+/// under `"href"` and `"etag"`, so writes can target it and detect concurrent changes.
+#[derive(Clone)]
+pub struct CalDavCalendarService {
+    account: CalDavAccount,
+    password: String,
+    http: reqwest::Client,
+    base: Url,
+}
+
+impl CalDavCalendarService {
+    pub fn new(account: &CalDavAccount, password: String) -> Result<Self> {
+        let mut base = Url::parse(account.url.trim())
+            .map_err(|err| Error::Unknown(format!("Invalid CalDAV URL: {err}")))?;
+        // Relative hrefs only resolve inside the collection when it ends with a slash.
+        if !base.path().ends_with('/') {
+            base.set_path(&format!("{}/", base.path()));
+        }
+        Ok(CalDavCalendarService {
+            account: account.clone(),
+            password,
+            http: reqwest::Client::new(),
+            base,
+        })
+    }
+
+    fn request(&self, method: Method, url: Url) -> reqwest::RequestBuilder {
+        self.http
+            .request(method, url)
+            .basic_auth(&self.account.username, Some(&self.password))
+    }
+
+    fn resolve(&self, href: &str) -> Result<Url> {
+        self.base
+            .join(href)
+            .map_err(|err| Error::Unknown(format!("Invalid href {href}: {err}")))
+    }
+
+    /// Sends a `PROPFIND` or `REPORT` and reads the multistatus it answers with.
+    async fn multistatus(
+        &self,
+        method: &[u8],
+        url: Url,
+        depth: &str,
+        body: String,
+        action: &str,
+    ) -> Result<Vec<xml::Response>> {
+        let method = Method::from_bytes(method).expect("WebDAV methods are valid");
+        let response = self
+            .request(method, url)
+            .header("Depth", depth)
+            .header(header::CONTENT_TYPE, "application/xml; charset=utf-8")
+            .body(body)
+            .send()
+            .await?;
+        let response = check_response(response, action).await?;
+        xml::multistatus(&response.text().await?)
+    }
+
+    /// Finds the collection holding the user's calendars by following
+    /// `current-user-principal` and `calendar-home-set`. Servers that don't
+    /// advertise them are assumed to have been given the home URL directly.
+    async fn calendar_home(&self) -> Result<Url> {
+        let principal = self
+            .multistatus(
+                b"PROPFIND",
+                self.base.clone(),
+                "0",
+                PRINCIPAL_QUERY.to_string(),
+                "discover principal",
+            )
+            .await?
+            .iter()
+            .find_map(|response| response.href_in("current-user-principal"));
+        let Some(principal) = principal else {
+            return Ok(self.base.clone());
+        };
+
+        let home = self
+            .multistatus(
+                b"PROPFIND",
+                self.resolve(&principal)?,
+                "0",
+                HOME_SET_QUERY.to_string(),
+                "discover calendar home",
+            )
+            .await?
+            .iter()
+            .find_map(|response| response.href_in("calendar-home-set"));
+        match home {
+            Some(home) => self.resolve(&home),
+            None => Ok(self.base.clone()),
+        }
+    }
+
+    fn calendar_from_response(response: &xml::Response) -> Option<Calendar> {
+        response.prop("resourcetype")?.child("calendar")?;
+        // Skip collections that only hold tasks or journals.
+        if let Some(components) = response.prop("supported-calendar-component-set") {
+            let has_events = components
+                .children("comp")
+                .any(|comp| comp.attribute("name") == Some("VEVENT"));
+            if !components.children.is_empty() && !has_events {
+                return None;
+            }
+        }
+
+        let privileges = response.prop("current-user-privilege-set");
+        let writable = privileges.map_or(true, |privileges| {
+            privileges.children("privilege").any(|privilege| {
+                ["all", "write", "write-content"]
+                    .iter()
+                    .any(|name| privilege.child(name).is_some())
+            })
+        });
+
+        let mut extra = HashMap::new();
+        if let Some(ctag) = response.text("getctag") {
+            extra.insert("ctag".to_string(), Value::String(ctag));
+        }
+
+        Some(Calendar {
+            id: response.href.clone(),
+            name: response.text("displayname").unwrap_or_else(|| {
+                response
+                    .href
+                    .trim_end_matches('/')
+                    .rsplit('/')
+                    .next()
+                    .unwrap_or_default()
+                    .to_string()
+            }),
+            description: response.text("calendar-description"),
+            timezone: None,
+            // Apple's color property is `#rrggbbaa`, the alpha is dropped.
+            color: response
+                .text("calendar-color")
+                .and_then(|color| color.get(..7).map(str::to_string)),
+            access_role: if writable { "owner" } else { "reader" }.to_string(),
+            provider: Provider::CalDav,
+            extra,
+        })
+    }
+
+    /// Where a new event with `uid` is stored inside `calendar`.
+    fn event_url(&self, calendar: &Calendar, uid: &str) -> Result<Url> {
+        let mut url = self.resolve(&calendar.id)?;
+        url.path_segments_mut()
+            .map_err(|_| Error::Unknown(format!("Invalid calendar URL {}", calendar.id)))?
+            .pop_if_empty()
+            .push(&format!("{uid}.ics"));
+        Ok(url)
+    }
+
+    fn stored_url(&self, event: &Event) -> Result<Option<Url>> {
+        match event.extra.get("href") {
+            Some(Value::String(href)) => self.resolve(href).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Where the resource holding `occurrence` and the rest of its series is stored.
+    fn series_url(&self, calendar: &Calendar, occurrence: &Event) -> Result<Url> {
+        match self.stored_url(occurrence)? {
+            Some(url) => Ok(url),
+            None => self.event_url(calendar, occurrence.series_id().unwrap_or(&occurrence.id)),
+        }
+    }
+
+    /// Stores `body` at `url`, as long as the resource is still the one `event` was
+    /// fetched from.
+    async fn put(&self, url: Url, body: String, event: &Event) -> Result<reqwest::Response> {
+        let mut request = self
+            .request(Method::PUT, url)
+            .header(header::CONTENT_TYPE, "text/calendar; charset=utf-8")
+            .body(body);
+        if let Some(Value::String(etag)) = event.extra.get("etag") {
+            request = request.header(header::IF_MATCH, etag);
+        }
+        let response = request.send().await?;
+        if response.status() == StatusCode::PRECONDITION_FAILED {
+            return Err(Error::EventConflict(event.summary.clone()));
+        }
+        check_response(response, "update event").await
+    }
+
+    /// Downloads the resource at `url`, changes it with `edit` and stores it back.
+    async fn rewrite(
+        &self,
+        url: Url,
+        event: &Event,
+        edit: impl FnOnce(&str) -> Result<String> + Send,
+    ) -> Result<()> {
+        let response = self.request(Method::GET, url.clone()).send().await?;
+        let response = check_response(response, "fetch event").await?;
+        let body = edit(&response.text().await?)?;
+        self.put(url, body, event).await?;
+        Ok(())
+    }
+
+    /// Runs a `calendar-query` REPORT for the events of a calendar matching `filter`,
+    /// the contents of the `VEVENT` comp-filter.
+    async fn query_events(&self, calendar_id: &str, filter: &str) -> Result<Vec<Event>> {
+        let body = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop><d:getetag/><c:calendar-data/></d:prop>
+  <c:filter>
+    <c:comp-filter name="VCALENDAR">
+      <c:comp-filter name="VEVENT">{filter}</c:comp-filter>
+    </c:comp-filter>
+  </c:filter>
+</c:calendar-query>"#
+        );
+        let responses = self
+            .multistatus(
+                b"REPORT",
+                self.resolve(calendar_id)?,
+                "1",
+                body,
+                "fetch events",
+            )
+            .await?;
+
+        let mut events = vec![];
+        for response in responses {
+            let Some(data) = response.text("calendar-data") else {
+                continue;
+            };
+            let etag = response.text("getetag");
+            let parsed = match ical::read_events(&data, calendar_id, Provider::CalDav) {
+                Ok(parsed) => parsed,
+                Err(err) => {
+                    tracing::warn!("Skipping {}: {err}", response.href);
+                    continue;
+                }
+            };
+            for skipped in &parsed.skipped {
+                if skipped.reason != ical::SkipReason::Cancelled {
+                    tracing::warn!(
+                        "Skipping {} in {}: {}",
+                        skipped.label,
+                        response.href,
+                        skipped.reason
+                    );
+                }
+            }
+            events.extend(parsed.events.into_iter().map(|mut event| {
+                event
+                    .extra
+                    .insert("href".to_string(), Value::String(response.href.clone()));
+                if let Some(etag) = &etag {
+                    event
+                        .extra
+                        .insert("etag".to_string(), Value::String(etag.clone()));
+                }
+                event
+            }));
+        }
+        Ok(events)
+    }
+}
+
+#[async_trait]
+impl CalendarService for CalDavCalendarService {
+    async fn fetch_calendars(&mut self) -> Result<Vec<Calendar>> {
+        let home = self.calendar_home().await?;
+        let calendars = self
+            .multistatus(
+                b"PROPFIND",
+                home,
+                "1",
+                CALENDARS_QUERY.to_string(),
+                "fetch calendars",
+            )
+            .await?
+            .iter()
+            .filter_map(Self::calendar_from_response)
+            .collect();
+        Ok(calendars)
+    }
+
+    async fn fetch_events(
+        &mut self,
+        calendar_id: &str,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+    ) -> Result<Vec<Event>> {
+        let time_range = format!(
+            r#"<c:time-range start="{}" end="{}"/>"#,
+            ical::format_utc(start),
+            ical::format_utc(end),
+        );
+        let events = self.query_events(calendar_id, &time_range).await?;
+        Ok(recurrence::expand(events, start, end))
+    }
+
+    async fn fetch_all_events(&mut self, calendar_id: &str) -> Result<Vec<Event>> {
+        self.query_events(calendar_id, "").await
+    }
+
+    async fn fetch_event(&mut self, calendar_id: &str, event_id: &str) -> Result<Event> {
+        let filter = format!(
+            r#"<c:prop-filter name="UID"><c:text-match collation="i;octet">{}</c:text-match></c:prop-filter>"#,
+            quick_xml::escape::escape(event_id)
+        );
+        self.query_events(calendar_id, &filter)
+            .await?
+            .into_iter()
+            .find(|event| event.id == event_id && !event.extra.contains_key("RECURRENCE-ID"))
+            .ok_or_else(|| Error::Unknown(format!("Event {event_id} not found")))
+    }
+
+    async fn create_event(&mut self, calendar: &Calendar, event: &Event) -> Result<Event> {
+        calendar.check_editable()?;
+
+        // The id is the UID, so imported events keep the one from their file.
+        let mut created = event.clone();
+        if created.id.is_empty() {
+            created.id = uuid::Uuid::new_v4().to_string();
+        }
+        created.calendar_id = calendar.id.clone();
+        created.provider = Provider::CalDav;
+
+        let url = self.event_url(calendar, &created.id)?;
+        let response = self
+            .request(Method::PUT, url.clone())
+            .header(header::CONTENT_TYPE, "text/calendar; charset=utf-8")
+            .header(header::IF_NONE_MATCH, "*")
+            .body(ical::write_event(&created))
+            .send()
+            .await?;
+        let response = check_response(response, "create event").await?;
+
+        created
+            .extra
+            .insert("href".to_string(), Value::String(url.path().to_string()));
+        if let Some(etag) = response_etag(&response) {
+            created
+                .extra
+                .insert("etag".to_string(), Value::String(etag));
+        }
+        Ok(created)
+    }
+
+    async fn update_event(&mut self, calendar: &Calendar, event: &Event) -> Result<Event> {
+        calendar.check_editable()?;
+
+        let url = match self.stored_url(event)? {
+            Some(url) => url,
+            None => self.event_url(calendar, &event.id)?,
+        };
+
+        // Rewrite the stored resource rather than replacing it, so overridden
+        // occurrences and properties the app doesn't model survive the update.
+        // Only a resource that's gone is written from scratch.
+        let existing = self.request(Method::GET, url.clone()).send().await?;
+        let body = if existing.status() == StatusCode::NOT_FOUND {
+            ical::write_event(event)
+        } else {
+            let existing = check_response(existing, "fetch event").await?;
+            ical::update_resource(&existing.text().await?, event)?
+        };
+        let response = self.put(url, body, event).await?;
+
+        let mut updated = event.clone();
+        match response_etag(&response) {
+            Some(etag) => updated
+                .extra
+                .insert("etag".to_string(), Value::String(etag)),
+            None => updated.extra.remove("etag"),
+        };
+        Ok(updated)
+    }
+
+    async fn delete_event(&mut self, calendar: &Calendar, event: &Event) -> Result<()> {
+        calendar.check_editable()?;
+
+        let url = match self.stored_url(event)? {
+            Some(url) => url,
+            None => self.event_url(calendar, &event.id)?,
+        };
+        let mut request = self.request(Method::DELETE, url);
+        if let Some(Value::String(etag)) = event.extra.get("etag") {
+            request = request.header(header::IF_MATCH, etag);
+        }
+        let response = request.send().await?;
+        match response.status() {
+            // Already gone from the server.
+            StatusCode::NOT_FOUND => Ok(()),
+            StatusCode::PRECONDITION_FAILED => Err(Error::EventConflict(event.summary.clone())),
+            _ => {
+                check_response(response, "delete event").await?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Changed occurrences are stored as overrides in the resource of their series.
+    async fn update_instance(
+        &mut self,
+        calendar: &Calendar,
+        _occurrence: &Event,
+        changed: &Event,
+    ) -> Result<()> {
+        let mut instance = changed.clone();
+        if let Some(series_id) = changed.series_id() {
+            instance.id = series_id.to_string();
+        }
+        instance.extra.remove("recurringEventId");
+        self.update_event(calendar, &instance).await?;
+        Ok(())
+    }
+
+    async fn delete_instance(&mut self, calendar: &Calendar, occurrence: &Event) -> Result<()> {
+        calendar.check_editable()?;
+
+        let uid = occurrence.series_id().unwrap_or(&occurrence.id).to_string();
+        let start = occurrence.original_start();
+        let url = self.series_url(calendar, occurrence)?;
+        self.rewrite(url, occurrence, |data| {
+            let mut series = series_in(data, calendar, &uid)?;
+            ical::recurrence::exclude(&mut series, start);
+            let data = ical::remove_overrides(data, &uid, |original| original == start)?;
+            ical::update_resource(&data, &series)
+        })
+        .await
+    }
+
+    /// Overrides of the occurrences from `start` on are dropped along with them.
+    async fn split_series(
+        &mut self,
+        calendar: &Calendar,
+        series: &Event,
+        start: EventTime,
+        changed: Option<&Event>,
+    ) -> Result<()> {
+        calendar.check_editable()?;
+
+        // The series is ended first, so a conflicting change to it leaves nothing
+        // behind, and the continuation is only created once that went through.
+        let (ended, following) = recurrence::split(series, start)?;
+        let url = self.series_url(calendar, series)?;
+        let split_at = start.at_offset(UtcOffset::UTC);
+        self.rewrite(url, series, |data| {
+            let data = ical::remove_overrides(data, &series.id, |original| {
+                original.at_offset(UtcOffset::UTC) >= split_at
+            })?;
+            ical::update_resource(&data, &ended)
+        })
+        .await?;
+
+        if let Some(changed) = changed {
+            let following = recurrence::apply_changes(&following, start, changed);
+            self.create_event(calendar, &following).await?;
+        }
+        Ok(())
+    }
+}
+
+/// The series `uid` among the events of a calendar resource.
+fn series_in(data: &str, calendar: &Calendar, uid: &str) -> Result<Event> {
+    ical::read_events(data, &calendar.id, Provider::CalDav)?
+        .events
+        .into_iter()
+        .find(|event| event.id == uid && !event.extra.contains_key("RECURRENCE-ID"))
+        .ok_or_else(|| Error::Unknown(format!("Event {uid} not found")))
+}
+
+fn response_etag(response: &reqwest::Response) -> Option<String> {
+    response
+        .headers()
+        .get(header::ETAG)?
+        .to_str()
+        .ok()
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex as StdMutex};
+
+    use time::macros::datetime;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+    use crate::models::fixtures;
+
+    const RESOURCE: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+PRODID:-//Test//EN\r
+BEGIN:VEVENT\r
+UID:standup\r
+DTSTAMP:20240101T000000Z\r
+DTSTART:20240304T100000Z\r
+DTEND:20240304T110000Z\r
+SUMMARY:Standup\r
+X-CUSTOM:kept\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+    /// A request the server received, with lowercase header names.
+    #[derive(Debug)]
+    struct Request {
+        method: String,
+        path: String,
+        headers: Vec<(String, String)>,
+        body: String,
+    }
+
+    impl Request {
+        fn header(&self, name: &str) -> Option<&str> {
+            self.headers
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        }
+    }
+
+    /// An answer of the server: the status line, extra headers and the body.
+    type Reply = (&'static str, Vec<(&'static str, &'static str)>, String);
+
+    fn multistatus(responses: &str) -> Reply {
+        (
+            "207 Multi-Status",
+            vec![("Content-Type", "application/xml; charset=utf-8")],
+            format!(
+                r#"<?xml version="1.0" encoding="utf-8"?><d:multistatus xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav" xmlns:cs="http://calendarserver.org/ns/" xmlns:ic="http://apple.com/ns/ical/">{responses}</d:multistatus>"#
+            ),
+        )
+    }
+
+    /// Answers requests with `replies` in turn. Returns an account on the server and
+    /// the requests received.
+    async fn serve(replies: Vec<Reply>) -> (CalDavAccount, Arc<StdMutex<Vec<Request>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/dav/", listener.local_addr().unwrap());
+        let requests = Arc::new(StdMutex::new(vec![]));
+
+        let received = requests.clone();
+        tokio::spawn(async move {
+            for (status, headers, body) in replies {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+                let request = read_request(&mut stream).await;
+                received.lock().unwrap().push(request);
+
+                let mut response = format!("HTTP/1.1 {status}\r\n");
+                for (name, value) in headers {
+                    response.push_str(&format!("{name}: {value}\r\n"));
+                }
+                response.push_str(&format!(
+                    "Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                ));
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            }
+        });
+
+        let account = CalDavAccount {
+            url,
+            username: "ann".to_string(),
+        };
+        (account, requests)
+    }
+
+    async fn read_request(stream: &mut tokio::net::TcpStream) -> Request {
+        let mut data = vec![];
+        let mut buffer = [0; 4096];
+        let head_end = loop {
+            if let Some(end) = data.windows(4).position(|window| window == b"\r\n\r\n") {
+                break end;
+            }
+            match stream.read(&mut buffer).await {
+                Ok(0) | Err(_) => break data.len(),
+                Ok(read) => data.extend_from_slice(&buffer[..read]),
+            }
+        };
+        let head = String::from_utf8_lossy(&data[..head_end]).into_owned();
+        let mut lines = head.split("\r\n");
+        let mut request_line = lines.next().unwrap_or_default().split(' ');
+        let method = request_line.next().unwrap_or_default().to_string();
+        let path = request_line.next().unwrap_or_default().to_string();
+        let headers: Vec<(String, String)> = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+            .collect();
+
+        let length: usize = headers
+            .iter()
+            .find(|(name, _)| name == "content-length")
+            .and_then(|(_, value)| value.parse().ok())
+            .unwrap_or(0);
+        let mut body = data.get(head_end + 4..).unwrap_or_default().to_vec();
+        while body.len() < length {
+            match stream.read(&mut buffer).await {
+                Ok(0) | Err(_) => break,
+                Ok(read) => body.extend_from_slice(&buffer[..read]),
+            }
+        }
+        Request {
+            method,
+            path,
+            headers,
+            body: String::from_utf8_lossy(&body).into_owned(),
+        }
+    }
+
+    fn calendar() -> Calendar {
+        let mut calendar = fixtures::calendar("/dav/calendars/ann/work/");
+        calendar.provider = Provider::CalDav;
+        calendar
+    }
+
+    /// The standup event as it was fetched, stored at its resource with etag `"1"`.
+    fn standup() -> Event {
+        let mut event = fixtures::event(
+            "/dav/calendars/ann/work/",
+            "standup",
+            datetime!(2024-03-04 10:00 UTC),
+            datetime!(2024-03-04 11:00 UTC),
+        );
+        event.provider = Provider::CalDav;
+        event.extra.insert(
+            "href".to_string(),
+            Value::String("/dav/calendars/ann/work/standup.ics".to_string()),
+        );
+        event
+            .extra
+            .insert("etag".to_string(), Value::String("\"1\"".to_string()));
+        event
+    }
+
+    #[tokio::test]
+    async fn discovers_calendars_through_the_principal() {
+        let (account, requests) = serve(vec![
+            multistatus(
+                "<d:response><d:href>/dav/</d:href><d:propstat><d:prop>\
+                <d:current-user-principal><d:href>/dav/principals/ann/</d:href></d:current-user-principal>\
+                </d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>",
+            ),
+            multistatus(
+                "<d:response><d:href>/dav/principals/ann/</d:href><d:propstat><d:prop>\
+                <c:calendar-home-set><d:href>/dav/calendars/ann/</d:href></c:calendar-home-set>\
+                </d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>",
+            ),
+            multistatus(
+                "<d:response><d:href>/dav/calendars/ann/</d:href><d:propstat><d:prop>\
+                <d:resourcetype><d:collection/></d:resourcetype>\
+                </d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>\
+                <d:response><d:href>/dav/calendars/ann/work/</d:href><d:propstat><d:prop>\
+                <d:resourcetype><d:collection/><c:calendar/></d:resourcetype>\
+                <d:displayname>Work</d:displayname>\
+                <ic:calendar-color>#FF8800FF</ic:calendar-color>\
+                <cs:getctag>ctag-1</cs:getctag>\
+                <d:current-user-privilege-set><d:privilege><d:read/></d:privilege>\
+                <d:privilege><d:write/></d:privilege></d:current-user-privilege-set>\
+                </d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>\
+                <d:response><d:href>/dav/calendars/ann/tasks/</d:href><d:propstat><d:prop>\
+                <d:resourcetype><d:collection/><c:calendar/></d:resourcetype>\
+                <c:supported-calendar-component-set><c:comp name=\"VTODO\"/></c:supported-calendar-component-set>\
+                </d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>\
+                <d:response><d:href>/dav/calendars/ann/holidays/</d:href><d:propstat><d:prop>\
+                <d:resourcetype><d:collection/><c:calendar/></d:resourcetype>\
+                <d:current-user-privilege-set><d:privilege><d:read/></d:privilege></d:current-user-privilege-set>\
+                </d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>",
+            ),
+        ])
+        .await;
+        let mut service = CalDavCalendarService::new(&account, "secret".to_string()).unwrap();
+
+        let calendars = service.fetch_calendars().await.unwrap();
+        let calendars: Vec<_> = calendars
+            .iter()
+            .map(|calendar| {
+                (
+                    calendar.id.as_str(),
+                    calendar.name.as_str(),
+                    calendar.color.as_deref(),
+                    calendar.access_role.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            calendars,
+            [
+                ("/dav/calendars/ann/work/", "Work", Some("#FF8800"), "owner"),
+                ("/dav/calendars/ann/holidays/", "holidays", None, "reader"),
+            ]
+        );
+
+        let requests = requests.lock().unwrap();
+        let asked: Vec<_> = requests
+            .iter()
+            .map(|request| {
+                (
+                    request.method.as_str(),
+                    request.path.as_str(),
+                    request.header("depth"),
+                )
+            })
+            .collect();
+        assert_eq!(
+            asked,
+            [
+                ("PROPFIND", "/dav/", Some("0")),
+                ("PROPFIND", "/dav/principals/ann/", Some("0")),
+                ("PROPFIND", "/dav/calendars/ann/", Some("1")),
+            ]
+        );
+        assert!(requests[0]
+            .header("authorization")
+            .is_some_and(|auth| auth.starts_with("Basic ")));
+    }
+
+    #[tokio::test]
+    async fn reads_events_from_a_calendar_query() {
+        let (account, requests) = serve(vec![multistatus(&format!(
+            "<d:response><d:href>/dav/calendars/ann/work/standup.ics</d:href>\
+            <d:propstat><d:prop><d:getetag>\"1\"</d:getetag>\
+            <c:calendar-data><![CDATA[{RESOURCE}]]></c:calendar-data></d:prop>\
+            <d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>\
+            <d:response><d:href>/dav/calendars/ann/work/gone.ics</d:href>\
+            <d:propstat><d:prop><d:getetag/><c:calendar-data/></d:prop>\
+            <d:status>HTTP/1.1 404 Not Found</d:status></d:propstat></d:response>"
+        ))])
+        .await;
+        let mut service = CalDavCalendarService::new(&account, "secret".to_string()).unwrap();
+
+        let events = service
+            .fetch_all_events("/dav/calendars/ann/work/")
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id, "standup");
+        assert_eq!(events[0].summary, "Standup");
+        assert_eq!(
+            events[0].extra.get("href"),
+            Some(&Value::String(
+                "/dav/calendars/ann/work/standup.ics".to_string()
+            ))
+        );
+        assert_eq!(
+            events[0].extra.get("etag"),
+            Some(&Value::String("\"1\"".to_string()))
+        );
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests[0].method, "REPORT");
+        assert_eq!(requests[0].path, "/dav/calendars/ann/work/");
+        assert!(requests[0].body.contains("calendar-query"));
+    }
+
+    #[tokio::test]
+    async fn creates_only_new_resources() {
+        let (account, requests) = serve(vec![(
+            "201 Created",
+            vec![("ETag", "\"1\"")],
+            String::new(),
+        )])
+        .await;
+        let mut service = CalDavCalendarService::new(&account, "secret".to_string()).unwrap();
+        let mut event = standup();
+        event.extra.clear();
+
+        let created = service.create_event(&calendar(), &event).await.unwrap();
+        assert_eq!(created.id, "standup");
+        assert_eq!(
+            created.extra.get("etag"),
+            Some(&Value::String("\"1\"".to_string()))
+        );
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests[0].method, "PUT");
+        assert_eq!(requests[0].path, "/dav/calendars/ann/work/standup.ics");
+        assert_eq!(requests[0].header("if-none-match"), Some("*"));
+        assert!(requests[0].body.contains("UID:standup"));
+    }
+
+    #[tokio::test]
+    async fn updates_the_fetched_version() {
+        let (account, requests) = serve(vec![
+            ("200 OK", vec![("ETag", "\"1\"")], RESOURCE.to_string()),
+            ("204 No Content", vec![("ETag", "\"2\"")], String::new()),
+        ])
+        .await;
+        let mut service = CalDavCalendarService::new(&account, "secret".to_string()).unwrap();
+        let mut event = standup();
+        event.summary = "Daily".to_string();
+
+        let updated = service.update_event(&calendar(), &event).await.unwrap();
+        assert_eq!(
+            updated.extra.get("etag"),
+            Some(&Value::String("\"2\"".to_string()))
+        );
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests[1].method, "PUT");
+        assert_eq!(requests[1].header("if-match"), Some("\"1\""));
+        // Properties the app doesn't model are kept.
+        assert!(requests[1].body.contains("SUMMARY:Daily"));
+        assert!(requests[1].body.contains("X-CUSTOM:kept"));
+    }
+
+    #[tokio::test]
+    async fn changes_made_elsewhere_conflict() {
+        let (account, requests) = serve(vec![
+            ("200 OK", vec![("ETag", "\"2\"")], RESOURCE.to_string()),
+            ("412 Precondition Failed", vec![], String::new()),
+            ("412 Precondition Failed", vec![], String::new()),
+        ])
+        .await;
+        let mut service = CalDavCalendarService::new(&account, "secret".to_string()).unwrap();
+
+        let updated = service.update_event(&calendar(), &standup()).await;
+        assert!(matches!(updated, Err(Error::EventConflict(_))));
+        let deleted = service.delete_event(&calendar(), &standup()).await;
+        assert!(matches!(deleted, Err(Error::EventConflict(_))));
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests[2].method, "DELETE");
+        assert_eq!(requests[2].header("if-match"), Some("\"1\""));
+    }
+}
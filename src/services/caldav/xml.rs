@@ -0,0 +1,200 @@
+//! A minimal XML tree for reading WebDAV multistatus responses.
+//!
+//! Namespaces are dropped and elements are matched by local name, which is enough
+//! to tell apart the handful of DAV and CalDAV properties the service asks for.
+
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+
+use crate::{Error, Result};
+
+#[derive(Debug, Clone, Default)]
+pub struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub text: String,
+    pub children: Vec<Element>,
+}
+
+impl Element {
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    pub fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// One `<response>` of a multistatus body, with the properties the server found.
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub href: String,
+    /// The `<prop>` of the successful `<propstat>`, if any.
+    pub prop: Option<Element>,
+}
+
+impl Response {
+    pub fn prop(&self, name: &str) -> Option<&Element> {
+        self.prop.as_ref()?.child(name)
+    }
+
+    pub fn text(&self, name: &str) -> Option<String> {
+        self.prop(name)
+            .map(|element| element.text.trim().to_string())
+            .filter(|text| !text.is_empty())
+    }
+
+    /// The `<href>` nested in a property such as `current-user-principal`.
+    pub fn href_in(&self, name: &str) -> Option<String> {
+        self.prop(name)?
+            .child("href")
+            .map(|href| href.text.trim().to_string())
+    }
+}
+
+pub fn parse(input: &str) -> Result<Element> {
+    let mut reader = Reader::from_str(input);
+    reader.config_mut().trim_text(true);
+
+    let mut stack: Vec<Element> = vec![Element::default()];
+    loop {
+        match reader.read_event()? {
+            Event::Start(start) => stack.push(element(&start)?),
+            Event::Empty(empty) => {
+                let element = element(&empty)?;
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(element);
+                }
+            }
+            Event::Text(text) => {
+                if let Some(current) = stack.last_mut() {
+                    current.text.push_str(&text.unescape()?);
+                }
+            }
+            Event::CData(data) => {
+                if let Some(current) = stack.last_mut() {
+                    current.text.push_str(&data.decode()?);
+                }
+            }
+            Event::End(_) => {
+                let element = stack
+                    .pop()
+                    .filter(|_| !stack.is_empty())
+                    .ok_or_else(|| Error::Unknown("Unbalanced XML response".to_string()))?;
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(element);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    stack
+        .pop()
+        .and_then(|document| document.children.into_iter().next())
+        .ok_or_else(|| Error::Unknown("Empty XML response".to_string()))
+}
+
+fn element(start: &BytesStart) -> Result<Element> {
+    let mut attributes = vec![];
+    for attribute in start.attributes().flatten() {
+        attributes.push((
+            String::from_utf8_lossy(attribute.key.local_name().as_ref()).into_owned(),
+            attribute.unescape_value()?.into_owned(),
+        ));
+    }
+    Ok(Element {
+        name: String::from_utf8_lossy(start.local_name().as_ref()).into_owned(),
+        attributes,
+        ..Default::default()
+    })
+}
+
+/// Reads the responses of a `207 Multi-Status` body.
+pub fn multistatus(input: &str) -> Result<Vec<Response>> {
+    let root = parse(input)?;
+    Ok(root
+        .children("response")
+        .filter_map(|response| {
+            let href = response.child("href")?.text.trim().to_string();
+            let prop = response
+                .children("propstat")
+                .find(|propstat| {
+                    propstat
+                        .child("status")
+                        .map_or(true, |status| status.text.contains(" 200 "))
+                })
+                .and_then(|propstat| propstat.child("prop"))
+                .cloned();
+            Some(Response { href, prop })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_successful_propstat() {
+        let responses = multistatus(
+            r#"<?xml version="1.0"?>
+<D:multistatus xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:response>
+    <D:href>/cal/work/a.ics</D:href>
+    <D:propstat>
+      <D:prop><C:calendar-data/></D:prop>
+      <D:status>HTTP/1.1 404 Not Found</D:status>
+    </D:propstat>
+    <D:propstat>
+      <D:prop>
+        <D:getetag>"a&amp;1"</D:getetag>
+        <C:calendar-data>BEGIN:VCALENDAR&#13;
+END:VCALENDAR</C:calendar-data>
+      </D:prop>
+      <D:status>HTTP/1.1 200 OK</D:status>
+    </D:propstat>
+  </D:response>
+  <D:response>
+    <D:href>/cal/work/b.ics</D:href>
+  </D:response>
+</D:multistatus>"#,
+        )
+        .unwrap();
+
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0].href, "/cal/work/a.ics");
+        assert_eq!(responses[0].text("getetag").as_deref(), Some("\"a&1\""));
+        assert_eq!(
+            responses[0].text("calendar-data").as_deref(),
+            Some("BEGIN:VCALENDAR\r\nEND:VCALENDAR")
+        );
+        assert!(responses[1].prop.is_none());
+    }
+
+    #[test]
+    fn reads_nested_hrefs() {
+        let responses = multistatus(
+            r#"<multistatus xmlns="DAV:"><response><href>/</href><propstat><prop>
+            <current-user-principal><href> /principals/ann/ </href></current-user-principal>
+            </prop></propstat></response></multistatus>"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            responses[0].href_in("current-user-principal").as_deref(),
+            Some("/principals/ann/")
+        );
+    }
+}
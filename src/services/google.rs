@@ -1,14 +1,38 @@
+use std::collections::HashMap;
+
 use accounts::{models::Account, AccountsClient};
 use async_trait::async_trait;
 use gcal_rs::{GCalClient, OToken};
+use reqwest::{StatusCode, Url};
+use serde_json::Value;
+use time::{
+    format_description::well_known::Rfc3339, macros::format_description, Date, OffsetDateTime,
+};
+
+use crate::{
+    ical,
+    models::{Calendar, Event, EventStatus},
+    services::{
+        check_response,
+        google::models::{EventsResponse, SyncResponse},
+        readable_event, CalendarService, SyncChanges,
+    },
+    Error, Result,
+};
 
-use crate::{models::Calendar, services::CalendarService, Result};
+pub mod models;
+
+/// Base URL of the Calendar REST API, used for the endpoints `gcal_rs` doesn't cover
+/// (paginated event listing and event writes).
+const API_URL: &str = "https://www.googleapis.com/calendar/v3";
 
 #[derive(Clone)]
 pub struct GoogleCalendarService {
     account: Account,
     client: AccountsClient,
     google: GCalClient,
+    http: reqwest::Client,
+    access_token: String,
 }
 
 impl GoogleCalendarService {
@@ -17,22 +41,35 @@ impl GoogleCalendarService {
         let access_token = client.get_access_token(&account.id).await?;
         let refresh_token = client.get_refresh_token(&account.id).await?;
         let mut token = OToken::default();
-        token.access = access_token;
+        token.access = access_token.clone();
         token.refresh = (!refresh_token.is_empty()).then_some(refresh_token);
         Ok(GoogleCalendarService {
             account: account.clone(),
             client: client.clone(),
             google: GCalClient::new(token, None)?,
+            http: reqwest::Client::new(),
+            access_token,
         })
     }
 
     pub async fn refresh_access_token(&mut self) -> Result<()> {
         let access_token = self.client.get_access_token(&self.account.id).await?;
         let mut token = OToken::default();
-        token.access = access_token;
+        token.access = access_token.clone();
         self.google = GCalClient::new(token, None)?;
+        self.access_token = access_token;
         Ok(())
     }
+
+    /// Builds an API URL from unescaped path segments, so calendar and event ids
+    /// containing `#` or `@` are encoded properly.
+    fn endpoint(segments: &[&str]) -> Url {
+        let mut url = Url::parse(API_URL).expect("API_URL is a valid URL");
+        url.path_segments_mut()
+            .expect("API_URL can be a base")
+            .extend(segments);
+        url
+    }
 }
 
 #[async_trait]
@@ -49,4 +86,309 @@ impl CalendarService for GoogleCalendarService {
             .collect();
         Ok(calendars)
     }
+
+    async fn fetch_events(
+        &mut self,
+        calendar_id: &str,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+    ) -> Result<Vec<Event>> {
+        self.refresh_access_token().await?;
+
+        let url = Self::endpoint(&["calendars", calendar_id, "events"]);
+        let time_min = start.format(&Rfc3339)?;
+        let time_max = end.format(&Rfc3339)?;
+
+        let mut events = vec![];
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut request = self
+                .http
+                .get(url.clone())
+                .bearer_auth(&self.access_token)
+                .query(&[
+                    ("timeMin", time_min.as_str()),
+                    ("timeMax", time_max.as_str()),
+                    ("singleEvents", "true"),
+                    ("maxResults", "250"),
+                ]);
+            if let Some(page_token) = &page_token {
+                request = request.query(&[("pageToken", page_token)]);
+            }
+
+            let response = check_response(request.send().await?, "fetch events").await?;
+            let response: EventsResponse = response.json().await?;
+            events.extend(
+                response
+                    .items
+                    .into_iter()
+                    .filter_map(readable_event)
+                    .filter(|event| event.status != EventStatus::Cancelled)
+                    .map(|mut event| {
+                        event.calendar_id = calendar_id.to_string();
+                        event
+                    }),
+            );
+
+            match response.next_page_token {
+                Some(next_page_token) => page_token = Some(next_page_token),
+                None => break,
+            }
+        }
+        Ok(events)
+    }
+
+    async fn fetch_all_events(&mut self, calendar_id: &str) -> Result<Vec<Event>> {
+        self.refresh_access_token().await?;
+
+        let url = Self::endpoint(&["calendars", calendar_id, "events"]);
+        let mut items = vec![];
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut request = self
+                .http
+                .get(url.clone())
+                .bearer_auth(&self.access_token)
+                .query(&[("singleEvents", "false"), ("maxResults", "250")]);
+            if let Some(page_token) = &page_token {
+                request = request.query(&[("pageToken", page_token)]);
+            }
+
+            let response = check_response(request.send().await?, "fetch events").await?;
+            let response: EventsResponse = response.json().await?;
+            items.extend(response.items);
+
+            match response.next_page_token {
+                Some(next_page_token) => page_token = Some(next_page_token),
+                None => break,
+            }
+        }
+
+        // Exceptions to a series are listed as separate items pointing at their master:
+        // cancelled ones become EXDATEs of the master, modified ones overrides of it.
+        let mut exdates: HashMap<String, Vec<String>> = HashMap::new();
+        let mut events = vec![];
+        for item in items {
+            let original = item
+                .recurring_event_id
+                .clone()
+                .zip(item.original_start_time.clone());
+            let Some(mut event) = readable_event(item) else {
+                continue;
+            };
+            event.calendar_id = calendar_id.to_string();
+            match original {
+                Some((master_id, original_start)) => {
+                    let Some(original_start) = original_start_line(&original_start) else {
+                        continue;
+                    };
+                    if event.status == EventStatus::Cancelled {
+                        exdates
+                            .entry(master_id)
+                            .or_default()
+                            .push(format!("EXDATE{original_start}"));
+                    } else {
+                        event.id = master_id;
+                        event.extra.insert(
+                            "RECURRENCE-ID".to_string(),
+                            Value::String(format!("RECURRENCE-ID{original_start}")),
+                        );
+                        events.push(event);
+                    }
+                }
+                None if event.status != EventStatus::Cancelled => events.push(event),
+                None => {}
+            }
+        }
+        for event in &mut events {
+            if !event.extra.contains_key("RECURRENCE-ID") {
+                if let Some(exdates) = exdates.remove(&event.id) {
+                    event.recurrence.extend(exdates);
+                }
+            }
+        }
+        Ok(events)
+    }
+
+    async fn fetch_event(&mut self, calendar_id: &str, event_id: &str) -> Result<Event> {
+        self.refresh_access_token().await?;
+
+        let response = self
+            .http
+            .get(Self::endpoint(&[
+                "calendars",
+                calendar_id,
+                "events",
+                event_id,
+            ]))
+            .bearer_auth(&self.access_token)
+            .send()
+            .await?;
+        let response = check_response(response, "fetch event").await?;
+        let mut event: Event = response.json::<models::Event>().await?.try_into()?;
+        event.calendar_id = calendar_id.to_string();
+        Ok(event)
+    }
+
+    async fn sync_events(
+        &mut self,
+        calendar_id: &str,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+        token: Option<&str>,
+    ) -> Result<Option<SyncChanges>> {
+        self.refresh_access_token().await?;
+
+        let url = Self::endpoint(&["calendars", calendar_id, "events"]);
+        let time_min = start.format(&Rfc3339)?;
+        let time_max = end.format(&Rfc3339)?;
+
+        let mut changes = SyncChanges {
+            full: token.is_none(),
+            ..Default::default()
+        };
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut request = self
+                .http
+                .get(url.clone())
+                .bearer_auth(&self.access_token)
+                .query(&[("singleEvents", "true"), ("maxResults", "250")]);
+            // The sync token remembers the time range of the full sync that issued it,
+            // and Google refuses to have it repeated.
+            request = match token {
+                Some(token) => request.query(&[("syncToken", token)]),
+                None => request.query(&[
+                    ("timeMin", time_min.as_str()),
+                    ("timeMax", time_max.as_str()),
+                ]),
+            };
+            if let Some(page_token) = &page_token {
+                request = request.query(&[("pageToken", page_token)]);
+            }
+
+            let response = request.send().await?;
+            if response.status() == StatusCode::GONE {
+                return Err(Error::SyncTokenExpired);
+            }
+            let response = check_response(response, "sync events").await?;
+            let response: SyncResponse = response.json().await?;
+            for item in response.items {
+                if item.get("status").and_then(Value::as_str) == Some("cancelled") {
+                    if let Some(id) = item.get("id").and_then(Value::as_str) {
+                        changes.removed.push(id.to_string());
+                    }
+                    continue;
+                }
+                let Some(mut event) =
+                    readable_event(serde_json::from_value::<models::Event>(item)?)
+                else {
+                    continue;
+                };
+                event.calendar_id = calendar_id.to_string();
+                changes.changed.push(event);
+            }
+
+            match (response.next_page_token, response.next_sync_token) {
+                (Some(next_page_token), _) => page_token = Some(next_page_token),
+                (None, Some(next_sync_token)) => {
+                    changes.token = next_sync_token;
+                    return Ok(Some(changes));
+                }
+                (None, None) => {
+                    return Err(Error::Unknown(
+                        "Google returned no sync token for the last page".to_string(),
+                    ))
+                }
+            }
+        }
+    }
+
+    async fn create_event(&mut self, calendar: &Calendar, event: &Event) -> Result<Event> {
+        calendar.check_editable()?;
+        self.refresh_access_token().await?;
+
+        let body = models::Event::try_from(event)?;
+        let response = self
+            .http
+            .post(Self::endpoint(&["calendars", &calendar.id, "events"]))
+            .bearer_auth(&self.access_token)
+            .json(&body)
+            .send()
+            .await?;
+        let response = check_response(response, "create event").await?;
+        let mut created: Event = response.json::<models::Event>().await?.try_into()?;
+        created.calendar_id = calendar.id.clone();
+        Ok(created)
+    }
+
+    async fn update_event(&mut self, calendar: &Calendar, event: &Event) -> Result<Event> {
+        calendar.check_editable()?;
+        self.refresh_access_token().await?;
+
+        let body = models::Event::try_from(event)?;
+        let request = self
+            .http
+            .patch(Self::endpoint(&[
+                "calendars",
+                &calendar.id,
+                "events",
+                &event.id,
+            ]))
+            .bearer_auth(&self.access_token)
+            .json(&body);
+        let response = if_match(request, event).send().await?;
+        if response.status() == StatusCode::PRECONDITION_FAILED {
+            return Err(Error::EventConflict(event.summary.clone()));
+        }
+        let response = check_response(response, "update event").await?;
+        let mut updated: Event = response.json::<models::Event>().await?.try_into()?;
+        updated.calendar_id = calendar.id.clone();
+        Ok(updated)
+    }
+
+    async fn delete_event(&mut self, calendar: &Calendar, event: &Event) -> Result<()> {
+        calendar.check_editable()?;
+        self.refresh_access_token().await?;
+
+        let request = self
+            .http
+            .delete(Self::endpoint(&[
+                "calendars",
+                &calendar.id,
+                "events",
+                &event.id,
+            ]))
+            .bearer_auth(&self.access_token);
+        let response = if_match(request, event).send().await?;
+        // Google answers 410 Gone for events that were already deleted.
+        if response.status() == StatusCode::GONE {
+            return Ok(());
+        }
+        if response.status() == StatusCode::PRECONDITION_FAILED {
+            return Err(Error::EventConflict(event.summary.clone()));
+        }
+        check_response(response, "delete event").await?;
+        Ok(())
+    }
+}
+
+/// The value of a `RECURRENCE-ID` or `EXDATE` line, parameters included,
+/// for the original start of an exception to a series.
+fn original_start_line(original_start: &models::EventDateTime) -> Option<String> {
+    if let Some(date) = &original_start.date {
+        let date = Date::parse(date, format_description!("[year]-[month]-[day]")).ok()?;
+        return Some(format!(";VALUE=DATE:{}", ical::format_date(date)));
+    }
+    let date_time = OffsetDateTime::parse(original_start.date_time.as_deref()?, &Rfc3339).ok()?;
+    Some(format!(":{}", ical::format_utc(date_time)))
+}
+
+/// Makes a write conditional on the event being unchanged since it was fetched,
+/// so edits made elsewhere in the meantime aren't overwritten.
+fn if_match(request: reqwest::RequestBuilder, event: &Event) -> reqwest::RequestBuilder {
+    match event.extra.get("etag") {
+        Some(Value::String(etag)) => request.header(reqwest::header::IF_MATCH, etag),
+        _ => request,
+    }
 }
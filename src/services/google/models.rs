@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EventsResponse {
+    #[serde(rename = "items", default)]
+    pub items: Vec<Event>,
+    #[serde(rename = "nextPageToken")]
+    pub next_page_token: Option<String>,
+    #[serde(rename = "nextSyncToken")]
+    pub next_sync_token: Option<String>,
+    #[serde(rename = "timeZone")]
+    pub time_zone: Option<String>,
+}
+
+/// A page of `events.list` in incremental sync, where deleted events come as bare
+/// items with a `cancelled` status, so items are only parsed once known to be live.
+#[derive(Debug, Deserialize)]
+pub struct SyncResponse {
+    #[serde(rename = "items", default)]
+    pub items: Vec<serde_json::Value>,
+    #[serde(rename = "nextPageToken")]
+    pub next_page_token: Option<String>,
+    #[serde(rename = "nextSyncToken")]
+    pub next_sync_token: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Event {
+    #[serde(rename = "id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(rename = "etag", skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(rename = "status", skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(rename = "htmlLink", skip_serializing_if = "Option::is_none")]
+    pub html_link: Option<String>,
+    #[serde(rename = "iCalUID", skip_serializing_if = "Option::is_none")]
+    pub ical_uid: Option<String>,
+    #[serde(rename = "summary", skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(rename = "description", skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "location", skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    #[serde(rename = "colorId", skip_serializing_if = "Option::is_none")]
+    pub color_id: Option<String>,
+    #[serde(rename = "start")]
+    pub start: EventDateTime,
+    #[serde(rename = "end")]
+    pub end: EventDateTime,
+    #[serde(rename = "recurrence", skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<Vec<String>>,
+    #[serde(rename = "recurringEventId", skip_serializing_if = "Option::is_none")]
+    pub recurring_event_id: Option<String>,
+    #[serde(rename = "originalStartTime", skip_serializing_if = "Option::is_none")]
+    pub original_start_time: Option<EventDateTime>,
+    #[serde(rename = "attendees", skip_serializing_if = "Option::is_none")]
+    pub attendees: Option<Vec<Attendee>>,
+    #[serde(rename = "organizer", skip_serializing_if = "Option::is_none")]
+    pub organizer: Option<Person>,
+    #[serde(rename = "reminders", skip_serializing_if = "Option::is_none")]
+    pub reminders: Option<Reminders>,
+    #[serde(rename = "transparency", skip_serializing_if = "Option::is_none")]
+    pub transparency: Option<String>,
+    #[serde(rename = "visibility", skip_serializing_if = "Option::is_none")]
+    pub visibility: Option<String>,
+    #[serde(rename = "hangoutLink", skip_serializing_if = "Option::is_none")]
+    pub hangout_link: Option<String>,
+    #[serde(rename = "sequence", skip_serializing_if = "Option::is_none")]
+    pub sequence: Option<i64>,
+    #[serde(rename = "created", skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[serde(rename = "updated", skip_serializing_if = "Option::is_none")]
+    pub updated: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct EventDateTime {
+    #[serde(rename = "date", skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    #[serde(rename = "dateTime", skip_serializing_if = "Option::is_none")]
+    pub date_time: Option<String>,
+    #[serde(rename = "timeZone", skip_serializing_if = "Option::is_none")]
+    pub time_zone: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Attendee {
+    #[serde(rename = "email")]
+    pub email: String,
+    #[serde(rename = "displayName", skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(rename = "responseStatus", skip_serializing_if = "Option::is_none")]
+    pub response_status: Option<String>,
+    #[serde(rename = "optional", skip_serializing_if = "Option::is_none")]
+    pub optional: Option<bool>,
+    #[serde(rename = "organizer", skip_serializing_if = "Option::is_none")]
+    pub organizer: Option<bool>,
+    #[serde(rename = "self", skip_serializing_if = "Option::is_none")]
+    pub is_self: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Person {
+    #[serde(rename = "email", skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(rename = "displayName", skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(rename = "self", skip_serializing_if = "Option::is_none")]
+    pub is_self: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Reminders {
+    #[serde(rename = "useDefault")]
+    pub use_default: bool,
+    #[serde(rename = "overrides", skip_serializing_if = "Option::is_none")]
+    pub overrides: Option<Vec<ReminderOverride>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReminderOverride {
+    #[serde(rename = "method")]
+    pub method: String,
+    #[serde(rename = "minutes")]
+    pub minutes: i64,
+}
@@ -0,0 +1,205 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use async_trait::async_trait;
+use cosmic::Application;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use time_tz::TimeZone;
+use tokio::sync::Mutex;
+
+use crate::{
+    app::AppModel,
+    fl, ical,
+    models::{zone, Calendar, Event, Provider},
+    services::CalendarService,
+    Error, Result,
+};
+
+/// Name of the file holding the local calendars, inside the app's data directory.
+const STORE_FILE: &str = "local-calendars.json";
+
+/// Color of the calendar created on first run.
+const DEFAULT_COLOR: &str = "#3584e4";
+
+/// Serializes reads and writes of the store, since every task opens its own service.
+static STORE_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// Everything kept on disk by the local service.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Store {
+    calendars: Vec<Calendar>,
+    events: Vec<Event>,
+}
+
+/// A calendar service keeping calendars and events in a JSON file under the XDG data
+/// directory, so the app works offline and without any online account.
+#[derive(Clone)]
+pub struct LocalCalendarService {
+    path: PathBuf,
+}
+
+impl LocalCalendarService {
+    pub fn new() -> Result<Self> {
+        let data_dir = dirs::data_dir()
+            .ok_or_else(|| Error::Unknown("No data directory available".to_string()))?;
+        Ok(LocalCalendarService {
+            path: data_dir.join(AppModel::APP_ID).join(STORE_FILE),
+        })
+    }
+
+    async fn load(&self) -> Result<Store> {
+        match tokio::fs::read(&self.path).await {
+            Ok(data) => Ok(serde_json::from_slice(&data)?),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Store::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Writes the store to a temporary file first, so a crash never leaves it half written.
+    async fn save(&self, store: &Store) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let temp = self.path.with_extension("json.tmp");
+        tokio::fs::write(&temp, serde_json::to_vec_pretty(store)?).await?;
+        tokio::fs::rename(&temp, &self.path).await?;
+        Ok(())
+    }
+
+    fn default_calendar() -> Calendar {
+        Calendar {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: fl!("personal"),
+            description: None,
+            timezone: Some(zone::system().name().to_string()),
+            color: Some(DEFAULT_COLOR.to_string()),
+            access_role: "owner".to_string(),
+            provider: Provider::Local,
+            extra: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl CalendarService for LocalCalendarService {
+    async fn fetch_calendars(&mut self) -> Result<Vec<Calendar>> {
+        let _lock = STORE_LOCK.lock().await;
+        let mut store = self.load().await?;
+        if store.calendars.is_empty() {
+            store.calendars.push(Self::default_calendar());
+            self.save(&store).await?;
+        }
+        Ok(store.calendars)
+    }
+
+    async fn fetch_events(
+        &mut self,
+        calendar_id: &str,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+    ) -> Result<Vec<Event>> {
+        let _lock = STORE_LOCK.lock().await;
+        let store = self.load().await?;
+        let events = store
+            .events
+            .into_iter()
+            .filter(|event| event.calendar_id == calendar_id)
+            .collect();
+        Ok(ical::recurrence::expand(events, start, end))
+    }
+
+    async fn fetch_all_events(&mut self, calendar_id: &str) -> Result<Vec<Event>> {
+        let _lock = STORE_LOCK.lock().await;
+        let store = self.load().await?;
+        Ok(store
+            .events
+            .into_iter()
+            .filter(|event| event.calendar_id == calendar_id)
+            .collect())
+    }
+
+    async fn fetch_event(&mut self, calendar_id: &str, event_id: &str) -> Result<Event> {
+        let _lock = STORE_LOCK.lock().await;
+        let store = self.load().await?;
+        store
+            .events
+            .into_iter()
+            .find(|e| e.id == event_id && e.calendar_id == calendar_id)
+            .ok_or_else(|| Error::Unknown(format!("Event {} not found", event_id)))
+    }
+
+    async fn create_event(&mut self, calendar: &Calendar, event: &Event) -> Result<Event> {
+        calendar.check_editable()?;
+        let _lock = STORE_LOCK.lock().await;
+        let mut store = self.load().await?;
+
+        let mut created = event.clone();
+        if created.id.is_empty() {
+            created.id = uuid::Uuid::new_v4().to_string();
+        }
+        created.calendar_id = calendar.id.clone();
+        created.provider = Provider::Local;
+        // Importing a file again replaces the events it brought in the first time.
+        store
+            .events
+            .retain(|e| !(e.id == created.id && e.calendar_id == created.calendar_id));
+        store.events.push(created.clone());
+
+        self.save(&store).await?;
+        Ok(created)
+    }
+
+    async fn update_event(&mut self, calendar: &Calendar, event: &Event) -> Result<Event> {
+        calendar.check_editable()?;
+        let _lock = STORE_LOCK.lock().await;
+        let mut store = self.load().await?;
+
+        let stored = store
+            .events
+            .iter_mut()
+            .find(|e| e.id == event.id && e.calendar_id == calendar.id)
+            .ok_or_else(|| Error::Unknown(format!("Event {} not found", event.id)))?;
+        *stored = event.clone();
+
+        self.save(&store).await?;
+        Ok(event.clone())
+    }
+
+    async fn delete_event(&mut self, calendar: &Calendar, event: &Event) -> Result<()> {
+        calendar.check_editable()?;
+        let _lock = STORE_LOCK.lock().await;
+        let mut store = self.load().await?;
+
+        store
+            .events
+            .retain(|e| e.id != event.id || e.calendar_id != calendar.id);
+
+        self.save(&store).await
+    }
+
+    /// The store can't hold overrides of a series, so a changed occurrence is left out
+    /// of it and saved as an event of its own, like overrides are on import.
+    async fn update_instance(
+        &mut self,
+        calendar: &Calendar,
+        occurrence: &Event,
+        changed: &Event,
+    ) -> Result<()> {
+        let mut series = self.fetch_series(calendar, occurrence).await?;
+        ical::recurrence::exclude(&mut series, occurrence.original_start());
+        self.update_event(calendar, &series).await?;
+
+        let mut detached = changed.clone();
+        detached.extra.remove("recurringEventId");
+        detached.extra.remove("RECURRENCE-ID");
+        self.create_event(calendar, &detached).await?;
+        Ok(())
+    }
+
+    async fn delete_instance(&mut self, calendar: &Calendar, occurrence: &Event) -> Result<()> {
+        let mut series = self.fetch_series(calendar, occurrence).await?;
+        ical::recurrence::exclude(&mut series, occurrence.original_start());
+        self.update_event(calendar, &series).await?;
+        Ok(())
+    }
+}
@@ -1,20 +1,42 @@
 use accounts::{models::Account, AccountsClient};
 use async_trait::async_trait;
 use graph_rs_sdk::GraphClient;
+use reqwest::{StatusCode, Url};
+use serde_json::Value;
+use time::{
+    format_description::well_known::Rfc3339, macros::format_description, Date, OffsetDateTime,
+};
 
 use crate::{
-    models::Calendar,
-    services::{microsoft::models::CalendarsResponse, CalendarService},
+    ical::recurrence,
+    models::{zone, Calendar, Event, EventStatus, EventTime},
+    services::{
+        check_response,
+        microsoft::models::{CalendarsResponse, DeltaResponse, EventsResponse},
+        readable_event, CalendarService, SyncChanges,
+    },
     Error, Result,
 };
 
 pub mod models;
 
+/// Base URL of the Graph REST API, used for the endpoints that need paging
+/// through `@odata.nextLink` and for event writes.
+const API_URL: &str = "https://graph.microsoft.com/v1.0";
+
+/// Asks Graph to report event times in UTC rather than in the mailbox's Windows time zone.
+const PREFER_UTC: &str = "outlook.timezone=\"UTC\"";
+
+/// Page size of delta queries, which ignore `$top`.
+const PREFER_PAGE_SIZE: &str = "odata.maxpagesize=100";
+
 #[derive(Debug, Clone)]
 pub struct MicrosoftCalendarService {
     account: Account,
     client: AccountsClient,
     graph: GraphClient,
+    http: reqwest::Client,
+    access_token: String,
 }
 
 impl MicrosoftCalendarService {
@@ -25,15 +47,27 @@ impl MicrosoftCalendarService {
         Ok(MicrosoftCalendarService {
             account: account.clone(),
             client: client.clone(),
-            graph: GraphClient::new(access_token),
+            graph: GraphClient::new(access_token.clone()),
+            http: reqwest::Client::new(),
+            access_token,
         })
     }
 
     pub async fn refresh_access_token(&mut self) -> Result<()> {
         let token = self.client.get_access_token(&self.account.id).await?;
-        self.graph = GraphClient::new(token);
+        self.graph = GraphClient::new(token.clone());
+        self.access_token = token;
         Ok(())
     }
+
+    /// Builds an API URL from unescaped path segments.
+    fn endpoint(segments: &[&str]) -> Url {
+        let mut url = Url::parse(API_URL).expect("API_URL is a valid URL");
+        url.path_segments_mut()
+            .expect("API_URL can be a base")
+            .extend(segments);
+        url
+    }
 }
 
 #[async_trait]
@@ -51,4 +85,333 @@ impl CalendarService for MicrosoftCalendarService {
         let response: CalendarsResponse = response.json().await.unwrap();
         Ok(response.value.into_iter().map(Into::into).collect())
     }
+
+    async fn fetch_events(
+        &mut self,
+        calendar_id: &str,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+    ) -> Result<Vec<Event>> {
+        self.refresh_access_token().await?;
+
+        let mut url = Self::endpoint(&["me", "calendars", calendar_id, "calendarView"]);
+        url.query_pairs_mut()
+            .append_pair("startDateTime", &start.format(&Rfc3339)?)
+            .append_pair("endDateTime", &end.format(&Rfc3339)?)
+            .append_pair("$top", "100");
+
+        let mut events = vec![];
+        let mut next_link = Some(url.to_string());
+        while let Some(link) = next_link {
+            let response = self
+                .http
+                .get(link)
+                .bearer_auth(&self.access_token)
+                .header("Prefer", PREFER_UTC)
+                .send()
+                .await?;
+            let response = check_response(response, "fetch events").await?;
+            let response: EventsResponse = response.json().await?;
+            events.extend(
+                response
+                    .value
+                    .into_iter()
+                    .filter_map(readable_event)
+                    .filter(|event| event.status != EventStatus::Cancelled)
+                    .map(|mut event| {
+                        event.calendar_id = calendar_id.to_string();
+                        event
+                    }),
+            );
+            next_link = response.odata_next_link;
+        }
+        Ok(events)
+    }
+
+    async fn fetch_all_events(&mut self, calendar_id: &str) -> Result<Vec<Event>> {
+        self.refresh_access_token().await?;
+
+        // Unlike calendarView, the events collection lists series masters instead of
+        // their occurrences.
+        let mut url = Self::endpoint(&["me", "calendars", calendar_id, "events"]);
+        url.query_pairs_mut().append_pair("$top", "100");
+
+        let mut events = vec![];
+        let mut next_link = Some(url.to_string());
+        while let Some(link) = next_link {
+            let response = self
+                .http
+                .get(link)
+                .bearer_auth(&self.access_token)
+                .header("Prefer", PREFER_UTC)
+                .send()
+                .await?;
+            let response = check_response(response, "fetch events").await?;
+            let response: EventsResponse = response.json().await?;
+            events.extend(
+                response
+                    .value
+                    .into_iter()
+                    .filter_map(readable_event)
+                    .filter(|event| event.status != EventStatus::Cancelled)
+                    .map(|mut event| {
+                        event.calendar_id = calendar_id.to_string();
+                        event
+                    }),
+            );
+            next_link = response.odata_next_link;
+        }
+        Ok(events)
+    }
+
+    async fn fetch_event(&mut self, calendar_id: &str, event_id: &str) -> Result<Event> {
+        self.refresh_access_token().await?;
+
+        let response = self
+            .http
+            .get(Self::endpoint(&["me", "events", event_id]))
+            .bearer_auth(&self.access_token)
+            .header("Prefer", PREFER_UTC)
+            .send()
+            .await?;
+        let response = check_response(response, "fetch event").await?;
+        let mut event: Event = response.json::<models::Event>().await?.try_into()?;
+        event.calendar_id = calendar_id.to_string();
+        Ok(event)
+    }
+
+    async fn sync_events(
+        &mut self,
+        calendar_id: &str,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+        token: Option<&str>,
+    ) -> Result<Option<SyncChanges>> {
+        self.refresh_access_token().await?;
+
+        // The delta link is the whole URL of the next query, range included.
+        let mut next_link = match token {
+            Some(delta_link) => delta_link.to_string(),
+            None => {
+                let mut url =
+                    Self::endpoint(&["me", "calendars", calendar_id, "calendarView", "delta"]);
+                url.query_pairs_mut()
+                    .append_pair("startDateTime", &start.format(&Rfc3339)?)
+                    .append_pair("endDateTime", &end.format(&Rfc3339)?);
+                url.to_string()
+            }
+        };
+
+        let mut changes = SyncChanges {
+            full: token.is_none(),
+            ..Default::default()
+        };
+        loop {
+            let response = self
+                .http
+                .get(&next_link)
+                .bearer_auth(&self.access_token)
+                .header("Prefer", PREFER_UTC)
+                .header("Prefer", PREFER_PAGE_SIZE)
+                .send()
+                .await?;
+            if response.status() == StatusCode::GONE {
+                return Err(Error::SyncTokenExpired);
+            }
+            let response = check_response(response, "sync events").await?;
+            let response: DeltaResponse = response.json().await?;
+            for item in response.value {
+                if item.get("@removed").is_some() {
+                    if let Some(id) = item.get("id").and_then(|id| id.as_str()) {
+                        changes.removed.push(id.to_string());
+                    }
+                    continue;
+                }
+                let Some(mut event) =
+                    readable_event(serde_json::from_value::<models::Event>(item)?)
+                else {
+                    continue;
+                };
+                event.calendar_id = calendar_id.to_string();
+                if event.status == EventStatus::Cancelled {
+                    changes.removed.push(event.id);
+                } else {
+                    changes.changed.push(event);
+                }
+            }
+
+            match (response.odata_next_link, response.odata_delta_link) {
+                (Some(link), _) => next_link = link,
+                (None, Some(delta_link)) => {
+                    changes.token = delta_link;
+                    return Ok(Some(changes));
+                }
+                (None, None) => {
+                    return Err(Error::Unknown(
+                        "Graph returned no delta link for the last page".to_string(),
+                    ))
+                }
+            }
+        }
+    }
+
+    async fn create_event(&mut self, calendar: &Calendar, event: &Event) -> Result<Event> {
+        calendar.check_editable()?;
+        self.refresh_access_token().await?;
+
+        let body = models::Event::try_from(event)?;
+        let response = self
+            .http
+            .post(Self::endpoint(&["me", "calendars", &calendar.id, "events"]))
+            .bearer_auth(&self.access_token)
+            .header("Prefer", PREFER_UTC)
+            .json(&body)
+            .send()
+            .await?;
+        let response = check_response(response, "create event").await?;
+        let mut created: Event = response.json::<models::Event>().await?.try_into()?;
+        created.calendar_id = calendar.id.clone();
+        Ok(created)
+    }
+
+    async fn update_event(&mut self, calendar: &Calendar, event: &Event) -> Result<Event> {
+        calendar.check_editable()?;
+        self.refresh_access_token().await?;
+
+        let body = models::Event::try_from(event)?;
+        let request = self
+            .http
+            .patch(Self::endpoint(&["me", "events", &event.id]))
+            .bearer_auth(&self.access_token)
+            .header("Prefer", PREFER_UTC)
+            .json(&body);
+        let response = if_match(request, event).send().await?;
+        if response.status() == StatusCode::PRECONDITION_FAILED {
+            return Err(Error::EventConflict(event.summary.clone()));
+        }
+        let response = check_response(response, "update event").await?;
+        let mut updated: Event = response.json::<models::Event>().await?.try_into()?;
+        updated.calendar_id = calendar.id.clone();
+        Ok(updated)
+    }
+
+    async fn delete_event(&mut self, calendar: &Calendar, event: &Event) -> Result<()> {
+        calendar.check_editable()?;
+        self.refresh_access_token().await?;
+
+        let request = self
+            .http
+            .delete(Self::endpoint(&["me", "events", &event.id]))
+            .bearer_auth(&self.access_token);
+        let response = if_match(request, event).send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(());
+        }
+        if response.status() == StatusCode::PRECONDITION_FAILED {
+            return Err(Error::EventConflict(event.summary.clone()));
+        }
+        check_response(response, "delete event").await?;
+        Ok(())
+    }
+
+    /// Graph's patterns can't be rebuilt from the RRULE a series is shown with,
+    /// so the stored pattern is cut short instead and copied to the new series.
+    async fn split_series(
+        &mut self,
+        calendar: &Calendar,
+        series: &Event,
+        start: EventTime,
+        changed: Option<&Event>,
+    ) -> Result<()> {
+        calendar.check_editable()?;
+        self.refresh_access_token().await?;
+
+        let response = self
+            .http
+            .get(Self::endpoint(&["me", "events", &series.id]))
+            .bearer_auth(&self.access_token)
+            .header("Prefer", PREFER_UTC)
+            .send()
+            .await?;
+        let response = check_response(response, "fetch event").await?;
+        let stored: models::Event = response.json().await?;
+        let Some(patterned) = stored.recurrence else {
+            return Err(Error::Unknown(format!(
+                "{} is not a recurring series",
+                series.summary
+            )));
+        };
+        // Range dates are in the zone of the series, taken to be the system one
+        // when Graph reports a Windows zone name the time zone database doesn't know.
+        let zone = patterned
+            .range
+            .recurrence_time_zone
+            .as_deref()
+            .and_then(zone::named)
+            .unwrap_or_else(zone::system);
+
+        // The series is ended first, guarded by the etag it was shown with, so the
+        // continuation is only created once that went through.
+        let mut range = patterned.range.clone();
+        let last_day = start.date_in(zone).previous_day();
+        range.range_type = "endDate".to_string();
+        range.end_date = last_day.map(format_date).transpose()?;
+        range.number_of_occurrences = None;
+        let body = serde_json::json!({
+            "recurrence": models::PatternedRecurrence {
+                pattern: patterned.pattern.clone(),
+                range,
+            },
+        });
+        let request = self
+            .http
+            .patch(Self::endpoint(&["me", "events", &series.id]))
+            .bearer_auth(&self.access_token)
+            .json(&body);
+        let response = if_match(request, series).send().await?;
+        if response.status() == StatusCode::PRECONDITION_FAILED {
+            return Err(Error::EventConflict(series.summary.clone()));
+        }
+        check_response(response, "update event").await?;
+
+        if let Some(changed) = changed {
+            let (_, following) = recurrence::split(series, start)?;
+            let following = recurrence::apply_changes(&following, start, changed);
+            let mut range = patterned.range;
+            range.start_date = format_date(following.start.date_in(zone))?;
+            if let Some(count) = range.number_of_occurrences {
+                let before = recurrence::occurrences_before(series, start)?;
+                range.number_of_occurrences = Some(count.saturating_sub(before).max(1));
+            }
+            let mut body = models::Event::try_from(&following)?;
+            body.recurrence = Some(models::PatternedRecurrence {
+                pattern: patterned.pattern,
+                range,
+            });
+            let response = self
+                .http
+                .post(Self::endpoint(&["me", "calendars", &calendar.id, "events"]))
+                .bearer_auth(&self.access_token)
+                .header("Prefer", PREFER_UTC)
+                .json(&body)
+                .send()
+                .await?;
+            check_response(response, "create event").await?;
+        }
+        Ok(())
+    }
+}
+
+/// Makes a write conditional on the event being unchanged since it was fetched,
+/// by the `@odata.etag` Graph reported for it, so edits made elsewhere in the
+/// meantime aren't overwritten.
+fn if_match(request: reqwest::RequestBuilder, event: &Event) -> reqwest::RequestBuilder {
+    match event.extra.get("etag") {
+        Some(Value::String(etag)) => request.header(reqwest::header::IF_MATCH, etag),
+        _ => request,
+    }
+}
+
+fn format_date(date: Date) -> Result<String> {
+    Ok(date.format(format_description!("[year]-[month]-[day]"))?)
 }
@@ -48,3 +48,175 @@ pub struct Owner {
     #[serde(rename = "address")]
     pub address: String,
 }
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EventsResponse {
+    #[serde(rename = "value")]
+    pub value: Vec<Event>,
+    #[serde(rename = "@odata.nextLink")]
+    pub odata_next_link: Option<String>,
+}
+
+/// A page of a `calendarView/delta` query. Deleted events come as bare items with
+/// an `@removed` annotation, so items are only parsed once known to be live.
+#[derive(Debug, Deserialize)]
+pub struct DeltaResponse {
+    #[serde(rename = "value")]
+    pub value: Vec<serde_json::Value>,
+    #[serde(rename = "@odata.nextLink")]
+    pub odata_next_link: Option<String>,
+    #[serde(rename = "@odata.deltaLink")]
+    pub odata_delta_link: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Event {
+    #[serde(rename = "id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(rename = "@odata.etag", skip_serializing_if = "Option::is_none")]
+    pub odata_etag: Option<String>,
+    #[serde(rename = "changeKey", skip_serializing_if = "Option::is_none")]
+    pub change_key: Option<String>,
+    #[serde(rename = "iCalUId", skip_serializing_if = "Option::is_none")]
+    pub ical_uid: Option<String>,
+    #[serde(rename = "subject", skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    #[serde(rename = "body", skip_serializing_if = "Option::is_none")]
+    pub body: Option<ItemBody>,
+    #[serde(rename = "bodyPreview", skip_serializing_if = "Option::is_none")]
+    pub body_preview: Option<String>,
+    #[serde(rename = "location", skip_serializing_if = "Option::is_none")]
+    pub location: Option<Location>,
+    #[serde(rename = "start")]
+    pub start: DateTimeTimeZone,
+    #[serde(rename = "end")]
+    pub end: DateTimeTimeZone,
+    #[serde(rename = "isAllDay", default)]
+    pub is_all_day: bool,
+    #[serde(rename = "isCancelled", default, skip_serializing)]
+    pub is_cancelled: bool,
+    #[serde(rename = "showAs", skip_serializing_if = "Option::is_none")]
+    pub show_as: Option<String>,
+    #[serde(rename = "attendees", skip_serializing_if = "Option::is_none")]
+    pub attendees: Option<Vec<Attendee>>,
+    #[serde(rename = "organizer", skip_serializing_if = "Option::is_none")]
+    pub organizer: Option<Recipient>,
+    #[serde(rename = "recurrence", skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<PatternedRecurrence>,
+    #[serde(rename = "seriesMasterId", skip_serializing_if = "Option::is_none")]
+    pub series_master_id: Option<String>,
+    /// Where an occurrence was scheduled in its series before it was moved.
+    #[serde(rename = "originalStart", skip_serializing_if = "Option::is_none")]
+    pub original_start: Option<String>,
+    /// The zone the event was created in, an IANA or a Windows name.
+    #[serde(rename = "originalStartTimeZone", default, skip_serializing)]
+    pub original_start_time_zone: Option<String>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub event_type: Option<String>,
+    #[serde(rename = "isReminderOn", skip_serializing_if = "Option::is_none")]
+    pub is_reminder_on: Option<bool>,
+    #[serde(
+        rename = "reminderMinutesBeforeStart",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub reminder_minutes_before_start: Option<i64>,
+    #[serde(rename = "webLink", skip_serializing_if = "Option::is_none")]
+    pub web_link: Option<String>,
+    #[serde(rename = "onlineMeetingUrl", skip_serializing_if = "Option::is_none")]
+    pub online_meeting_url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ItemBody {
+    #[serde(rename = "contentType")]
+    pub content_type: String,
+    #[serde(rename = "content")]
+    pub content: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Location {
+    #[serde(rename = "displayName")]
+    pub display_name: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DateTimeTimeZone {
+    #[serde(rename = "dateTime")]
+    pub date_time: String,
+    #[serde(rename = "timeZone")]
+    pub time_zone: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Attendee {
+    #[serde(rename = "emailAddress")]
+    pub email_address: EmailAddress,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub attendee_type: Option<String>,
+    #[serde(rename = "status", skip_serializing_if = "Option::is_none")]
+    pub status: Option<ResponseStatus>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResponseStatus {
+    #[serde(rename = "response")]
+    pub response: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Recipient {
+    #[serde(rename = "emailAddress")]
+    pub email_address: EmailAddress,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmailAddress {
+    #[serde(rename = "name", skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(rename = "address", skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatternedRecurrence {
+    #[serde(rename = "pattern")]
+    pub pattern: RecurrencePattern,
+    #[serde(rename = "range")]
+    pub range: RecurrenceRange,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecurrencePattern {
+    #[serde(rename = "type")]
+    pub pattern_type: String,
+    #[serde(rename = "interval")]
+    pub interval: u32,
+    #[serde(rename = "month", default)]
+    pub month: u8,
+    #[serde(rename = "dayOfMonth", default)]
+    pub day_of_month: u8,
+    #[serde(rename = "daysOfWeek", default)]
+    pub days_of_week: Vec<String>,
+    #[serde(rename = "firstDayOfWeek", skip_serializing_if = "Option::is_none")]
+    pub first_day_of_week: Option<String>,
+    #[serde(rename = "index", skip_serializing_if = "Option::is_none")]
+    pub index: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecurrenceRange {
+    #[serde(rename = "type")]
+    pub range_type: String,
+    #[serde(rename = "startDate")]
+    pub start_date: String,
+    #[serde(rename = "endDate", skip_serializing_if = "Option::is_none")]
+    pub end_date: Option<String>,
+    #[serde(
+        rename = "numberOfOccurrences",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub number_of_occurrences: Option<u32>,
+    #[serde(rename = "recurrenceTimeZone", skip_serializing_if = "Option::is_none")]
+    pub recurrence_time_zone: Option<String>,
+}
@@ -0,0 +1,347 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use reqwest::{header, StatusCode, Url};
+use time::OffsetDateTime;
+use tokio::sync::Mutex;
+
+use crate::{
+    ical,
+    models::{Calendar, Event, Feed, Provider},
+    services::{check_response, CalendarService},
+    Error, Result,
+};
+
+/// How often the app polls calendars for changes, unless configured otherwise.
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+/// How long a downloaded feed is used as is, so loading calendars and events at the
+/// same time or navigating around doesn't hit the server every time.
+const REVALIDATE_AFTER: Duration = Duration::from_secs(60);
+
+/// The feeds downloaded so far, by URL. Every task opens its own service,
+/// so the copies and their validators are kept here to survive between them.
+static FEEDS: Mutex<BTreeMap<String, CachedFeed>> = Mutex::const_new(BTreeMap::new());
+
+#[derive(Debug, Clone)]
+struct CachedFeed {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    fetched: Instant,
+    calendar: Calendar,
+    events: Vec<Event>,
+}
+
+/// A read-only calendar service for a published `.ics` feed, downloaded with
+/// `ETag`/`Last-Modified` validation so unchanged feeds aren't transferred again.
+#[derive(Clone)]
+pub struct SubscriptionService {
+    url: Url,
+    http: reqwest::Client,
+}
+
+/// Parses a feed URL as entered by the user, turning `webcal://` into `https://`.
+pub fn feed_url(url: &str) -> Result<Url> {
+    let url = url.trim();
+    let url = match url.split_once("://") {
+        Some((scheme, rest))
+            if scheme.eq_ignore_ascii_case("webcal") || scheme.eq_ignore_ascii_case("webcals") =>
+        {
+            format!("https://{rest}")
+        }
+        _ => url.to_string(),
+    };
+    let url =
+        Url::parse(&url).map_err(|err| Error::Unknown(format!("Invalid feed URL {url}: {err}")))?;
+    match url.scheme() {
+        "http" | "https" => Ok(url),
+        scheme => Err(Error::Unknown(format!("Unsupported feed scheme {scheme}"))),
+    }
+}
+
+impl SubscriptionService {
+    pub fn new(feed: &Feed) -> Result<Self> {
+        Ok(SubscriptionService {
+            url: feed_url(&feed.url)?,
+            http: reqwest::Client::new(),
+        })
+    }
+
+    /// Returns the feed, downloading it again only when the server has a newer copy.
+    /// The copies are only locked to read and store them, so a slow server doesn't
+    /// hold up loading other feeds.
+    async fn load(&self) -> Result<CachedFeed> {
+        let (etag, last_modified) = {
+            let feeds = FEEDS.lock().await;
+            match feeds.get(self.url.as_str()) {
+                Some(cached) if cached.fetched.elapsed() < REVALIDATE_AFTER => {
+                    return Ok(cached.clone())
+                }
+                Some(cached) => (cached.etag.clone(), cached.last_modified.clone()),
+                None => (None, None),
+            }
+        };
+
+        let mut request = self.http.get(self.url.clone());
+        if let Some(etag) = &etag {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &last_modified {
+            request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+        }
+        let response = request.send().await?;
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            let mut feeds = FEEDS.lock().await;
+            let cached = feeds.get_mut(self.url.as_str()).ok_or_else(|| {
+                Error::Unknown(format!(
+                    "{} is not modified but was never fetched",
+                    self.url
+                ))
+            })?;
+            cached.fetched = Instant::now();
+            return Ok(cached.clone());
+        }
+
+        let response = check_response(response, "fetch subscribed calendar").await?;
+        let validator = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let etag = validator(header::ETAG);
+        let last_modified = validator(header::LAST_MODIFIED);
+        let data = response.text().await?;
+
+        let feed = CachedFeed {
+            etag,
+            last_modified,
+            fetched: Instant::now(),
+            calendar: self.calendar(&data)?,
+            events: self.events(&data)?,
+        };
+        FEEDS
+            .lock()
+            .await
+            .insert(self.url.to_string(), feed.clone());
+        Ok(feed)
+    }
+
+    /// The calendar the feed is shown as, named after its `X-WR-CALNAME` if it has one.
+    fn calendar(&self, data: &str) -> Result<Calendar> {
+        let components = ical::parse(data)?;
+        let vcalendar = components
+            .iter()
+            .find(|component| component.name.eq_ignore_ascii_case("VCALENDAR"))
+            .ok_or_else(|| Error::ICalendar(format!("{} is not an iCalendar feed", self.url)))?;
+        Ok(Calendar {
+            id: self.url.to_string(),
+            name: vcalendar
+                .text("X-WR-CALNAME")
+                .unwrap_or_else(|| self.url.host_str().unwrap_or_default().to_string()),
+            description: vcalendar.text("X-WR-CALDESC"),
+            timezone: vcalendar.text("X-WR-TIMEZONE"),
+            color: vcalendar
+                .text("X-APPLE-CALENDAR-COLOR")
+                .map(|color| color.chars().take(7).collect()),
+            access_role: "reader".to_string(),
+            provider: Provider::Subscription,
+            extra: HashMap::new(),
+        })
+    }
+
+    fn events(&self, data: &str) -> Result<Vec<Event>> {
+        let parsed = ical::read_events(data, self.url.as_str(), Provider::Subscription)?;
+        for skipped in &parsed.skipped {
+            if skipped.reason != ical::SkipReason::Cancelled {
+                tracing::warn!(
+                    "Skipping {} in {}: {}",
+                    skipped.label,
+                    self.url,
+                    skipped.reason
+                );
+            }
+        }
+        Ok(parsed.events)
+    }
+}
+
+#[async_trait]
+impl CalendarService for SubscriptionService {
+    async fn fetch_calendars(&mut self) -> Result<Vec<Calendar>> {
+        Ok(vec![self.load().await?.calendar])
+    }
+
+    async fn fetch_events(
+        &mut self,
+        _calendar_id: &str,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+    ) -> Result<Vec<Event>> {
+        let events = self.load().await?.events;
+        Ok(ical::recurrence::expand(events, start, end))
+    }
+
+    async fn fetch_all_events(&mut self, _calendar_id: &str) -> Result<Vec<Event>> {
+        Ok(self.load().await?.events)
+    }
+
+    async fn fetch_event(&mut self, _calendar_id: &str, event_id: &str) -> Result<Event> {
+        self.load()
+            .await?
+            .events
+            .into_iter()
+            .find(|event| event.id == event_id && !event.extra.contains_key("RECURRENCE-ID"))
+            .ok_or_else(|| Error::Unknown(format!("Event {event_id} not found")))
+    }
+
+    async fn create_event(&mut self, calendar: &Calendar, _event: &Event) -> Result<Event> {
+        Err(Error::ReadOnlyCalendar(calendar.name.clone()))
+    }
+
+    async fn update_event(&mut self, calendar: &Calendar, _event: &Event) -> Result<Event> {
+        Err(Error::ReadOnlyCalendar(calendar.name.clone()))
+    }
+
+    async fn delete_event(&mut self, calendar: &Calendar, _event: &Event) -> Result<()> {
+        Err(Error::ReadOnlyCalendar(calendar.name.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex as StdMutex};
+
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    const FEED: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+PRODID:-//Test//Feed//EN\r
+X-WR-CALNAME:Holidays\r
+BEGIN:VEVENT\r
+UID:new-year@example.com\r
+DTSTAMP:20240101T000000Z\r
+DTSTART;VALUE=DATE:20250101\r
+DTEND;VALUE=DATE:20250102\r
+SUMMARY:New Year\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+    /// Serves [`FEED`] with the `validator` header, answering 304 to requests that
+    /// send its value back in `condition`. Returns the feed and the requests received.
+    async fn serve(
+        validator: &'static str,
+        value: &'static str,
+        condition: &'static str,
+    ) -> (Feed, Arc<StdMutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/feed.ics", listener.local_addr().unwrap());
+        let requests = Arc::new(StdMutex::new(vec![]));
+
+        let received = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+                let mut request = vec![];
+                let mut buffer = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    match stream.read(&mut buffer).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(read) => request.extend_from_slice(&buffer[..read]),
+                    }
+                }
+                let request = String::from_utf8_lossy(&request).to_lowercase();
+                let unchanged = request.contains(&format!("{condition}: {value}").to_lowercase());
+                received.lock().unwrap().push(request);
+
+                let response = if unchanged {
+                    format!("HTTP/1.1 304 Not Modified\r\n{validator}: {value}\r\nConnection: close\r\n\r\n")
+                } else {
+                    format!(
+                        "HTTP/1.1 200 OK\r\n{validator}: {value}\r\nContent-Type: text/calendar\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{FEED}",
+                        FEED.len()
+                    )
+                };
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            }
+        });
+
+        (Feed { url }, requests)
+    }
+
+    /// Makes the copy of `feed` old enough to be revalidated on the next load.
+    async fn expire(feed: &Feed) {
+        let mut feeds = FEEDS.lock().await;
+        let cached = feeds.get_mut(&feed.url).unwrap();
+        cached.fetched = Instant::now().checked_sub(REVALIDATE_AFTER).unwrap();
+    }
+
+    async fn revalidates(validator: &'static str, value: &'static str, condition: &'static str) {
+        let (feed, requests) = serve(validator, value, condition).await;
+        let mut service = SubscriptionService::new(&feed).unwrap();
+
+        let calendars = service.fetch_calendars().await.unwrap();
+        assert_eq!(calendars[0].name, "Holidays");
+        assert_eq!(requests.lock().unwrap().len(), 1);
+        assert!(!requests.lock().unwrap()[0].contains(condition));
+
+        // A fresh copy is used without asking the server.
+        let events = service.fetch_all_events(&feed.url).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(requests.lock().unwrap().len(), 1);
+
+        // A stale one is revalidated, and kept when the server answers 304.
+        expire(&feed).await;
+        let events = service.fetch_all_events(&feed.url).await.unwrap();
+        assert_eq!(events[0].summary, "New Year");
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].contains(&format!("{condition}: {value}").to_lowercase()));
+    }
+
+    #[tokio::test]
+    async fn revalidates_with_etag() {
+        revalidates("ETag", "\"feed-1\"", "if-none-match").await;
+    }
+
+    #[tokio::test]
+    async fn revalidates_with_last_modified() {
+        revalidates(
+            "Last-Modified",
+            "Wed, 01 Jan 2025 00:00:00 GMT",
+            "if-modified-since",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn downloads_again_when_changed() {
+        let (feed, requests) = serve("ETag", "\"feed-2\"", "if-none-match").await;
+        let mut service = SubscriptionService::new(&feed).unwrap();
+        service.fetch_calendars().await.unwrap();
+
+        // The server no longer has the copy the app holds.
+        FEEDS.lock().await.get_mut(&feed.url).unwrap().etag = Some("\"feed-1\"".to_string());
+        expire(&feed).await;
+        let events = service.fetch_all_events(&feed.url).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(requests.lock().unwrap().len(), 2);
+
+        let feeds = FEEDS.lock().await;
+        assert_eq!(feeds[&feed.url].etag.as_deref(), Some("\"feed-2\""));
+    }
+}
@@ -0,0 +1,179 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Fetching of events into the cache. Google and Microsoft calendars are kept up to date
+//! through incremental sync over a window around today, so only what changed since the
+//! last sync is transferred; other sources and ranges outside the window are fetched
+//! in full, falling back to the cached copy when the source can't be reached.
+
+use time::{Duration, OffsetDateTime};
+
+use crate::{
+    cache::{self, SyncState},
+    models::{Event, Source},
+    outbox,
+    services::CalendarServiceFactory,
+    Error, Result,
+};
+
+/// How far before today incremental sync reaches.
+const SYNC_PAST: Duration = Duration::days(365);
+
+/// How far after today incremental sync reaches.
+const SYNC_FUTURE: Duration = Duration::days(2 * 365);
+
+/// Once less than this is left until the end of the window, a full sync moves it
+/// to be around today again.
+const SYNC_RENEW: Duration = Duration::days(365);
+
+/// The events of a calendar overlapping `[start, end)`, brought up to date with the
+/// source and cached, with the changes still waiting in the outbox applied.
+pub async fn fetch_events(
+    source: Source,
+    calendar_id: String,
+    start: OffsetDateTime,
+    end: OffsetDateTime,
+) -> Result<Vec<Event>> {
+    let events = fetch_from_source(source.clone(), calendar_id.clone(), start, end).await?;
+    Ok(outbox::overlay(&source, &calendar_id, start, end, events).await)
+}
+
+async fn fetch_from_source(
+    source: Source,
+    calendar_id: String,
+    start: OffsetDateTime,
+    end: OffsetDateTime,
+) -> Result<Vec<Event>> {
+    match sync_calendar(&source, &calendar_id, start, end).await {
+        Ok(true) => {
+            return cache::with(move |cache| cache.events(&source, &calendar_id, start, end)).await
+        }
+        Ok(false) => {}
+        Err(err) => tracing::warn!("Failed to sync calendar {calendar_id}: {err}"),
+    }
+
+    let fetched = async {
+        let mut service = CalendarServiceFactory::get_service(&source).await?;
+        service.fetch_events(&calendar_id, start, end).await
+    }
+    .await;
+    match fetched {
+        Ok(events) => {
+            let cached = events.clone();
+            if let Err(err) = cache::with(move |cache| {
+                cache.set_events(&source, &calendar_id, start, end, &cached)
+            })
+            .await
+            {
+                tracing::warn!("Failed to cache events: {}", err);
+            }
+            Ok(events)
+        }
+        Err(err) => {
+            tracing::warn!("Failed to fetch events, using cached ones: {}", err);
+            cache::with(move |cache| cache.events(&source, &calendar_id, start, end)).await
+        }
+    }
+}
+
+/// Applies what changed in a calendar since its last sync to the cache. Returns `false`
+/// when the source has no incremental sync or `[start, end)` isn't within its window.
+async fn sync_calendar(
+    source: &Source,
+    calendar_id: &str,
+    start: OffsetDateTime,
+    end: OffsetDateTime,
+) -> Result<bool> {
+    if !matches!(source, Source::Account(_)) {
+        return Ok(false);
+    }
+
+    let (state_source, state_id) = (source.clone(), calendar_id.to_string());
+    let state = cache::with(move |cache| cache.sync_state(&state_source, &state_id)).await?;
+    let now = OffsetDateTime::now_utc();
+    let mut state = window(state, now);
+    if start < state.start || end > state.end {
+        return Ok(false);
+    }
+
+    let mut service = CalendarServiceFactory::get_service(source).await?;
+    let token = (!state.token.is_empty()).then_some(state.token.as_str());
+    let synced = service
+        .sync_events(calendar_id, state.start, state.end, token)
+        .await;
+    let changes = match synced {
+        Err(Error::SyncTokenExpired) => {
+            tracing::info!("Sync token of calendar {calendar_id} expired, syncing it in full");
+            state = new_window(now);
+            service
+                .sync_events(calendar_id, state.start, state.end, None)
+                .await?
+        }
+        changes => changes?,
+    };
+    let Some(changes) = changes else {
+        return Ok(false);
+    };
+    state.token = changes.token.clone();
+    // A full sync after an expired token may have moved the window.
+    let covered = start >= state.start && end <= state.end;
+
+    let (source, calendar_id) = (source.clone(), calendar_id.to_string());
+    cache::with(move |cache| cache.apply_changes(&source, &calendar_id, &state, &changes)).await?;
+    Ok(covered)
+}
+
+/// The window to sync, which is the stored one unless it's running out.
+fn window(state: Option<SyncState>, now: OffsetDateTime) -> SyncState {
+    state
+        .filter(|state| state.end - now > SYNC_RENEW)
+        .unwrap_or_else(|| new_window(now))
+}
+
+/// The window a full sync started now covers, without a token yet.
+fn new_window(now: OffsetDateTime) -> SyncState {
+    SyncState {
+        token: String::new(),
+        start: now - SYNC_PAST,
+        end: now + SYNC_FUTURE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    const NOW: OffsetDateTime = datetime!(2024-03-04 12:00 UTC);
+
+    fn stored(end: OffsetDateTime) -> Option<SyncState> {
+        Some(SyncState {
+            token: "1".to_string(),
+            start: NOW - SYNC_PAST,
+            end,
+        })
+    }
+
+    #[test]
+    fn the_stored_window_is_kept_while_far_from_its_end() {
+        let state = window(stored(NOW + SYNC_RENEW + Duration::DAY), NOW);
+        assert_eq!(state.token, "1");
+        assert_eq!(state.end, NOW + SYNC_RENEW + Duration::DAY);
+    }
+
+    #[test]
+    fn a_window_running_out_is_renewed_around_today() {
+        let state = window(stored(NOW + SYNC_RENEW - Duration::DAY), NOW);
+        assert!(state.token.is_empty());
+        assert_eq!(state.start, NOW - SYNC_PAST);
+        assert_eq!(state.end, NOW + SYNC_FUTURE);
+    }
+
+    #[test]
+    fn the_first_sync_starts_a_window() {
+        let state = window(None, NOW);
+        assert!(state.token.is_empty());
+        assert_eq!(state.start, NOW - SYNC_PAST);
+        assert_eq!(state.end, NOW + SYNC_FUTURE);
+    }
+}